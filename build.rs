@@ -0,0 +1,119 @@
+use std::{
+    env,
+    fs::read_to_string,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use git2::{
+    Repository,
+    StatusOptions,
+};
+
+use serde_json::{
+    json,
+    Value,
+};
+
+use toml_edit::{
+    DocumentMut,
+    Item,
+};
+
+/// Records the versions of rem-cli and the rem-* crates it is built with in
+/// `REM_CRATE_VERSIONS`, as a JSON list of `tests::record::CrateVersion`s.
+/// Path dependencies are looked up in their checkouts, along with the commit
+/// each checkout is on and whether it has uncommitted changes. Doing this here
+/// rather than when a run is recorded means the versions are the ones that
+/// were built, wherever the binary is run from.
+fn main() {
+    let manifest_dir: PathBuf = PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("Cargo sets CARGO_MANIFEST_DIR"));
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    println!("cargo:rerun-if-changed=src");
+
+    let (sha, dirty) = git_state(&manifest_dir);
+    let mut crates: Vec<Value> = vec![json!({
+        "name": "rem-cli",
+        "version": env::var("CARGO_PKG_VERSION").ok(),
+        "sha": sha,
+        "dirty": dirty,
+    })];
+
+    let dependencies: Option<DocumentMut> = read_manifest(&manifest_dir.join("Cargo.toml"));
+    let dependencies = dependencies
+        .as_ref()
+        .and_then(|manifest| manifest.get("dependencies"))
+        .and_then(Item::as_table_like);
+    for (name, dependency) in dependencies.into_iter().flat_map(|dependencies| dependencies.iter()) {
+        if !name.starts_with("rem-") {
+            continue;
+        }
+        let krate: Value = match dependency.get("path").and_then(Item::as_str) {
+            Some(path) => {
+                let crate_dir: PathBuf = manifest_dir.join(path);
+                println!("cargo:rerun-if-changed={}", crate_dir.join("Cargo.toml").display());
+                println!("cargo:rerun-if-changed={}", crate_dir.join("src").display());
+                let (sha, dirty) = git_state(&crate_dir);
+                json!({
+                    "name": name,
+                    "version": package_version(&crate_dir),
+                    "sha": sha,
+                    "dirty": dirty,
+                })
+            }
+            None => json!({
+                "name": name,
+                "version": dependency.as_str().or_else(|| dependency.get("version").and_then(Item::as_str)),
+                "sha": null,
+                "dirty": false,
+            }),
+        };
+        crates.push(krate);
+    }
+
+    println!("cargo:rustc-env=REM_CRATE_VERSIONS={}", Value::Array(crates));
+}
+
+fn read_manifest(path: &Path) -> Option<DocumentMut> {
+    read_to_string(path).ok()?.parse::<DocumentMut>().ok()
+}
+
+/// The version in a package's manifest, which may be inherited from the
+/// workspace it is in.
+fn package_version(crate_dir: &Path) -> Option<String> {
+    let manifest: DocumentMut = read_manifest(&crate_dir.join("Cargo.toml"))?;
+    let version: &Item = manifest.get("package")?.get("version")?;
+    if let Some(version) = version.as_str() {
+        return Some(version.to_string());
+    }
+    crate_dir
+        .ancestors()
+        .skip(1)
+        .filter_map(|dir| read_manifest(&dir.join("Cargo.toml")))
+        .find_map(|workspace| {
+            workspace.get("workspace")?.get("package")?.get("version")?.as_str().map(str::to_string)
+        })
+}
+
+/// The commit the repository `dir` is in is on, and whether any tracked file
+/// has uncommitted changes. The build script is rerun when the repository's
+/// HEAD or index changes, so that a new commit is picked up.
+fn git_state(dir: &Path) -> (Option<String>, bool) {
+    let Ok(repository) = Repository::discover(dir) else {
+        return (None, false);
+    };
+    println!("cargo:rerun-if-changed={}", repository.path().join("HEAD").display());
+    println!("cargo:rerun-if-changed={}", repository.path().join("index").display());
+    let sha: Option<String> = repository
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map(|commit| commit.id().to_string())
+        .ok();
+    let mut options: StatusOptions = StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    let dirty: bool = repository.statuses(Some(&mut options)).is_ok_and(|statuses| !statuses.is_empty());
+    (sha, dirty)
+}
@@ -1,13 +1,13 @@
-fn foo() {
-    loop {
-        let n = 1;
-        let m = n + 1;
-        break;
-        let k = 2;
-        let h = 1 + k;
-    }
-}
-
-fn main() {
-
-}
+fn foo() {
+    loop {
+        let n = 1;
+        let m = n + 1;
+        break;
+        let k = 2;
+        let h = 1 + k;
+    }
+}
+
+fn main() {
+
+}
@@ -0,0 +1,14 @@
+fn first_multiple(n: u32, start: u32) -> u32 {
+    let mut i = start;
+    loop {
+        if i % n == 0 {
+            break;
+        }
+        i += 1;
+    }
+    i
+}
+
+fn main() {
+
+}
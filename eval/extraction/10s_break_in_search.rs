@@ -0,0 +1,21 @@
+fn first_multiple(n: u32, start: u32) -> u32 {
+    let mut i = start;
+    loop {
+        if fun_name(n, i) {
+            break;
+        }
+        i += 1;
+    }
+    i
+}
+
+fn fun_name(n: u32, i: u32) -> bool {
+    if i % n == 0 {
+        return true;
+    }
+    false
+}
+
+fn main() {
+
+}
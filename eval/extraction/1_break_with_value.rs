@@ -1,15 +1,15 @@
-fn foo() -> i32 {
-    loop {
-        let n = 1;
-        let k = 1;
-        if k == 42 {
-            break 3;
-        }
-        let m = k + 1;
-        let h = 1;
-    }
-}
-
-fn main() {
-
-}
+fn foo() -> i32 {
+    loop {
+        let n = 1;
+        let k = 1;
+        if k == 42 {
+            break 3;
+        }
+        let m = k + 1;
+        let h = 1;
+    }
+}
+
+fn main() {
+
+}
@@ -1,13 +1,13 @@
-fn f() {
-    let c = {
-        // comment 1
-        let a = 2 + 3;
-        // comment 2
-        let b = 5;
-        a + b
-    };
-}
-
-fn main() {
-
-}
+fn f() {
+    let c = {
+        // comment 1
+        let a = 2 + 3;
+        // comment 2
+        let b = 5;
+        a + b
+    };
+}
+
+fn main() {
+
+}
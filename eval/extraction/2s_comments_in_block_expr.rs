@@ -1,15 +1,15 @@
-fn f() {
-    let c = fun_name();
-}
-
-fn fun_name() -> i32 {
-    // comment 1
-    let a = 2 + 3;
-    // comment 2
-    let b = 5;
-    a + b
-}
-
-fn main() {
-
-}
+fn f() {
+    let c = fun_name();
+}
+
+fn fun_name() -> i32 {
+    // comment 1
+    let a = 2 + 3;
+    // comment 2
+    let b = 5;
+    a + b
+}
+
+fn main() {
+
+}
@@ -1,11 +1,11 @@
-fn main() {
-    let x = true;
-    let tuple = match x {
-        true => (2 + 2, true),
-        _ => (0, false)
-    };
-}
-
-fn main() {
-
-}
+fn main() {
+    let x = true;
+    let tuple = match x {
+        true => (2 + 2, true),
+        _ => (0, false)
+    };
+}
+
+fn main() {
+
+}
@@ -1,16 +1,16 @@
-struct Foo;
-impl Foo {
-    fn foo(&mut self) {}
-}
-fn foo() {
-    let mut x = Foo;
-    while false {
-        let y = &mut x;
-        y.foo();
-    }
-    let z = x;
-}
-
-fn main() {
-
-}
+struct Foo;
+impl Foo {
+    fn foo(&mut self) {}
+}
+fn foo() {
+    let mut x = Foo;
+    while false {
+        let y = &mut x;
+        y.foo();
+    }
+    let z = x;
+}
+
+fn main() {
+
+}
@@ -1,7 +1,7 @@
-fn foo() -> u32 {
-    return 2 + 2;
-}
-
-fn main() {
-
-}
+fn foo() -> u32 {
+    return 2 + 2;
+}
+
+fn main() {
+
+}
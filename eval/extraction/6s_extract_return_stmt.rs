@@ -1,11 +1,11 @@
-fn foo() -> u32 {
-    return fun_name();
-}
-
-fn fun_name() -> u32 {
-    2 + 2
-}
-
-fn main() {
-
-}
+fn foo() -> u32 {
+    return fun_name();
+}
+
+fn fun_name() -> u32 {
+    2 + 2
+}
+
+fn main() {
+
+}
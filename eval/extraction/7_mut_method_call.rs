@@ -1,14 +1,14 @@
-trait I {
-    fn inc(&mut self);
-}
-impl I for i32 {
-    fn inc(&mut self) { *self += 1 }
-}
-fn foo() {
-    let mut n = 1;
-    n.inc();
-}
-
-fn main() {
-
-}
+trait I {
+    fn inc(&mut self);
+}
+impl I for i32 {
+    fn inc(&mut self) { *self += 1 }
+}
+fn foo() {
+    let mut n = 1;
+    n.inc();
+}
+
+fn main() {
+
+}
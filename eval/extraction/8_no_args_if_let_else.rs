@@ -1,7 +1,7 @@
-fn foo() -> i32 {
-    if let true = false { 1 } else { 2 }
-}
-
-fn main() {
-
-}
+fn foo() -> i32 {
+    if let true = false { 1 } else { 2 }
+}
+
+fn main() {
+
+}
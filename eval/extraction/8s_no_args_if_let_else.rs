@@ -1,11 +1,11 @@
-fn foo() -> i32 {
-    fun_name()
-}
-
-fn fun_name() -> i32 {
-    if let true = false { 1 } else { 2 }
-}
-
-fn main() {
-
-}
+fn foo() -> i32 {
+    fun_name()
+}
+
+fn fun_name() -> i32 {
+    if let true = false { 1 } else { 2 }
+}
+
+fn main() {
+
+}
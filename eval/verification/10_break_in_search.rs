@@ -0,0 +1,5 @@
+#[test]
+fn finds_the_first_multiple() {
+    assert_eq!(first_multiple(3, 7), 9);
+    assert_eq!(first_multiple(5, 10), 10);
+}
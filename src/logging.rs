@@ -1,46 +1,60 @@
-use flexi_logger::{
-    Logger,
-    FileSpec,
-    Criterion,
-    Naming,
-    Cleanup,
-    Age,
-    DeferredNow,
-    Record
-
-};
-
-pub fn init_logging() {
-    Logger::try_with_str("info")
-        .unwrap()
-        .log_to_file(
-            FileSpec::default()
-                .directory("logs")
-                .basename("rem-cli-log")
-                .suffix("log")
-        )
-        .rotate(
-            Criterion::Age(Age::Day), // Rotate Daily
-            Naming::Timestamps, // Use timestamps
-            Cleanup::KeepLogFiles(60)
-        )
-        .format_for_files(format_log_with_timestamp)
-        .start()
-        .unwrap_or_else(|e| panic!("Logger intialization failed: {}", e));
-}
-
-
-// Custom log formatter function that includes the timestamp.
-fn format_log_with_timestamp(
-    w: &mut dyn std::io::Write,
-    now: &mut DeferredNow,
-    record: &Record,
-) -> std::io::Result<()> {
-    write!(
-        w,
-        "{} [{}] - {}",
-        now.now().format("%Y-%m-%d %H:%M:%S"), // Timestamp in "YYYY-MM-DD HH:MM:SS" format
-        record.level(),
-        record.args()
-    )
+use flexi_logger::{
+    Logger,
+    FileSpec,
+    Criterion,
+    Naming,
+    Cleanup,
+    Age,
+    DeferredNow,
+    Record
+
+};
+
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+pub const LOG_DIRECTORY: &str = "logs";
+const LOG_BASENAME: &str = "rem-cli-log";
+const LOG_SUFFIX: &str = "log";
+
+pub fn init_logging() {
+    Logger::try_with_str("info")
+        .unwrap()
+        .log_to_file(
+            FileSpec::default()
+                .directory(LOG_DIRECTORY)
+                .basename(LOG_BASENAME)
+                .suffix(LOG_SUFFIX)
+        )
+        .rotate(
+            Criterion::Age(Age::Day), // Rotate Daily
+            Naming::Timestamps, // Use timestamps
+            Cleanup::KeepLogFiles(60)
+        )
+        .format_for_files(format_log_with_timestamp)
+        .start()
+        .unwrap_or_else(|e| panic!("Logger intialization failed: {}", e));
+}
+
+/// The file the current run is logging to. Once a day it is renamed with the
+/// time it was rotated, and a new one is started.
+pub fn current_log_file() -> PathBuf {
+    Path::new(LOG_DIRECTORY).join(format!("{}_rCURRENT.{}", LOG_BASENAME, LOG_SUFFIX))
+}
+
+// Custom log formatter function that includes the timestamp.
+fn format_log_with_timestamp(
+    w: &mut dyn std::io::Write,
+    now: &mut DeferredNow,
+    record: &Record,
+) -> std::io::Result<()> {
+    write!(
+        w,
+        "{} [{}] - {}",
+        now.now().format("%Y-%m-%d %H:%M:%S"), // Timestamp in "YYYY-MM-DD HH:MM:SS" format
+        record.level(),
+        record.args()
+    )
 }
\ No newline at end of file
@@ -1,308 +1,926 @@
-use std::{
-    iter::Successors, path::PathBuf, process::exit
-};
-
-use clap::Parser;
-
-use log::{
-    error,
-    info,
-};
-
-mod logging;
-mod error;
-mod tests;
-
-mod refactor;
-use refactor::{
-    non_local_controller::non_local_controller,
-    borrow::borrow,
-};
-
-use rem_repairer::{
-    common::{
-        RepairResult,
-        RepairSystem,
-    }, repair_lifetime_loosest_bound_first, repair_lifetime_simple, repair_lifetime_tightest_bound_first, repair_rustfix
-};
-
-mod utils;
-use utils::{
-    delete_backup,
-    delete_repo,
-    get_from_git,
-    handle_result,
-    run_tests,
-    backup_file,
-};
-
-mod messages;
-
-mod rem_args;
-use rem_args::{
-    REMArgs,
-    REMCommands,
-};
-
-use crate::refactor::throughput::{
-    Extract,
-    Controller,
-    Borrower,
-    Repairer,
-};
-
-#[derive(Debug, PartialEq, Eq)]
-enum ProgramOptions{
-    Refactoring,
-    Testing,
-    CargoRepairing,
-}
-
-/// The CLI Takes the following arguments:
-fn main() {
-
-    logging::init_logging();
-
-    info!("Application Started");
-
-    let args: REMArgs = REMArgs::parse();
-    let mut backup_path: Option<PathBuf> = None;
-
-    // Set to refactoring by default. This will be changed by the rest of the
-    // CLI if a non-refactoring program is run.
-    let mut prog_run: ProgramOptions = ProgramOptions::Refactoring;
-
-    match &args.command {
-        REMCommands::Run {
-            file_path,
-            new_file_path,
-            caller_fn_name,
-            callee_fn_name
-        } => {
-            // Create our backup
-            backup_path = backup_file(file_path.clone());
-
-            let file_path = file_path.to_str().expect("Path is not valid UTF-8");
-            let new_file_path = new_file_path.to_str().expect("Path is not valid UTF-8");
-        },
-
-        REMCommands::Controller {
-            file_path,
-            new_file_path,
-            caller_fn_name,
-            callee_fn_name
-        } => {
-            // Create our backup
-            backup_path = backup_file(file_path.clone());
-
-            let file_path: &str = file_path.to_str().expect("Path is not valid UTF-8");
-            let new_file_path: &str = new_file_path.to_str().expect("Path is not valid UTF-8");
-
-            let controller: Controller = todo!();
-
-            let result: Result<String, error::RefactoringError>  = non_local_controller(
-                controller,
-            );
-
-            // TODO Handle the result
-            let success: bool = match result {
-                Ok(_) => true,
-                Err(e) => {
-                    error!("Controller failed: {:?}", e);
-                    false
-                }
-            };
-
-            handle_result(
-                success,
-                "Controller",
-                &format!(
-                    "Controller was run on its own with file_path: {} | new_file_path: {} | caller_fn_name: {} | callee_fn_name: {}",
-                    file_path,
-                    new_file_path,
-                    caller_fn_name,
-                    callee_fn_name,
-                    ),
-            )
-
-        },
-
-        REMCommands::Borrower {
-            file_path,
-            new_file_path,
-            caller_fn_name,
-            callee_fn_name,
-            mut_method_file_path,
-            pre_extract_file_path
-        } => {
-            // Create our backup
-            backup_path = backup_file(file_path.clone());
-
-            let file_path: &str = file_path.to_str().expect("Path is not valid UTF-8");
-            let new_file_path: &str = new_file_path.to_str().expect("Path is not valid UTF-8");
-            let mut_method_file_path: &str = mut_method_file_path.to_str().expect("Path is not valid UTF-8");
-            let pre_extract_file_path: &str = pre_extract_file_path.to_str().expect("Path is not valid UTF-8");
-
-            let input_borrower: Borrower = todo!();
-            let result  = borrow(
-                input_borrower,
-                // file_path,
-                // new_file_path,
-                // callee_fn_name,
-                // caller_fn_name,
-                // mut_method_file_path,
-                // pre_extract_file_path,
-            );
-            let success: bool = match result {
-                Ok(_) => true,
-                Err(e) => {
-                    error!("Borrower failed: {:?}", e);
-                    false
-                }
-            };
-
-            handle_result(success,
-                "Borrower",
-                &format!(
-                    "Borrower was run on its own with file_path: {} | new_file_path: {} | caller_fn_name: {} | callee_fn_name: {} | mut_method_file_path: {} | pre_extract_file_path: {}",
-                    file_path,
-                    new_file_path,
-                    caller_fn_name,
-                    callee_fn_name,
-                    mut_method_file_path,
-                    pre_extract_file_path,
-                ),
-            )
-
-        },
-
-        REMCommands::Repairer {
-            file_path,
-            new_file_path,
-            fn_name,
-            repairer,
-            verbose, // TODO Implement this
-        } => {
-            // Create our backup
-            backup_path = backup_file(file_path.clone());
-
-            let file_path: &str = file_path.to_str().expect("Path is not valid UTF-8");
-            let new_file_path: &str = new_file_path.to_str().expect("Path is not valid UTF-8");
-            let repair_system: &dyn RepairSystem = match repairer {
-                1 => &repair_lifetime_simple::Repairer {},
-                2 => &repair_lifetime_loosest_bound_first::Repairer {},
-                3 => &repair_lifetime_tightest_bound_first::Repairer {},
-                4 => &repair_rustfix::Repairer {},
-                _ => {
-                    error!("{} is not a valid option for the repair system", *repairer);
-                    exit(1)
-                },
-            };
-
-            let RepairResult { success, .. } = repair_system.repair_function(
-                file_path,
-                new_file_path,
-                fn_name
-                );
-
-            handle_result(success,
-                "Repairer",
-                &format!(
-                    "Repairer was run on its own with file_path: {} | new_file_path: {} | fn_name: {} | repair_system: {}",
-                    file_path,
-                    new_file_path,
-                    fn_name,
-                    repair_system.name(),
-                ),
-            )
-        },
-
-        REMCommands::RepairerCargo {
-            src_path,
-            manifest_path,
-            fn_name,
-            repairer,
-            verbose
-        } => {
-            prog_run = ProgramOptions::CargoRepairing;
-
-        },
-
-        REMCommands::Test {
-            folder,
-            verbose // NYI
-        } => {
-            prog_run = ProgramOptions::Testing;
-
-            if *verbose {
-                info!("Running tests in verbose mode");
-            } else {
-                info!("Running tests");
-            }
-
-            match run_tests(folder.clone()) {
-                Ok(x) => info!("Test running finished, {} tests failed", x),
-                Err(e) => {
-                    error!("Test running failed: {:?}", e);
-                    exit(1);
-                }
-            }
-        },
-
-        REMCommands::TestGithub {
-            repo,
-            verbose, // NYI
-        } => {
-            prog_run = ProgramOptions::Testing;
-
-            if *verbose {
-                info!("Running tests in verbose mode from GitHub repo: {}", repo.clone());
-            } else {
-                info!("Running tests from GitHub repo: {}", repo.clone());
-            }
-
-            let path: PathBuf = match get_from_git(repo.clone()) {
-                Ok(p) => p,
-                Err(e) => {
-                    error!("Failed to fetch from GitHub: {}", e);
-                    exit(1);
-                }
-            };
-
-            match run_tests(path.clone()) {
-                Ok(failed_tests) => info!("Test running finished, {} tests failed", failed_tests),
-                Err(e) => {
-                    error!("Test running failed: {:?}", e);
-                    exit(1);
-                }
-            }
-
-            match delete_repo(path) {
-                Ok(_) => info!("Successfully deleted folder downloaded from Git"),
-                Err(e) => {
-                    error!("Unable to delete downloaded folder: {}", e);
-                    exit(1);
-                }
-            }
-        },
-    }
-
-    // Attempt to delete the backup
-    if let Some(backup_path_real) = backup_path {
-        if let Err(e) = delete_backup(backup_path_real) {
-            error!("Failed to delete backup: {:?}", e);
-            exit(1);
-        } else {
-            info!("Backup deleted successfully");
-        }
-    } else if prog_run == ProgramOptions::Refactoring {
-        // Handle backup path being none -
-        // How tf did we end up here
-        error!("Backup path was never provided / saved, HOW DID WE GET HERE?");
-        exit(1);
-    }
-
-    // If we have gotten this far then we know that all previous activities have
-    // been successful. Delete the backup and exit successfully if that works
-    info!("Refactoring completed successfully")
-}
+use std::{
+    iter::Successors, path::{Path, PathBuf}, process::exit, time::Duration
+};
+
+use clap::Parser;
+
+use tempfile::TempDir;
+
+use log::{
+    error,
+    info,
+};
+
+mod logging;
+mod error;
+mod tests;
+
+mod refactor;
+use refactor::{
+    non_local_controller::non_local_controller,
+    borrow::borrow,
+};
+
+use rem_repairer::{
+    common::{
+        RepairResult,
+        RepairSystem,
+    }, repair_lifetime_loosest_bound_first, repair_lifetime_simple, repair_lifetime_tightest_bound_first, repair_rustfix
+};
+
+mod utils;
+use tests::harness::{
+    run_case_to_file,
+    HarnessOptions,
+};
+use tests::fuzz::{
+    default_seed,
+    load_selection,
+    run_fuzz,
+    run_fuzz_case,
+    FuzzOptions,
+    Selection,
+};
+use tests::bench::{
+    compare_with_baseline,
+    run_bench,
+    run_bench_case,
+    BenchOptions,
+    BenchReport,
+};
+use tests::bundle::{
+    run_extract,
+    run_repro,
+    Refactoring,
+};
+use tests::reduce::{
+    run_reduce,
+    ReduceOptions,
+};
+use tests::extract::SELECTION_MANIFEST;
+use tests::eval::{
+    run_eval,
+    run_eval_case,
+    EvalOptions,
+};
+use tests::record::{
+    load_run,
+    print_comparison,
+    record_run,
+};
+use tests::replay::{
+    run_replay,
+    run_replay_case,
+    ReplayOptions,
+};
+use tests::mine::{
+    run_mine,
+    MineOptions,
+};
+use tests::import::{
+    run_import,
+    ImportOptions,
+};
+use tests::corpus::{
+    run_corpus_stats,
+    CorpusOptions,
+};
+use tests::report::{
+    ReportFormat,
+    TestReport,
+};
+use utils::{
+    delete_backup,
+    get_from_git,
+    handle_result,
+    run_tests,
+    backup_file,
+    write_reports,
+};
+
+mod messages;
+
+mod rem_args;
+use rem_args::{
+    REMArgs,
+    REMCommands,
+};
+
+use crate::refactor::throughput::{
+    Extract,
+    Controller,
+    Borrower,
+    Repairer,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+enum ProgramOptions{
+    Refactoring,
+    Testing,
+    CargoRepairing,
+}
+
+/// The CLI Takes the following arguments:
+fn main() {
+
+    logging::init_logging();
+
+    info!("Application Started");
+
+    let args: REMArgs = REMArgs::parse();
+    let mut backup_path: Option<PathBuf> = None;
+
+    // Set to refactoring by default. This will be changed by the rest of the
+    // CLI if a non-refactoring program is run.
+    let mut prog_run: ProgramOptions = ProgramOptions::Refactoring;
+
+    match &args.command {
+        REMCommands::Run {
+            file_path,
+            new_file_path,
+            caller_fn_name,
+            callee_fn_name
+        } => {
+            // Create our backup
+            backup_path = backup_file(file_path.clone());
+
+            let file_path = file_path.to_str().expect("Path is not valid UTF-8");
+            let new_file_path = new_file_path.to_str().expect("Path is not valid UTF-8");
+        },
+
+        REMCommands::Extract {
+            file_path,
+            new_file_path,
+            start_idx,
+            end_idx,
+            new_fn_name,
+            bundles,
+            no_bundle,
+        } => {
+            // Create our backup
+            backup_path = backup_file(file_path.clone());
+
+            let refactoring: Refactoring = Refactoring {
+                file: file_path.clone(),
+                start_idx: *start_idx,
+                end_idx: *end_idx,
+                new_fn_name: new_fn_name.clone(),
+            };
+            let bundles: Option<&Path> = (!*no_bundle).then_some(bundles.as_path());
+
+            let success: bool = match run_extract(&refactoring, new_file_path, bundles) {
+                Ok(success) => success,
+                Err(e) => {
+                    error!("Extract failed to run: {}", e);
+                    eprintln!("{}", e);
+                    false
+                }
+            };
+
+            handle_result(
+                success,
+                "Extract",
+                &format!(
+                    "Extract was run with file_path: {:?} | new_file_path: {:?} | start_idx: {} | end_idx: {} | new_fn_name: {}",
+                    file_path,
+                    new_file_path,
+                    start_idx,
+                    end_idx,
+                    new_fn_name,
+                ),
+            )
+        },
+
+        REMCommands::Repro { bundle } => {
+            prog_run = ProgramOptions::Testing;
+
+            match run_repro(bundle) {
+                Ok(still_fails) => {
+                    if still_fails {
+                        exit(1);
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to reproduce {:?}: {}", bundle, e);
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            }
+        },
+
+        REMCommands::Controller {
+            file_path,
+            new_file_path,
+            caller_fn_name,
+            callee_fn_name
+        } => {
+            // Create our backup
+            backup_path = backup_file(file_path.clone());
+
+            let file_path: &str = file_path.to_str().expect("Path is not valid UTF-8");
+            let new_file_path: &str = new_file_path.to_str().expect("Path is not valid UTF-8");
+
+            let controller: Controller = todo!();
+
+            let result: Result<String, error::RefactoringError>  = non_local_controller(
+                controller,
+            );
+
+            // TODO Handle the result
+            let success: bool = match result {
+                Ok(_) => true,
+                Err(e) => {
+                    error!("Controller failed: {:?}", e);
+                    false
+                }
+            };
+
+            handle_result(
+                success,
+                "Controller",
+                &format!(
+                    "Controller was run on its own with file_path: {} | new_file_path: {} | caller_fn_name: {} | callee_fn_name: {}",
+                    file_path,
+                    new_file_path,
+                    caller_fn_name,
+                    callee_fn_name,
+                    ),
+            )
+
+        },
+
+        REMCommands::Borrower {
+            file_path,
+            new_file_path,
+            caller_fn_name,
+            callee_fn_name,
+            mut_method_file_path,
+            pre_extract_file_path
+        } => {
+            // Create our backup
+            backup_path = backup_file(file_path.clone());
+
+            let file_path: &str = file_path.to_str().expect("Path is not valid UTF-8");
+            let new_file_path: &str = new_file_path.to_str().expect("Path is not valid UTF-8");
+            let mut_method_file_path: &str = mut_method_file_path.to_str().expect("Path is not valid UTF-8");
+            let pre_extract_file_path: &str = pre_extract_file_path.to_str().expect("Path is not valid UTF-8");
+
+            let input_borrower: Borrower = todo!();
+            let result  = borrow(
+                input_borrower,
+                // file_path,
+                // new_file_path,
+                // callee_fn_name,
+                // caller_fn_name,
+                // mut_method_file_path,
+                // pre_extract_file_path,
+            );
+            let success: bool = match result {
+                Ok(_) => true,
+                Err(e) => {
+                    error!("Borrower failed: {:?}", e);
+                    false
+                }
+            };
+
+            handle_result(success,
+                "Borrower",
+                &format!(
+                    "Borrower was run on its own with file_path: {} | new_file_path: {} | caller_fn_name: {} | callee_fn_name: {} | mut_method_file_path: {} | pre_extract_file_path: {}",
+                    file_path,
+                    new_file_path,
+                    caller_fn_name,
+                    callee_fn_name,
+                    mut_method_file_path,
+                    pre_extract_file_path,
+                ),
+            )
+
+        },
+
+        REMCommands::Repairer {
+            file_path,
+            new_file_path,
+            fn_name,
+            repairer,
+            verbose, // TODO Implement this
+        } => {
+            // Create our backup
+            backup_path = backup_file(file_path.clone());
+
+            let file_path: &str = file_path.to_str().expect("Path is not valid UTF-8");
+            let new_file_path: &str = new_file_path.to_str().expect("Path is not valid UTF-8");
+            let repair_system: &dyn RepairSystem = match repairer {
+                1 => &repair_lifetime_simple::Repairer {},
+                2 => &repair_lifetime_loosest_bound_first::Repairer {},
+                3 => &repair_lifetime_tightest_bound_first::Repairer {},
+                4 => &repair_rustfix::Repairer {},
+                _ => {
+                    error!("{} is not a valid option for the repair system", *repairer);
+                    exit(1)
+                },
+            };
+
+            let RepairResult { success, .. } = repair_system.repair_function(
+                file_path,
+                new_file_path,
+                fn_name
+                );
+
+            handle_result(success,
+                "Repairer",
+                &format!(
+                    "Repairer was run on its own with file_path: {} | new_file_path: {} | fn_name: {} | repair_system: {}",
+                    file_path,
+                    new_file_path,
+                    fn_name,
+                    repair_system.name(),
+                ),
+            )
+        },
+
+        REMCommands::RepairerCargo {
+            src_path,
+            manifest_path,
+            fn_name,
+            repairer,
+            verbose
+        } => {
+            prog_run = ProgramOptions::CargoRepairing;
+
+        },
+
+        REMCommands::Test {
+            folder,
+            verbose, // NYI
+            reports,
+            jobs,
+            timeout,
+            cargo_test,
+            compare,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            if *verbose {
+                info!("Running tests in verbose mode");
+            } else {
+                info!("Running tests");
+            }
+
+            let options: HarnessOptions = HarnessOptions {
+                jobs: *jobs,
+                timeout: Duration::from_secs(*timeout),
+                cargo_test: *cargo_test,
+                compare: *compare,
+            };
+
+            match run_tests(folder.clone(), &options) {
+                Ok(report) => {
+                    info!("Test running finished, {} tests failed", report.failed());
+                    if let Err(e) = write_reports(&report, reports) {
+                        error!("{}", e);
+                        exit(1);
+                    }
+                },
+                Err(e) => {
+                    error!("Test running failed: {:?}", e);
+                    exit(1);
+                }
+            }
+        },
+
+        REMCommands::TestGithub {
+            repo,
+            rev,
+            subdir,
+            keep,
+            verbose, // NYI
+            reports,
+            jobs,
+            timeout,
+            cargo_test,
+            compare,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            if *verbose {
+                info!("Running tests in verbose mode from Git repo: {}", repo.clone());
+            } else {
+                info!("Running tests from Git repo: {}", repo.clone());
+            }
+
+            let clone: TempDir = match get_from_git(repo, rev.as_deref()) {
+                Ok(clone) => clone,
+                Err(e) => {
+                    error!("Failed to fetch from Git: {}", e);
+                    exit(1);
+                }
+            };
+
+            let path: PathBuf = match subdir {
+                Some(subdir) => clone.path().join(subdir),
+                None => clone.path().to_path_buf(),
+            };
+
+            let options: HarnessOptions = HarnessOptions {
+                jobs: *jobs,
+                timeout: Duration::from_secs(*timeout),
+                cargo_test: *cargo_test,
+                compare: *compare,
+            };
+
+            // The clone is cleaned up before exiting, whether or not the
+            // tests could be run and their reports written
+            let succeeded: bool = match run_tests(path, &options) {
+                Ok(report) => {
+                    info!("Test running finished, {} tests failed", report.failed());
+                    match write_reports(&report, reports) {
+                        Ok(_) => true,
+                        Err(e) => {
+                            error!("{}", e);
+                            false
+                        }
+                    }
+                },
+                Err(e) => {
+                    error!("Test running failed: {:?}", e);
+                    false
+                }
+            };
+
+            if *keep {
+                let kept: PathBuf = clone.keep();
+                println!("Kept the cloned repository at {:?}", kept);
+                info!("Kept the cloned repository at {:?}", kept);
+            } else {
+                match clone.close() {
+                    Ok(_) => info!("Successfully deleted folder downloaded from Git"),
+                    Err(e) => {
+                        error!("Unable to delete downloaded folder: {}", e);
+                        exit(1);
+                    }
+                }
+            }
+
+            if !succeeded {
+                exit(1);
+            }
+        },
+
+        REMCommands::Fuzz {
+            folder,
+            iterations,
+            seed,
+            jobs,
+            timeout,
+            out,
+            reports,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            let options: FuzzOptions = FuzzOptions {
+                iterations: *iterations,
+                seed: seed.unwrap_or_else(default_seed),
+                jobs: *jobs,
+                timeout: Duration::from_secs(*timeout),
+                out: match out {
+                    Some(out) => out.clone(),
+                    None if folder.join("extract/input").is_dir() => folder.clone(),
+                    None => folder.join("fuzz"),
+                },
+            };
+
+            match run_fuzz(folder, &options) {
+                Ok(suite) => {
+                    info!("Fuzzing finished, {} selections flagged", suite.failed());
+                    let report: TestReport = TestReport {
+                        suites: vec![suite],
+                    };
+                    if let Err(e) = write_reports(&report, reports) {
+                        error!("{}", e);
+                        exit(1);
+                    }
+                },
+                Err(e) => {
+                    error!("Fuzzing failed: {}", e);
+                    exit(1);
+                }
+            }
+        },
+
+        REMCommands::Reduce {
+            project,
+            start_idx,
+            end_idx,
+            name,
+            out,
+            jobs,
+            timeout,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            let fixture: String = project
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let selection: Selection = match (start_idx, end_idx) {
+                (Some(start_idx), Some(end_idx)) => Selection {
+                    fixture: fixture.clone(),
+                    start_idx: *start_idx,
+                    end_idx: *end_idx,
+                },
+                _ => match load_selection(project) {
+                    Ok(selection) => Selection {
+                        fixture: fixture.clone(),
+                        ..selection
+                    },
+                    Err(e) => {
+                        error!("No selection given, and none could be read from {:?}: {}", project.join(SELECTION_MANIFEST), e);
+                        exit(1);
+                    }
+                },
+            };
+
+            let options: ReduceOptions = ReduceOptions {
+                jobs: *jobs,
+                timeout: Duration::from_secs(*timeout),
+                out: out.clone(),
+                name: name.clone().unwrap_or_else(|| format!("{}_reduced", fixture)),
+            };
+
+            if let Err(e) = run_reduce(project, &selection, &options) {
+                error!("Reduction failed: {}", e);
+                eprintln!("{}", e);
+                exit(1);
+            }
+        },
+
+        REMCommands::Bench {
+            folder,
+            iterations,
+            warmup,
+            cases,
+            save,
+            baseline,
+            threshold,
+            timeout,
+            reports,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            if reports.iter().any(|target| target.format == ReportFormat::Junit) {
+                error!("Benchmarks can't be written as junit reports (expected json, csv or latex)");
+                exit(1);
+            }
+
+            let options: BenchOptions = BenchOptions {
+                iterations: *iterations,
+                warmup: *warmup,
+                cases: cases.clone(),
+                baseline: baseline.clone(),
+                threshold: *threshold,
+                save: save.clone(),
+                timeout: Duration::from_secs(*timeout),
+            };
+
+            // Load the baseline first, so a bad path doesn't waste a whole run
+            let baseline: Option<BenchReport> = match &options.baseline {
+                Some(path) => match BenchReport::load(path) {
+                    Ok(baseline) => Some(baseline),
+                    Err(e) => {
+                        error!("Failed to read baseline {:?}: {}", path, e);
+                        exit(1);
+                    }
+                },
+                None => None,
+            };
+
+            let report: BenchReport = match run_bench(folder, &options) {
+                Ok(report) => report,
+                Err(e) => {
+                    error!("Benchmark failed: {}", e);
+                    exit(1);
+                }
+            };
+
+            if let Some(path) = &options.save {
+                if let Err(e) = report.save(path) {
+                    error!("Failed to save benchmark to {:?}: {}", path, e);
+                    exit(1);
+                }
+                info!("Benchmark saved to {:?}", path);
+            }
+
+            if let Err(e) = write_reports(&report, reports) {
+                error!("{}", e);
+                exit(1);
+            }
+
+            if let Some(baseline) = baseline {
+                if !compare_with_baseline(&report, &baseline, options.threshold).is_empty() {
+                    exit(1);
+                }
+            }
+        },
+
+        REMCommands::Eval {
+            folder,
+            runs: Some(runs),
+            results,
+            ..
+        } => {
+            prog_run = ProgramOptions::Testing;
+            let results_dir: PathBuf = results.clone().unwrap_or_else(|| folder.join("results"));
+
+            let (before, after) = match (load_run(&results_dir, &runs[0]), load_run(&results_dir, &runs[1])) {
+                (Ok(before), Ok(after)) => (before, after),
+                (Err(e), _) | (_, Err(e)) => {
+                    error!("{}", e);
+                    exit(1);
+                }
+            };
+            // Like a failing test run, so scripts can stop on regressions
+            if print_comparison(&before, &after) > 0 {
+                exit(1);
+            }
+        },
+
+        REMCommands::Eval {
+            folder,
+            jobs,
+            timeout,
+            reports,
+            baseline,
+            no_miri,
+            record,
+            results,
+            compare,
+            ..
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            let options: EvalOptions = EvalOptions {
+                jobs: *jobs,
+                timeout: Duration::from_secs(*timeout),
+                compare: *compare,
+                baseline: *baseline,
+                miri: !*no_miri,
+            };
+
+            match run_eval(folder, &options) {
+                Ok(report) => {
+                    for suite in &report.suites {
+                        info!("Evaluation finished, {} pairs failed in {}", suite.failed(), suite.name);
+                    }
+                    if let Err(e) = write_reports(&report, reports) {
+                        error!("{}", e);
+                        exit(1);
+                    }
+                    if *record {
+                        let results_dir: PathBuf = results.clone().unwrap_or_else(|| folder.join("results"));
+                        match record_run(&report, &results_dir) {
+                            Ok(path) => info!("Recorded the run in {:?}", path),
+                            Err(e) => {
+                                error!("Failed to record the run in {:?}: {}", results_dir, e);
+                                exit(1);
+                            }
+                        }
+                    }
+                },
+                Err(e) => {
+                    error!("Evaluation failed: {}", e);
+                    exit(1);
+                }
+            }
+        },
+
+        REMCommands::EvalCase {
+            input,
+            expected,
+            start_idx,
+            end_idx,
+            verification,
+            tool,
+            no_miri,
+            timeout,
+            result,
+            compare,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            // Only the options that affect a single pair are passed through
+            let options: EvalOptions = EvalOptions {
+                compare: *compare,
+                miri: !*no_miri,
+                timeout: Duration::from_secs(*timeout),
+                ..EvalOptions::default()
+            };
+
+            if let Err(e) = run_eval_case(input, expected, verification.as_deref(), *start_idx..*end_idx, *tool, &options, result) {
+                error!("Eval case {:?} failed to run: {}", input, e);
+                eprintln!("{}", e);
+                exit(1);
+            }
+        },
+
+        REMCommands::Replay {
+            cases,
+            repos,
+            jobs,
+            timeout,
+            reports,
+            compare,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            let options: ReplayOptions = ReplayOptions {
+                repos: repos.clone(),
+                jobs: *jobs,
+                timeout: Duration::from_secs(*timeout),
+                compare: *compare,
+            };
+
+            match run_replay(cases, &options) {
+                Ok(suite) => {
+                    info!("Replay finished, {} cases failed", suite.failed());
+                    let report: TestReport = TestReport {
+                        suites: vec![suite],
+                    };
+                    if let Err(e) = write_reports(&report, reports) {
+                        error!("{}", e);
+                        exit(1);
+                    }
+                },
+                Err(e) => {
+                    error!("Replay failed: {}", e);
+                    exit(1);
+                }
+            }
+        },
+
+        REMCommands::ReplayCase {
+            name,
+            input,
+            expected,
+            fn_name,
+            start_line,
+            end_line,
+            result,
+            compare,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            if let Err(e) = run_replay_case(name, input, expected, fn_name, *start_line..=*end_line, compare, result) {
+                error!("Replay case {} failed to run: {}", name, e);
+                eprintln!("{}", e);
+                exit(1);
+            }
+        },
+
+        REMCommands::Mine {
+            repo,
+            out,
+            depth,
+            max_cases,
+            min_match,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            let options: MineOptions = MineOptions {
+                out: out.clone(),
+                depth: *depth,
+                max_cases: *max_cases,
+                min_match: *min_match,
+            };
+
+            if let Err(e) = run_mine(repo, &options) {
+                error!("Mining {:?} failed: {}", repo, e);
+                eprintln!("Mining {:?} failed: {}", repo, e);
+                exit(1);
+            }
+        },
+
+        REMCommands::ImportCase {
+            cases,
+            repos,
+            names,
+            out,
+            force,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            let options: ImportOptions = ImportOptions {
+                repos: repos.clone(),
+                out: out.clone(),
+                cases: names.clone(),
+                force: *force,
+            };
+
+            match run_import(cases, &options) {
+                Ok(0) => info!("Imported every case from {:?}", cases),
+                Ok(failed) => {
+                    error!("{} cases from {:?} could not be imported", failed, cases);
+                    exit(1);
+                },
+                Err(e) => {
+                    error!("Importing {:?} failed: {}", cases, e);
+                    eprintln!("Importing {:?} failed: {}", cases, e);
+                    exit(1);
+                }
+            }
+        },
+
+        REMCommands::CorpusStats {
+            cases,
+            repos,
+            fixtures,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            let options: CorpusOptions = CorpusOptions {
+                repos: repos.clone(),
+                fixtures: fixtures.clone(),
+            };
+
+            if let Err(e) = run_corpus_stats(cases, &options) {
+                error!("Summarising {:?} failed: {}", cases, e);
+                eprintln!("Summarising {:?} failed: {}", cases, e);
+                exit(1);
+            }
+        },
+
+        REMCommands::FuzzCase {
+            fixture,
+            start_idx,
+            end_idx,
+            result,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            if let Err(e) = run_fuzz_case(fixture, *start_idx, *end_idx, result) {
+                error!("Fuzz case {:?} {}..{} failed to run: {}", fixture, start_idx, end_idx, e);
+                eprintln!("{}", e);
+                exit(1);
+            }
+        },
+
+        REMCommands::BenchCase {
+            folder,
+            case,
+            warmup,
+            iterations,
+            result,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            if let Err(e) = run_bench_case(folder, case, *warmup, *iterations, result) {
+                error!("Bench case {} failed to run: {}", case, e);
+                eprintln!("{}", e);
+                exit(1);
+            }
+        },
+
+        REMCommands::TestCase {
+            suite,
+            folder,
+            case,
+            result,
+            cargo_test,
+            compare,
+        } => {
+            prog_run = ProgramOptions::Testing;
+
+            // Only the options that affect a single case are passed through
+            let options: HarnessOptions = HarnessOptions {
+                cargo_test: *cargo_test,
+                compare: *compare,
+                ..HarnessOptions::default()
+            };
+
+            if let Err(e) = run_case_to_file(*suite, folder, case, result, &options) {
+                error!("Test case {} {} failed to run: {}", suite, case, e);
+                eprintln!("{}", e);
+                exit(1);
+            }
+        },
+    }
+
+    // Attempt to delete the backup
+    if let Some(backup_path_real) = backup_path {
+        if let Err(e) = delete_backup(backup_path_real) {
+            error!("Failed to delete backup: {:?}", e);
+            exit(1);
+        } else {
+            info!("Backup deleted successfully");
+        }
+    } else if prog_run == ProgramOptions::Refactoring {
+        // Handle backup path being none -
+        // How tf did we end up here
+        error!("Backup path was never provided / saved, HOW DID WE GET HERE?");
+        exit(1);
+    }
+
+    // If we have gotten this far then we know that all previous activities have
+    // been successful. Delete the backup and exit successfully if that works
+    info!("Refactoring completed successfully")
+}
@@ -1,10 +1,10 @@
-pub const ABOUT: &str =
-r#"A CLI tool for the REM toolchain.
-Utilizes:
-    - rem-controller: git= https://github.com/RuleBrittonica/rem-controller
-    - rem-borrower:   git= https://github.com/RuleBrittonica/rem-borrower
-    - rem-repairer:   git= https://github.com/RuleBrittonica/rem-repairer
-    - rem-utils:      git= https://github.com/RuleBrittonica/rem-utils
-
-Designed and implemented for the rem-vscode plugin, available at git= https://github.com/RuleBrittonica/rem-vscode
+pub const ABOUT: &str =
+r#"A CLI tool for the REM toolchain.
+Utilizes:
+    - rem-controller: git= https://github.com/RuleBrittonica/rem-controller
+    - rem-borrower:   git= https://github.com/RuleBrittonica/rem-borrower
+    - rem-repairer:   git= https://github.com/RuleBrittonica/rem-repairer
+    - rem-utils:      git= https://github.com/RuleBrittonica/rem-utils
+
+Designed and implemented for the rem-vscode plugin, available at git= https://github.com/RuleBrittonica/rem-vscode
 "#;
\ No newline at end of file
@@ -1,3 +1,3 @@
-pub mod author;
-pub mod about;
+pub mod author;
+pub mod about;
 pub mod version;
\ No newline at end of file
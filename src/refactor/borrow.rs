@@ -1,17 +1,27 @@
-use rem_borrower::borrow;
-use std::time::Instant;
-use log::{
-    info,
-    error
-};
-
-use super::throughput::Borrower;
-use crate::error::RefactoringError;
-
-/// Only takes a file path as the non_local_controller has already setup the
-/// final output filepath.
-pub fn borrow(
-    input: Borrower
-) -> Result<String, RefactoringError> {
-    todo!()
+use rem_borrower::borrow;
+use std::time::Instant;
+use log::{
+    info,
+    error
+};
+
+use super::throughput::Borrower;
+use crate::error::RefactoringError;
+
+/// Only takes a file path as the non_local_controller has already setup the
+/// final output filepath.
+pub fn borrow(
+    input: Borrower
+) -> Result<String, RefactoringError> {
+    let begin: Instant = Instant::now();
+    match borrow::make_borrows(input.into()) {
+        Ok(output_code) => {
+            info!("Borrower completed in {:?}", begin.elapsed());
+            Ok(output_code)
+        },
+        Err(e) => {
+            error!("Borrower failed after {:?}: {:?}", begin.elapsed(), e);
+            Err(RefactoringError::BorrowerFailed(format!("{:?}", e)))
+        },
+    }
 }
\ No newline at end of file
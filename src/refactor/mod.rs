@@ -1,6 +1,6 @@
-pub mod refactor_main;
-pub mod borrow;
-pub mod repair_lifetime;
-pub mod non_local_controller;
-pub mod throughput;
+pub mod refactor_main;
+pub mod borrow;
+pub mod repair_lifetime;
+pub mod non_local_controller;
+pub mod throughput;
 pub mod extract_function;
\ No newline at end of file
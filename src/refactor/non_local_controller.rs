@@ -1,16 +1,26 @@
-    use rem_controller::non_local_controller;
-use std::time::Instant;
-use log::{
-    info,
-    error,
-};
-
-use crate::error::RefactoringError;
-
-use super::throughput::Controller;
-
-pub fn non_local_controller(
-    input: Controller
-) -> Result<String, RefactoringError> {
-    todo!()
+    use rem_controller::non_local_controller;
+use std::time::Instant;
+use log::{
+    info,
+    error,
+};
+
+use crate::error::RefactoringError;
+
+use super::throughput::Controller;
+
+pub fn non_local_controller(
+    input: Controller
+) -> Result<String, RefactoringError> {
+    let begin: Instant = Instant::now();
+    match non_local_controller::make_controls(input.into()) {
+        Ok(output_code) => {
+            info!("Controller completed in {:?}", begin.elapsed());
+            Ok(output_code)
+        },
+        Err(e) => {
+            error!("Controller failed after {:?}: {:?}", begin.elapsed(), e);
+            Err(RefactoringError::ControllerFailed(format!("{:?}", e)))
+        },
+    }
 }
\ No newline at end of file
@@ -1,164 +1,560 @@
-use clap::{
-    Parser,
-    Subcommand,
-    ArgAction,
-};
-
-use std::path::PathBuf;
-
-use crate::messages::{
-    version::VERSION,
-    about::ABOUT,
-    author::AUTHOR,
-};
-
-#[derive(Parser)]
-#[command(
-    author = AUTHOR,
-    version = VERSION,
-    about = ABOUT
-)]
-pub struct REMArgs {
-    #[command(subcommand)]
-    pub command: REMCommands
-}
-
-#[derive(Subcommand)]
-pub enum REMCommands {
-
-    // Run the whole repairer
-    Run {
-        #[arg(help = "The path to the file that contains just the code that will be refactored", index = 1)]
-        file_path: PathBuf,
-
-        #[arg(help = "The path to the output file (where the refactored code ends up)", index = 2)]
-        new_file_path: PathBuf,
-
-        #[arg(help = "The name of the function that contains the code to be refactored", index = 3)]
-        caller_fn_name: String,
-
-        #[arg(help = "The name of the new function that is being extracted", index = 4)]
-        callee_fn_name: String,
-    },
-
-    // Run just the controller
-    Controller {
-        #[arg(help = "The path to the file that contains just the code that will be refactored", index = 1)]
-        file_path: PathBuf,
-
-        #[arg(help = "The path to the output file (where the refactored code ends up)", index = 2)]
-        new_file_path: PathBuf,
-
-        #[arg(help = "The name of the function that contains the code to be refactored", index = 3)]
-        caller_fn_name: String,
-
-        #[arg(help = "The name of the new function that is being extracted", index = 4)]
-        callee_fn_name: String,
-    },
-
-    //
-    Borrower {
-
-        #[arg(help = "The path to the file that contains just the code that will be refactored", index = 1)]
-        file_path: PathBuf,
-
-        #[arg(help = "The path to the output file (where the refactored code ends up)", index = 2)]
-        new_file_path: PathBuf,
-
-        #[arg(help = "The name of the function that contains the code to be refactored", index = 3)]
-        caller_fn_name: String,
-
-        #[arg(help = "The name of the new function that is being extracted", index = 4)]
-        callee_fn_name: String,
-
-        #[arg(help = "The path to the file where the mutated method will be dumped", index = 5)]
-        mut_method_file_path: PathBuf,
-
-        #[arg(help = "The path to the original file", index = 6)]
-        pre_extract_file_path: PathBuf,
-    },
-
-    Repairer {
-
-        #[arg(help = "The path to the file that contains just the code that will be refactored", index = 1)]
-        file_path: PathBuf,
-
-        #[arg(help = "The path to the output file (where the refactored code ends up)", index = 2)]
-        new_file_path: PathBuf,
-
-        #[arg(help = "The name of the function to be repaired", index = 3)]
-        fn_name: String,
-
-        /// The repairer options are as follows:
-        /// * 1 => Simple
-        /// * 2 => Loosest Bounds First
-        /// * 3 => Tightest Bounds First
-        /// * 4 => Rustfix
-        ///
-        /// Any other repairer selection will result in an error.
-        #[arg(help = "Repairer option (1=Simple, 2=Loosest Bounds First, 3=Tightest Bounds First, 4=Rustfix)", index = 4)]
-        repairer: u8,
-
-        /// The repair method to be used:
-        /// * 1 => Default
-        /// * 2 => Rustc
-        /// * 3 => Cargo
-        // TODO Implement this lol
-        // #[arg(help = "Repair Method (1=default, 2=rustc, 3=cargo)", index = 4)]
-        // repair_method: u8,
-
-        #[arg(short, long, help = "Enable verbose output", action = ArgAction::SetTrue)]
-        verbose: bool,
-    },
-
-    RepairerCargo {
-        src_path: PathBuf,
-
-        manifest_path: PathBuf,
-
-        fn_name: u8,
-
-        /// The repairer options are as follows:
-        /// * 1 => Simple
-        /// * 2 => Loosest Bounds First
-        /// * 3 => Tightest Bounds First
-        ///
-        /// Any other repairer selection will result in the program
-        /// terminating.
-        #[arg(help = "Repairer option (1=Simple, 2=Loosest Bounds First, 3=Tightest Bounds First)", index = 4)]
-        repairer: u8,
-
-        #[arg(short, long, help = "Enable verbose output", action = ArgAction::SetTrue)]
-        verbose: bool,
-    },
-
-    /// Test all repair systems against a set of input files.
-    /// Test files available from:
-    /// * https://github.com/RuleBrittonica/rem-testfiles
-    /// * https://github.com/RuleBrittonica/rem-cli => ./src_tests
-    Test {
-        #[arg(help = "Folder path containing test files")]
-        folder: PathBuf,
-
-        /// NOT YET IMPLEMENTED.
-        ///
-        /// CURRENTLY THE OUTPUT IS THE VERBOSE OUTPUT
-        #[arg(short, long, help = "Enable verbose output", action = ArgAction::SetTrue)]
-        verbose: bool
-    },
-
-    /// Same as test command, but allows you to specify a repository instead of
-    /// a folder. Test files available from:
-    /// * https://github.com/RuleBrittonica/rem-testfiles
-    TestGithub {
-        #[arg(help = "Folder path containing test files")]
-        repo: String,
-
-        /// NOT YET IMPLEMENTED.
-        ///
-        /// CURRENTLY THE OUTPUT IS THE VERBOSE OUTPUT
-        #[arg(short, long, help = "Enable verbose output", action = ArgAction::SetTrue)]
-        verbose: bool,
-    },
-
+use clap::{
+    Parser,
+    Subcommand,
+    ArgAction,
+};
+
+use std::path::PathBuf;
+
+use crate::tests::{
+    ast::CompareOptions,
+    eval::Tool,
+    harness::{
+        Suite,
+        DEFAULT_TIMEOUT_SECS,
+    },
+    report::ReportTarget,
+};
+
+use crate::messages::{
+    version::VERSION,
+    about::ABOUT,
+    author::AUTHOR,
+};
+
+#[derive(Parser)]
+#[command(
+    author = AUTHOR,
+    version = VERSION,
+    about = ABOUT
+)]
+pub struct REMArgs {
+    #[command(subcommand)]
+    pub command: REMCommands
+}
+
+#[derive(Subcommand)]
+pub enum REMCommands {
+
+    // Run the whole repairer
+    Run {
+        #[arg(help = "The path to the file that contains just the code that will be refactored", index = 1)]
+        file_path: PathBuf,
+
+        #[arg(help = "The path to the output file (where the refactored code ends up)", index = 2)]
+        new_file_path: PathBuf,
+
+        #[arg(help = "The name of the function that contains the code to be refactored", index = 3)]
+        caller_fn_name: String,
+
+        #[arg(help = "The name of the new function that is being extracted", index = 4)]
+        callee_fn_name: String,
+    },
+
+    /// Extracts a selection from a file in a Cargo project into a new
+    /// function, running every stage of the pipeline. If a stage fails, a
+    /// bundle with everything needed to reproduce the failure is written, to
+    /// be attached to bug reports.
+    Extract {
+        #[arg(help = "The path to the file that contains the selection", index = 1)]
+        file_path: PathBuf,
+
+        #[arg(help = "The path to the output file (where the refactored code ends up)", index = 2)]
+        new_file_path: PathBuf,
+
+        #[arg(help = "Start of the selection, as a byte offset into the file", index = 3)]
+        start_idx: u32,
+
+        #[arg(help = "End of the selection", index = 4)]
+        end_idx: u32,
+
+        #[arg(long, default_value = "fun_name", help = "The name of the new function that is being extracted")]
+        new_fn_name: String,
+
+        #[arg(long, value_name = "DIR", default_value = "bundles", help = "Folder bundles for failed refactorings are written to")]
+        bundles: PathBuf,
+
+        #[arg(long, help = "Don't write a bundle if the refactoring fails", action = ArgAction::SetTrue)]
+        no_bundle: bool,
+    },
+
+    /// Re-runs the refactoring in a bundle written by `extract`, and reports
+    /// whether it still fails in the same way. Exits with an error for as long
+    /// as it still fails, so it can be used to check a fix.
+    Repro {
+        #[arg(help = "Folder of the bundle")]
+        bundle: PathBuf,
+    },
+
+    // Run just the controller
+    Controller {
+        #[arg(help = "The path to the file that contains just the code that will be refactored", index = 1)]
+        file_path: PathBuf,
+
+        #[arg(help = "The path to the output file (where the refactored code ends up)", index = 2)]
+        new_file_path: PathBuf,
+
+        #[arg(help = "The name of the function that contains the code to be refactored", index = 3)]
+        caller_fn_name: String,
+
+        #[arg(help = "The name of the new function that is being extracted", index = 4)]
+        callee_fn_name: String,
+    },
+
+    //
+    Borrower {
+
+        #[arg(help = "The path to the file that contains just the code that will be refactored", index = 1)]
+        file_path: PathBuf,
+
+        #[arg(help = "The path to the output file (where the refactored code ends up)", index = 2)]
+        new_file_path: PathBuf,
+
+        #[arg(help = "The name of the function that contains the code to be refactored", index = 3)]
+        caller_fn_name: String,
+
+        #[arg(help = "The name of the new function that is being extracted", index = 4)]
+        callee_fn_name: String,
+
+        #[arg(help = "The path to the file where the mutated method will be dumped", index = 5)]
+        mut_method_file_path: PathBuf,
+
+        #[arg(help = "The path to the original file", index = 6)]
+        pre_extract_file_path: PathBuf,
+    },
+
+    Repairer {
+
+        #[arg(help = "The path to the file that contains just the code that will be refactored", index = 1)]
+        file_path: PathBuf,
+
+        #[arg(help = "The path to the output file (where the refactored code ends up)", index = 2)]
+        new_file_path: PathBuf,
+
+        #[arg(help = "The name of the function to be repaired", index = 3)]
+        fn_name: String,
+
+        /// The repairer options are as follows:
+        /// * 1 => Simple
+        /// * 2 => Loosest Bounds First
+        /// * 3 => Tightest Bounds First
+        /// * 4 => Rustfix
+        ///
+        /// Any other repairer selection will result in an error.
+        #[arg(help = "Repairer option (1=Simple, 2=Loosest Bounds First, 3=Tightest Bounds First, 4=Rustfix)", index = 4)]
+        repairer: u8,
+
+        /// The repair method to be used:
+        /// * 1 => Default
+        /// * 2 => Rustc
+        /// * 3 => Cargo
+        // TODO Implement this lol
+        // #[arg(help = "Repair Method (1=default, 2=rustc, 3=cargo)", index = 4)]
+        // repair_method: u8,
+
+        #[arg(short, long, help = "Enable verbose output", action = ArgAction::SetTrue)]
+        verbose: bool,
+    },
+
+    RepairerCargo {
+        src_path: PathBuf,
+
+        manifest_path: PathBuf,
+
+        fn_name: u8,
+
+        /// The repairer options are as follows:
+        /// * 1 => Simple
+        /// * 2 => Loosest Bounds First
+        /// * 3 => Tightest Bounds First
+        ///
+        /// Any other repairer selection will result in the program
+        /// terminating.
+        #[arg(help = "Repairer option (1=Simple, 2=Loosest Bounds First, 3=Tightest Bounds First)", index = 4)]
+        repairer: u8,
+
+        #[arg(short, long, help = "Enable verbose output", action = ArgAction::SetTrue)]
+        verbose: bool,
+    },
+
+    /// Test all repair systems against a set of input files.
+    /// Test files available from:
+    /// * https://github.com/RuleBrittonica/rem-testfiles
+    /// * https://github.com/RuleBrittonica/rem-cli => ./src_tests
+    Test {
+        #[arg(help = "Folder path containing test files")]
+        folder: PathBuf,
+
+        /// NOT YET IMPLEMENTED.
+        ///
+        /// CURRENTLY THE OUTPUT IS THE VERBOSE OUTPUT
+        #[arg(short, long, help = "Enable verbose output", action = ArgAction::SetTrue)]
+        verbose: bool,
+
+        /// Write a machine readable report of the results. Can be given more
+        /// than once, e.g. `--report junit=results.xml --report csv=results.csv`
+        #[arg(long = "report", value_name = "FORMAT=PATH", help = "Write a report as <format>=<path> (junit, json, csv or latex)")]
+        reports: Vec<ReportTarget>,
+
+        #[arg(short, long, default_value_t = 1, help = "Number of test cases to run at the same time")]
+        jobs: usize,
+
+        #[arg(long, value_name = "SECS", default_value_t = DEFAULT_TIMEOUT_SECS, help = "Seconds a single test case may run before it is killed")]
+        timeout: u64,
+
+        #[arg(long, help = "Also compare `cargo test` results before and after extraction", action = ArgAction::SetTrue)]
+        cargo_test: bool,
+
+        #[command(flatten)]
+        compare: CompareOptions,
+    },
+
+    /// Same as test command, but allows you to specify a repository instead of
+    /// a folder. Test files available from:
+    /// * https://github.com/RuleBrittonica/rem-testfiles
+    ///
+    /// The repository is cloned into a new temporary directory, which is
+    /// deleted once the tests have run.
+    #[command(alias = "test-git")]
+    TestGithub {
+        #[arg(help = "Repository containing the test files: a URL, a file:// URL, or a path to a local repository")]
+        repo: String,
+
+        #[arg(long, help = "Branch, tag or commit to test instead of the default branch")]
+        rev: Option<String>,
+
+        #[arg(long, help = "Folder inside the repository that contains the test files")]
+        subdir: Option<PathBuf>,
+
+        #[arg(long, help = "Keep the cloned repository instead of deleting it", action = ArgAction::SetTrue)]
+        keep: bool,
+
+        /// NOT YET IMPLEMENTED.
+        ///
+        /// CURRENTLY THE OUTPUT IS THE VERBOSE OUTPUT
+        #[arg(short, long, help = "Enable verbose output", action = ArgAction::SetTrue)]
+        verbose: bool,
+
+        /// Write a machine readable report of the results. Can be given more
+        /// than once, e.g. `--report junit=results.xml --report csv=results.csv`
+        #[arg(long = "report", value_name = "FORMAT=PATH", help = "Write a report as <format>=<path> (junit, json, csv or latex)")]
+        reports: Vec<ReportTarget>,
+
+        #[arg(short, long, default_value_t = 1, help = "Number of test cases to run at the same time")]
+        jobs: usize,
+
+        #[arg(long, value_name = "SECS", default_value_t = DEFAULT_TIMEOUT_SECS, help = "Seconds a single test case may run before it is killed")]
+        timeout: u64,
+
+        #[arg(long, help = "Also compare `cargo test` results before and after extraction", action = ArgAction::SetTrue)]
+        cargo_test: bool,
+
+        #[command(flatten)]
+        compare: CompareOptions,
+    },
+
+    /// Runs the pipeline on randomly chosen statement and expression
+    /// selections from the fixture programs. Selections that make REM panic or
+    /// hang, or whose output doesn't compile or behaves differently, are saved
+    /// as new fixtures.
+    Fuzz {
+        #[arg(help = "Folder containing the fixture programs (a test folder, or a folder of Cargo projects)")]
+        folder: PathBuf,
+
+        #[arg(short = 'n', long, default_value_t = 100, help = "Number of selections to try")]
+        iterations: usize,
+
+        #[arg(long, help = "Seed for choosing selections. Defaults to one taken from the clock")]
+        seed: Option<u64>,
+
+        #[arg(short, long, default_value_t = 1, help = "Number of selections to run at the same time")]
+        jobs: usize,
+
+        #[arg(long, value_name = "SECS", default_value_t = DEFAULT_TIMEOUT_SECS, help = "Seconds a single selection may run before it is counted as a hang")]
+        timeout: u64,
+
+        #[arg(long, help = "Test folder flagged selections are added to, under extract/input. Defaults to <FOLDER> if it is a test folder, otherwise <FOLDER>/fuzz")]
+        out: Option<PathBuf>,
+
+        #[arg(long = "report", value_name = "FORMAT=PATH", help = "Write a report as <format>=<path> (junit, json, csv or latex)")]
+        reports: Vec<ReportTarget>,
+    },
+
+    /// Shrinks a program whose selection fails in the pipeline, removing items
+    /// and statements for as long as it keeps failing in the same way. The
+    /// result is written out as a fixture for the extract suite.
+    Reduce {
+        #[arg(help = "Cargo project containing the failing program in src/main.rs (e.g. a fixture saved by `fuzz`)")]
+        project: PathBuf,
+
+        #[arg(requires = "end_idx", help = "Start of the selection. Read from the project's selection.json if not given")]
+        start_idx: Option<u32>,
+
+        #[arg(help = "End of the selection")]
+        end_idx: Option<u32>,
+
+        #[arg(long, help = "Name of the reduced fixture. Defaults to <PROJECT>_reduced")]
+        name: Option<String>,
+
+        #[arg(long, default_value = "src_tests", help = "Test folder the reduced fixture is added to, under extract/input")]
+        out: PathBuf,
+
+        #[arg(short, long, default_value_t = 1, help = "Number of smaller programs to try at the same time")]
+        jobs: usize,
+
+        #[arg(long, value_name = "SECS", default_value_t = DEFAULT_TIMEOUT_SECS, help = "Seconds a single attempt may run before it is counted as a hang")]
+        timeout: u64,
+    },
+
+    /// Times each stage of the pipeline, and the pipeline as a whole, on every
+    /// extract fixture. The results can be saved as a baseline, and compared
+    /// with a previous one to catch slowdowns.
+    Bench {
+        #[arg(help = "Folder path containing test files")]
+        folder: PathBuf,
+
+        #[arg(short = 'n', long, default_value_t = 5, help = "Number of timed runs of each fixture")]
+        iterations: usize,
+
+        #[arg(long, default_value_t = 1, help = "Number of untimed runs of each fixture before the timed ones")]
+        warmup: usize,
+
+        #[arg(long = "case", value_name = "NAME", help = "Only benchmark this fixture (can be given more than once)")]
+        cases: Vec<String>,
+
+        #[arg(long, value_name = "FILE", help = "Save the results as JSON, to be used as a baseline later")]
+        save: Option<PathBuf>,
+
+        #[arg(long, value_name = "FILE", help = "Compare the results with a baseline saved by --save")]
+        baseline: Option<PathBuf>,
+
+        #[arg(long, value_name = "PERCENT", default_value_t = 10.0, help = "How much slower a stage's median may get before it counts as a regression")]
+        threshold: f64,
+
+        #[arg(long, value_name = "SECS", default_value_t = DEFAULT_TIMEOUT_SECS, help = "Seconds all the runs of a single fixture may take before they are killed")]
+        timeout: u64,
+
+        #[arg(long = "report", value_name = "FORMAT=PATH", help = "Write a report as <format>=<path> (json, csv or latex)")]
+        reports: Vec<ReportTarget>,
+    },
+
+    /// Runs the pipeline on every input/expected pair in the evaluation set,
+    /// and reports whether each output compiles, behaves the same as the input,
+    /// and matches the expected output. With `--baseline`, rust-analyzer's own
+    /// extract function is evaluated too, and shown next to REM.
+    Eval {
+        #[arg(default_value = "eval", help = "Folder containing the extraction/ and verification/ folders")]
+        folder: PathBuf,
+
+        #[arg(short, long, default_value_t = 1, help = "Number of pairs to evaluate at the same time")]
+        jobs: usize,
+
+        #[arg(long, value_name = "SECS", default_value_t = DEFAULT_TIMEOUT_SECS, help = "Seconds a single pair may run before it is killed")]
+        timeout: u64,
+
+        #[arg(long = "report", value_name = "FORMAT=PATH", help = "Write a report as <format>=<path> (junit, json, csv or latex)")]
+        reports: Vec<ReportTarget>,
+
+        #[arg(long, help = "Also run rust-analyzer's extract function on every pair, as a baseline for REM", action = ArgAction::SetTrue)]
+        baseline: bool,
+
+        #[arg(long, help = "Don't run the equivalence harnesses under Miri, even if it is installed", action = ArgAction::SetTrue)]
+        no_miri: bool,
+
+        #[arg(long, help = "Save every pair's results in the results folder, with the versions of the rem crates", action = ArgAction::SetTrue)]
+        record: bool,
+
+        #[arg(long = "compare", num_args = 2, value_names = ["A", "B"], conflicts_with = "record", help = "Instead of evaluating, list the pairs that changed between two recorded runs (an id, a path, latest or latest~N)")]
+        runs: Option<Vec<String>>,
+
+        #[arg(long, value_name = "DIR", help = "Folder recorded runs are saved in [default: <FOLDER>/results]")]
+        results: Option<PathBuf>,
+
+        #[command(flatten)]
+        compare: CompareOptions,
+    },
+
+    /// Replays extract-method refactorings mined from git history: each
+    /// selection is extracted from the code before the refactoring, under the
+    /// developer's function name, and compared with what the developer wrote.
+    Replay {
+        #[arg(help = "CSV file of mined cases, with the columns written by scraping/0_scraper.py")]
+        cases: PathBuf,
+
+        #[arg(long, help = "Folder containing a local clone of each repository in the cases file")]
+        repos: PathBuf,
+
+        #[arg(short, long, default_value_t = 1, help = "Number of cases to replay at the same time")]
+        jobs: usize,
+
+        #[arg(long, value_name = "SECS", default_value_t = DEFAULT_TIMEOUT_SECS, help = "Seconds a single case may run before it is killed")]
+        timeout: u64,
+
+        #[arg(long = "report", value_name = "FORMAT=PATH", help = "Write a report as <format>=<path> (junit, json, csv or latex)")]
+        reports: Vec<ReportTarget>,
+
+        #[command(flatten)]
+        compare: CompareOptions,
+    },
+
+    /// Mines a repository's history for extract-method refactorings, and
+    /// appends them to a CSV file that `replay` can read.
+    Mine {
+        #[arg(help = "Local clone of the repository to mine")]
+        repo: PathBuf,
+
+        #[arg(short, long, default_value = "scraping/extracts.csv", help = "CSV file the cases are appended to")]
+        out: PathBuf,
+
+        #[arg(long, default_value_t = 500, help = "Number of commits back from HEAD to look at")]
+        depth: usize,
+
+        #[arg(long, default_value_t = 20, help = "Most cases to record for the repository")]
+        max_cases: usize,
+
+        #[arg(long, value_name = "PERCENT", default_value_t = 60, help = "How much of the removed code has to reappear in the new function")]
+        min_match: usize,
+    },
+
+    /// Turns mined cases into fixtures for the extract suite: the package the
+    /// selection is in, with a manifest that builds on its own, a selection
+    /// manifest, and the developer's version of the file as the expected output.
+    ImportCase {
+        #[arg(help = "CSV file of mined cases, with the columns written by scraping/0_scraper.py")]
+        cases: PathBuf,
+
+        #[arg(long, help = "Folder containing a local clone of each repository in the cases file")]
+        repos: PathBuf,
+
+        #[arg(long = "case", value_name = "NAME", help = "Only import this case (can be given more than once)")]
+        names: Vec<String>,
+
+        #[arg(long, default_value = "src_tests", help = "Test folder the fixtures are written to")]
+        out: PathBuf,
+
+        #[arg(long, action = ArgAction::SetTrue, help = "Replace fixtures that already exist")]
+        force: bool,
+    },
+
+    /// Summarises a file of mined cases: how large the extracted regions are,
+    /// how many parameters the extracted functions take, which features they
+    /// use, and how many cases came from each repository.
+    CorpusStats {
+        #[arg(help = "CSV file of mined cases, with the columns written by scraping/0_scraper.py")]
+        cases: PathBuf,
+
+        #[arg(long, help = "Folder containing a local clone of each repository in the cases file")]
+        repos: PathBuf,
+
+        #[arg(long, value_name = "FOLDER", help = "Test folder whose extract fixtures are shown next to the mined cases")]
+        fixtures: Option<PathBuf>,
+    },
+
+    /// Runs the pipeline on a single selection in the current directory and
+    /// writes the result to a file. Used by `fuzz` and `reduce`.
+    #[command(hide = true)]
+    FuzzCase {
+        fixture: PathBuf,
+
+        start_idx: u32,
+
+        end_idx: u32,
+
+        #[arg(long)]
+        result: PathBuf,
+    },
+
+    /// Evaluates a single input/expected pair in the current directory and
+    /// writes the result to a file. Used by `eval`.
+    #[command(hide = true)]
+    EvalCase {
+        input: PathBuf,
+
+        expected: PathBuf,
+
+        start_idx: u32,
+
+        end_idx: u32,
+
+        #[arg(long)]
+        verification: Option<PathBuf>,
+
+        #[arg(long, value_enum, default_value_t = Tool::Rem)]
+        tool: Tool,
+
+        #[arg(long, action = ArgAction::SetTrue)]
+        no_miri: bool,
+
+        #[arg(long, default_value_t = DEFAULT_TIMEOUT_SECS)]
+        timeout: u64,
+
+        #[arg(long)]
+        result: PathBuf,
+
+        #[command(flatten)]
+        compare: CompareOptions,
+    },
+
+    /// Replays a single mined case on a checked out copy of its repository and
+    /// writes the result to a file. Used by `replay`.
+    #[command(hide = true)]
+    ReplayCase {
+        name: String,
+
+        input: PathBuf,
+
+        expected: PathBuf,
+
+        fn_name: String,
+
+        start_line: usize,
+
+        end_line: usize,
+
+        #[arg(long)]
+        result: PathBuf,
+
+        #[command(flatten)]
+        compare: CompareOptions,
+    },
+
+    /// Times the pipeline on a single extract fixture in the current directory
+    /// and writes the timings to a file. Used by `bench`.
+    #[command(hide = true)]
+    BenchCase {
+        folder: PathBuf,
+
+        case: String,
+
+        #[arg(long)]
+        warmup: usize,
+
+        #[arg(long)]
+        iterations: usize,
+
+        #[arg(long)]
+        result: PathBuf,
+    },
+
+    /// Runs a single test case in the current directory and writes the result
+    /// to a file. Used by `test` to run each case in its own scratch directory.
+    #[command(hide = true)]
+    TestCase {
+        #[arg(value_enum)]
+        suite: Suite,
+
+        folder: PathBuf,
+
+        case: String,
+
+        #[arg(long)]
+        result: PathBuf,
+
+        #[arg(long, action = ArgAction::SetTrue)]
+        cargo_test: bool,
+
+        #[command(flatten)]
+        compare: CompareOptions,
+    },
+
 }
\ No newline at end of file
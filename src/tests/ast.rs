@@ -0,0 +1,690 @@
+use std::collections::HashMap;
+
+use clap::{
+    ArgAction,
+    Args,
+};
+
+use proc_macro2::{
+    Delimiter,
+    Group,
+    Ident,
+    TokenStream,
+    TokenTree,
+};
+
+use quote::ToTokens;
+
+use rem_utils::parse_and_compare_ast;
+
+use syn::{
+    visit_mut::{
+        self,
+        VisitMut,
+    },
+    Arm,
+    Attribute,
+    Block,
+    ConstParam,
+    ExprClosure,
+    ExprForLoop,
+    ExprIf,
+    ExprPath,
+    ExprWhile,
+    Field,
+    FieldPat,
+    FieldValue,
+    GenericParam,
+    Generics,
+    ImplItem,
+    ImplItemFn,
+    Item,
+    ItemEnum,
+    ItemFn,
+    ItemImpl,
+    ItemStruct,
+    ItemTrait,
+    ItemType,
+    Lifetime,
+    Local,
+    Macro,
+    PatIdent,
+    Signature,
+    TraitItem,
+    TraitItemFn,
+    TypeParam,
+    TypePath,
+    Variant,
+};
+
+/// Lifetimes that mean the same thing wherever they appear, and so are never
+/// renamed.
+const FIXED_LIFETIMES: [&str; 2] = ["static", "_"];
+
+/// Renames every named lifetime to `'l0`, `'l1`, ... in the order they are
+/// first seen, so that two items which only differ in their choice of
+/// lifetime names compare equal.
+#[derive(Default)]
+struct LifetimeRenamer {
+    names: HashMap<String, String>,
+}
+
+impl VisitMut for LifetimeRenamer {
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        let name: String = lifetime.ident.to_string();
+        if FIXED_LIFETIMES.contains(&name.as_str()) {
+            return;
+        }
+
+        let next: usize = self.names.len();
+        let renamed: &String = self.names
+            .entry(name)
+            .or_insert_with(|| format!("l{}", next));
+        lifetime.ident = Ident::new(renamed, lifetime.ident.span());
+    }
+}
+
+/// Parses a function signature, e.g. `fn foo<'a>(x: &'a i32) -> &'a i32`. A
+/// trailing body or `{` is ignored, so signatures copied straight out of a
+/// source file can be used.
+pub fn parse_signature(signature: &str) -> Result<Signature, syn::Error> {
+    let signature: &str = match signature.find('{') {
+        Some(body) => &signature[..body],
+        None => signature,
+    };
+    syn::parse_str::<Signature>(signature.trim().trim_end_matches(';'))
+}
+
+/// Finds the signature of the function called `fn_name` in `source`, looking
+/// at free functions, methods in `impl` blocks and inline modules.
+pub fn find_signature(source: &str, fn_name: &str) -> Result<Option<Signature>, syn::Error> {
+    let file: syn::File = syn::parse_file(source)?;
+    Ok(find_in_items(&file.items, fn_name).map(|found| found.sig().clone()))
+}
+
+/// Finds the function called `fn_name` in `source`, in the same places as
+/// `find_signature`, and prints it back out on its own so that it can be
+/// compared with `compare_ast`.
+pub fn find_fn(source: &str, fn_name: &str) -> Result<Option<String>, syn::Error> {
+    let file: syn::File = syn::parse_file(source)?;
+    Ok(find_in_items(&file.items, fn_name).map(|found| match found {
+        FoundFn::Free(item_fn) => item_fn.to_token_stream().to_string(),
+        FoundFn::Method(method) => method.to_token_stream().to_string(),
+    }))
+}
+
+/// A function found by name: either a free function or a method.
+enum FoundFn<'a> {
+    Free(&'a ItemFn),
+    Method(&'a ImplItemFn),
+}
+
+impl FoundFn<'_> {
+    fn sig(&self) -> &Signature {
+        match self {
+            FoundFn::Free(item_fn) => &item_fn.sig,
+            FoundFn::Method(method) => &method.sig,
+        }
+    }
+}
+
+fn find_in_items<'a>(items: &'a [Item], fn_name: &str) -> Option<FoundFn<'a>> {
+    for item in items {
+        match item {
+            Item::Fn(item_fn) if item_fn.sig.ident == fn_name => {
+                return Some(FoundFn::Free(item_fn));
+            }
+            Item::Impl(item_impl) => {
+                for impl_item in &item_impl.items {
+                    if let ImplItem::Fn(method) = impl_item {
+                        if method.sig.ident == fn_name {
+                            return Some(FoundFn::Method(method));
+                        }
+                    }
+                }
+            }
+            Item::Mod(item_mod) => {
+                if let Some((_, items)) = &item_mod.content {
+                    if let Some(found) = find_in_items(items, fn_name) {
+                        return Some(found);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Renders a signature with its lifetimes renamed by position. Two signatures
+/// are equal modulo lifetime renaming if their normalised forms are equal.
+pub fn normalise_signature(signature: &Signature) -> String {
+    let mut signature: Signature = signature.clone();
+    LifetimeRenamer::default().visit_signature_mut(&mut signature);
+    signature.to_token_stream().to_string()
+}
+
+/// Compares two signatures modulo lifetime renaming.
+pub fn signatures_match(actual: &Signature, expected: &Signature) -> bool {
+    normalise_signature(actual) == normalise_signature(expected)
+}
+
+/// How strictly two files are compared by `compare_ast`. With every option
+/// off, this is exactly `parse_and_compare_ast`.
+/// - alpha: Treat programs that only differ in the names of lifetimes,
+///   generic parameters and local bindings (e.g. the parameters REM generates
+///   for an extracted function) as equal
+/// - ignore_comments: Ignore doc comments. Plain comments are always ignored
+/// - ignore_attr_order: Ignore the order of attributes on an item
+/// - ignore_trailing_commas: Ignore trailing commas in any list
+#[derive(Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompareOptions {
+    #[arg(long, help = "Compare outputs modulo renaming of lifetimes, generics and local bindings", action = ArgAction::SetTrue)]
+    pub alpha: bool,
+
+    #[arg(long, help = "Ignore doc comments when comparing outputs", action = ArgAction::SetTrue)]
+    pub ignore_comments: bool,
+
+    #[arg(long, help = "Ignore the order of attributes when comparing outputs", action = ArgAction::SetTrue)]
+    pub ignore_attr_order: bool,
+
+    #[arg(long, help = "Ignore trailing commas when comparing outputs", action = ArgAction::SetTrue)]
+    pub ignore_trailing_commas: bool,
+}
+
+impl CompareOptions {
+    fn is_strict(&self) -> bool {
+        *self == CompareOptions::default()
+    }
+
+    /// The command line flags that recreate these options, for passing on to
+    /// the `test-case` subprocesses.
+    pub fn to_args(self) -> Vec<&'static str> {
+        let mut args: Vec<&'static str> = Vec::new();
+        if self.alpha {
+            args.push("--alpha");
+        }
+        if self.ignore_comments {
+            args.push("--ignore-comments");
+        }
+        if self.ignore_attr_order {
+            args.push("--ignore-attr-order");
+        }
+        if self.ignore_trailing_commas {
+            args.push("--ignore-trailing-commas");
+        }
+        args
+    }
+}
+
+/// Compares the ASTs of two source files. Both files are normalised according
+/// to `options` and then handed to `parse_and_compare_ast`.
+pub fn compare_ast(actual: &str, expected: &str, options: &CompareOptions) -> Result<bool, String> {
+    if options.is_strict() {
+        return parse_and_compare_ast(actual, expected).map_err(|e| e.to_string());
+    }
+
+    let actual: String = normalise_source(actual, options).map_err(|e| e.to_string())?;
+    let expected: String = normalise_source(expected, options).map_err(|e| e.to_string())?;
+    parse_and_compare_ast(&actual, &expected).map_err(|e| e.to_string())
+}
+
+/// Parses `source`, applies the normalisations asked for in `options`, and
+/// prints it back out.
+pub fn normalise_source(source: &str, options: &CompareOptions) -> Result<String, syn::Error> {
+    let file: syn::File = normalise_file(source, options)?;
+    Ok(render(&file, options))
+}
+
+/// Parses `source` and applies the normalisations asked for in `options`
+/// that work on the syntax tree. Spans still point into `source`. Trailing
+/// commas are only dropped when the tree is rendered, by `render`.
+pub fn normalise_file(source: &str, options: &CompareOptions) -> Result<syn::File, syn::Error> {
+    let mut file: syn::File = syn::parse_file(source)?;
+
+    if options.ignore_comments || options.ignore_attr_order {
+        AttributeNormaliser {
+            strip_docs: options.ignore_comments,
+            sort: options.ignore_attr_order,
+        }.visit_file_mut(&mut file);
+    }
+
+    if options.alpha {
+        AlphaRenamer::default().visit_file_mut(&mut file);
+    }
+
+    Ok(file)
+}
+
+/// Prints any node of a (normalised) tree, dropping trailing commas if asked
+/// to. Two nodes are considered equal if they render the same.
+pub fn render(node: &impl ToTokens, options: &CompareOptions) -> String {
+    let mut tokens: TokenStream = node.to_token_stream();
+    if options.ignore_trailing_commas {
+        tokens = strip_trailing_commas(tokens);
+    }
+    tokens.to_string()
+}
+
+/// Removes doc attributes (which is what `///` and `//!` comments become once
+/// parsed) and/or sorts the remaining attributes of every item, field and
+/// variant.
+struct AttributeNormaliser {
+    strip_docs: bool,
+    sort: bool,
+}
+
+impl AttributeNormaliser {
+    fn normalise(&self, attrs: &mut Vec<Attribute>) {
+        if self.strip_docs {
+            attrs.retain(|attr| !attr.path().is_ident("doc"));
+        }
+        if self.sort {
+            attrs.sort_by_cached_key(|attr| attr.to_token_stream().to_string());
+        }
+    }
+}
+
+impl VisitMut for AttributeNormaliser {
+    fn visit_file_mut(&mut self, file: &mut syn::File) {
+        self.normalise(&mut file.attrs);
+        visit_mut::visit_file_mut(self, file);
+    }
+
+    fn visit_item_mut(&mut self, item: &mut Item) {
+        if let Some(attrs) = item_attrs(item) {
+            self.normalise(attrs);
+        }
+        visit_mut::visit_item_mut(self, item);
+    }
+
+    fn visit_impl_item_mut(&mut self, item: &mut ImplItem) {
+        match item {
+            ImplItem::Fn(item) => self.normalise(&mut item.attrs),
+            ImplItem::Const(item) => self.normalise(&mut item.attrs),
+            ImplItem::Type(item) => self.normalise(&mut item.attrs),
+            ImplItem::Macro(item) => self.normalise(&mut item.attrs),
+            _ => {}
+        }
+        visit_mut::visit_impl_item_mut(self, item);
+    }
+
+    fn visit_trait_item_mut(&mut self, item: &mut TraitItem) {
+        match item {
+            TraitItem::Fn(item) => self.normalise(&mut item.attrs),
+            TraitItem::Const(item) => self.normalise(&mut item.attrs),
+            TraitItem::Type(item) => self.normalise(&mut item.attrs),
+            TraitItem::Macro(item) => self.normalise(&mut item.attrs),
+            _ => {}
+        }
+        visit_mut::visit_trait_item_mut(self, item);
+    }
+
+    fn visit_field_mut(&mut self, field: &mut Field) {
+        self.normalise(&mut field.attrs);
+        visit_mut::visit_field_mut(self, field);
+    }
+
+    fn visit_variant_mut(&mut self, variant: &mut Variant) {
+        self.normalise(&mut variant.attrs);
+        visit_mut::visit_variant_mut(self, variant);
+    }
+}
+
+fn item_attrs(item: &mut Item) -> Option<&mut Vec<Attribute>> {
+    match item {
+        Item::Const(item) => Some(&mut item.attrs),
+        Item::Enum(item) => Some(&mut item.attrs),
+        Item::ExternCrate(item) => Some(&mut item.attrs),
+        Item::Fn(item) => Some(&mut item.attrs),
+        Item::ForeignMod(item) => Some(&mut item.attrs),
+        Item::Impl(item) => Some(&mut item.attrs),
+        Item::Macro(item) => Some(&mut item.attrs),
+        Item::Mod(item) => Some(&mut item.attrs),
+        Item::Static(item) => Some(&mut item.attrs),
+        Item::Struct(item) => Some(&mut item.attrs),
+        Item::Trait(item) => Some(&mut item.attrs),
+        Item::TraitAlias(item) => Some(&mut item.attrs),
+        Item::Type(item) => Some(&mut item.attrs),
+        Item::Union(item) => Some(&mut item.attrs),
+        Item::Use(item) => Some(&mut item.attrs),
+        _ => None,
+    }
+}
+
+/// Renames bound names by their binding position, so that alpha-equivalent
+/// programs print the same:
+/// - Lifetimes and generic parameters become `'l<n>` and `T<n>`, numbered in
+///   the order their binders are seen.
+/// - Local bindings (function parameters, `let`, closure parameters, match
+///   arms, `for` patterns, ...) become `v<n>`.
+///
+/// References are resolved to the innermost binding in scope. Item names are
+/// never renamed, and nor are bindings that start with an upper case letter,
+/// since `syn` can't tell those apart from unit structs, constants and enum
+/// variants in patterns.
+#[derive(Default)]
+struct AlphaRenamer {
+    scopes: Vec<HashMap<String, String>>,
+    lifetimes: Vec<HashMap<String, String>>,
+    next_generic: usize,
+    next_lifetime: usize,
+    next_local: usize,
+    /// Free (undeclared) lifetimes are renamed by first use, as in
+    /// `normalise_signature`.
+    free_lifetimes: HashMap<String, String>,
+}
+
+impl AlphaRenamer {
+    /// Runs `visit` with the parameters of `generics` bound. The generics
+    /// themselves are visited inside the scope so their declarations are
+    /// renamed too.
+    fn with_generics(&mut self, generics: &Generics, visit: impl FnOnce(&mut Self)) {
+        let mut types: HashMap<String, String> = HashMap::new();
+        let mut lifetimes: HashMap<String, String> = HashMap::new();
+        for param in &generics.params {
+            match param {
+                GenericParam::Lifetime(param) => {
+                    lifetimes.insert(param.lifetime.ident.to_string(), format!("l{}", self.next_lifetime));
+                    self.next_lifetime += 1;
+                }
+                GenericParam::Type(param) => {
+                    types.insert(param.ident.to_string(), format!("T{}", self.next_generic));
+                    self.next_generic += 1;
+                }
+                GenericParam::Const(param) => {
+                    types.insert(param.ident.to_string(), format!("C{}", self.next_generic));
+                    self.next_generic += 1;
+                }
+            }
+        }
+
+        self.scopes.push(types);
+        self.lifetimes.push(lifetimes);
+        visit(self);
+        self.lifetimes.pop();
+        self.scopes.pop();
+    }
+
+    /// Runs `visit` in a new scope for local bindings.
+    fn with_scope(&mut self, visit: impl FnOnce(&mut Self)) {
+        self.scopes.push(HashMap::new());
+        visit(self);
+        self.scopes.pop();
+    }
+
+    fn bind(&mut self, ident: &mut Ident) {
+        let name: String = ident.to_string();
+        if name.starts_with(|c: char| c.is_uppercase()) || name == "self" {
+            return;
+        }
+
+        let renamed: String = format!("v{}", self.next_local);
+        self.next_local += 1;
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, renamed.clone());
+        }
+        *ident = Ident::new(&renamed, ident.span());
+    }
+
+    fn resolve(&self, name: &str) -> Option<&String> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Like `resolve`, for the first segment of a path (`T::default`), which
+    /// can be a generic parameter but never a local binding. A local that
+    /// happens to share its name with a module (`io::stdin`) is left alone.
+    fn resolve_generic(&self, name: &str) -> Option<&String> {
+        self.resolve(name).filter(|renamed| !renamed.starts_with('v'))
+    }
+
+    /// Macro bodies aren't parsed by `syn`, so bound names used in them (e.g.
+    /// `println!("{}", x)`) are renamed token by token.
+    fn rename_tokens(&self, tokens: TokenStream) -> TokenStream {
+        tokens
+            .into_iter()
+            .map(|token| match token {
+                TokenTree::Ident(ident) => match self.resolve(&ident.to_string()) {
+                    Some(renamed) => TokenTree::Ident(Ident::new(renamed, ident.span())),
+                    None => TokenTree::Ident(ident),
+                },
+                TokenTree::Group(group) => {
+                    let mut renamed: Group = Group::new(group.delimiter(), self.rename_tokens(group.stream()));
+                    renamed.set_span(group.span());
+                    TokenTree::Group(renamed)
+                }
+                other => other,
+            })
+            .collect()
+    }
+}
+
+impl VisitMut for AlphaRenamer {
+    fn visit_item_fn_mut(&mut self, item: &mut ItemFn) {
+        let generics: Generics = item.sig.generics.clone();
+        self.with_generics(&generics, |this| this.with_scope(|this| visit_mut::visit_item_fn_mut(this, item)));
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, item: &mut ImplItemFn) {
+        let generics: Generics = item.sig.generics.clone();
+        self.with_generics(&generics, |this| this.with_scope(|this| visit_mut::visit_impl_item_fn_mut(this, item)));
+    }
+
+    fn visit_trait_item_fn_mut(&mut self, item: &mut TraitItemFn) {
+        let generics: Generics = item.sig.generics.clone();
+        self.with_generics(&generics, |this| this.with_scope(|this| visit_mut::visit_trait_item_fn_mut(this, item)));
+    }
+
+    fn visit_item_impl_mut(&mut self, item: &mut ItemImpl) {
+        let generics: Generics = item.generics.clone();
+        self.with_generics(&generics, |this| visit_mut::visit_item_impl_mut(this, item));
+    }
+
+    fn visit_item_struct_mut(&mut self, item: &mut ItemStruct) {
+        let generics: Generics = item.generics.clone();
+        self.with_generics(&generics, |this| visit_mut::visit_item_struct_mut(this, item));
+    }
+
+    fn visit_item_enum_mut(&mut self, item: &mut ItemEnum) {
+        let generics: Generics = item.generics.clone();
+        self.with_generics(&generics, |this| visit_mut::visit_item_enum_mut(this, item));
+    }
+
+    fn visit_item_trait_mut(&mut self, item: &mut ItemTrait) {
+        let generics: Generics = item.generics.clone();
+        self.with_generics(&generics, |this| visit_mut::visit_item_trait_mut(this, item));
+    }
+
+    fn visit_item_type_mut(&mut self, item: &mut ItemType) {
+        let generics: Generics = item.generics.clone();
+        self.with_generics(&generics, |this| visit_mut::visit_item_type_mut(this, item));
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        self.with_scope(|this| visit_mut::visit_block_mut(this, block));
+    }
+
+    fn visit_expr_closure_mut(&mut self, closure: &mut ExprClosure) {
+        self.with_scope(|this| visit_mut::visit_expr_closure_mut(this, closure));
+    }
+
+    fn visit_arm_mut(&mut self, arm: &mut Arm) {
+        self.with_scope(|this| visit_mut::visit_arm_mut(this, arm));
+    }
+
+    fn visit_expr_for_loop_mut(&mut self, expr: &mut ExprForLoop) {
+        // The iterator is evaluated outside of the loop's bindings
+        self.visit_expr_mut(&mut expr.expr);
+        self.with_scope(|this| {
+            this.visit_pat_mut(&mut expr.pat);
+            this.visit_block_mut(&mut expr.body);
+        });
+    }
+
+    fn visit_expr_if_mut(&mut self, expr: &mut ExprIf) {
+        // `if let` bindings are only visible in the then branch
+        self.with_scope(|this| {
+            this.visit_expr_mut(&mut expr.cond);
+            this.visit_block_mut(&mut expr.then_branch);
+        });
+        if let Some((_, else_branch)) = &mut expr.else_branch {
+            self.visit_expr_mut(else_branch);
+        }
+    }
+
+    fn visit_expr_while_mut(&mut self, expr: &mut ExprWhile) {
+        self.with_scope(|this| visit_mut::visit_expr_while_mut(this, expr));
+    }
+
+    fn visit_local_mut(&mut self, local: &mut Local) {
+        // The initialiser can't see the names the `let` binds
+        if let Some(init) = &mut local.init {
+            self.visit_local_init_mut(init);
+        }
+        self.visit_pat_mut(&mut local.pat);
+    }
+
+    fn visit_pat_ident_mut(&mut self, pat: &mut PatIdent) {
+        if let Some((_, subpat)) = &mut pat.subpat {
+            self.visit_pat_mut(subpat);
+        }
+        self.bind(&mut pat.ident);
+    }
+
+    fn visit_field_pat_mut(&mut self, field: &mut FieldPat) {
+        // `S { x }` has to become `S { x: v0 }` to keep the field name
+        field.colon_token.get_or_insert_with(Default::default);
+        visit_mut::visit_field_pat_mut(self, field);
+    }
+
+    fn visit_field_value_mut(&mut self, field: &mut FieldValue) {
+        field.colon_token.get_or_insert_with(Default::default);
+        visit_mut::visit_field_value_mut(self, field);
+    }
+
+    fn visit_expr_path_mut(&mut self, expr: &mut ExprPath) {
+        if expr.qself.is_none() && expr.path.leading_colon.is_none() {
+            let single: bool = expr.path.segments.len() == 1;
+            if let Some(segment) = expr.path.segments.first_mut() {
+                let name: String = segment.ident.to_string();
+                let renamed: Option<&String> = if single { self.resolve(&name) } else { self.resolve_generic(&name) };
+                if let Some(renamed) = renamed {
+                    segment.ident = Ident::new(renamed, segment.ident.span());
+                }
+            }
+        }
+        visit_mut::visit_expr_path_mut(self, expr);
+    }
+
+    fn visit_type_path_mut(&mut self, ty: &mut TypePath) {
+        if ty.qself.is_none() && ty.path.leading_colon.is_none() {
+            if let Some(segment) = ty.path.segments.first_mut() {
+                if let Some(renamed) = self.resolve_generic(&segment.ident.to_string()) {
+                    segment.ident = Ident::new(renamed, segment.ident.span());
+                }
+            }
+        }
+        visit_mut::visit_type_path_mut(self, ty);
+    }
+
+    fn visit_type_param_mut(&mut self, param: &mut TypeParam) {
+        if let Some(renamed) = self.resolve(&param.ident.to_string()) {
+            param.ident = Ident::new(renamed, param.ident.span());
+        }
+        visit_mut::visit_type_param_mut(self, param);
+    }
+
+    fn visit_const_param_mut(&mut self, param: &mut ConstParam) {
+        if let Some(renamed) = self.resolve(&param.ident.to_string()) {
+            param.ident = Ident::new(renamed, param.ident.span());
+        }
+        visit_mut::visit_const_param_mut(self, param);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        let name: String = lifetime.ident.to_string();
+        if FIXED_LIFETIMES.contains(&name.as_str()) {
+            return;
+        }
+
+        let bound: Option<String> = self.lifetimes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name))
+            .cloned();
+        let renamed: String = match bound {
+            Some(renamed) => renamed,
+            None => {
+                let next: usize = self.free_lifetimes.len();
+                self.free_lifetimes
+                    .entry(name)
+                    .or_insert_with(|| format!("free{}", next))
+                    .clone()
+            }
+        };
+        lifetime.ident = Ident::new(&renamed, lifetime.ident.span());
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut Macro) {
+        mac.tokens = self.rename_tokens(mac.tokens.clone());
+        visit_mut::visit_macro_mut(self, mac);
+    }
+}
+
+/// Drops a `,` that is the last token in any delimited group, except where
+/// the comma is what makes the group a one-element tuple: `(x,)` and `(x)`
+/// are different types and expressions, while `[x,]` and `[x]` are not.
+fn strip_trailing_commas(tokens: TokenStream) -> TokenStream {
+    strip_trailing_commas_in(tokens, Delimiter::None)
+}
+
+/// `strip_trailing_commas` for the contents of a group delimited by
+/// `delimiter`.
+fn strip_trailing_commas_in(tokens: TokenStream, delimiter: Delimiter) -> TokenStream {
+    let mut tokens: Vec<TokenTree> = tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => {
+                let mut stripped: Group = Group::new(
+                    group.delimiter(),
+                    strip_trailing_commas_in(group.stream(), group.delimiter()),
+                );
+                stripped.set_span(group.span());
+                TokenTree::Group(stripped)
+            }
+            other => other,
+        })
+        .collect();
+
+    if matches!(tokens.last(), Some(TokenTree::Punct(punct)) if punct.as_char() == ',') {
+        let one_tuple: bool = delimiter == Delimiter::Parenthesis
+            && !has_separator(&tokens[..tokens.len() - 1]);
+        if !one_tuple {
+            tokens.pop();
+        }
+    }
+    tokens.into_iter().collect()
+}
+
+/// Whether `tokens` contain a `,` that separates elements, i.e. one that isn't
+/// inside a `<...>` (as in `HashMap<K, V>`). A `<` that is really "less than"
+/// only makes this answer `false`, which keeps the comma it is deciding on.
+fn has_separator(tokens: &[TokenTree]) -> bool {
+    let mut depth: usize = 0;
+    let mut previous: Option<char> = None;
+    for token in tokens {
+        let TokenTree::Punct(punct) = token else {
+            previous = None;
+            continue;
+        };
+        match punct.as_char() {
+            '<' => depth += 1,
+            // The `>` of `->` and `=>` doesn't close anything.
+            '>' if !matches!(previous, Some('-' | '=')) => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => return true,
+            _ => {}
+        }
+        previous = Some(punct.as_char());
+    }
+    false
+}
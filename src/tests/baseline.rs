@@ -0,0 +1,127 @@
+use std::{
+    fs::read_to_string,
+    path::Path,
+};
+
+use ra_ap_ide_assists::{
+    assists,
+    Assist,
+    AssistConfig,
+    AssistResolveStrategy,
+};
+
+use ra_ap_ide_db::{
+    imports::insert_use::{
+        ImportGranularity,
+        InsertUseConfig,
+        PrefixKind,
+    },
+    source_change::SourceChange,
+    FileRange,
+    RootDatabase,
+};
+
+use ra_ap_load_cargo::{
+    load_workspace_at,
+    LoadCargoConfig,
+    ProcMacroServerChoice,
+};
+
+use ra_ap_project_model::{
+    CargoConfig,
+    RustLibSource,
+};
+
+use ra_ap_syntax::{
+    TextRange,
+    TextSize,
+};
+
+use ra_ap_vfs::{
+    AbsPathBuf,
+    FileId,
+    Vfs,
+    VfsPath,
+};
+
+use crate::utils::convert_to_abs_path_buf;
+
+/// The id rust-analyzer gives its extract function assist.
+const EXTRACT_FUNCTION: &str = "extract_function";
+
+/// Runs rust-analyzer's own extract function assist, which REM builds on, on a
+/// selection in a file of a Cargo project. This is what an editor would do
+/// without REM, so it is the baseline REM is evaluated against. Returns the
+/// refactored file, or why the assist couldn't be applied.
+pub fn extract_function(project_dir: &Path, file: &Path, start_idx: u32, end_idx: u32) -> Result<String, String> {
+    let cargo_config: CargoConfig = CargoConfig {
+        sysroot: Some(RustLibSource::Discover),
+        ..CargoConfig::default()
+    };
+    // Build scripts and proc macros aren't needed to find the selection's
+    // variables, and running them would make the baseline much slower than REM
+    let load_config: LoadCargoConfig = LoadCargoConfig {
+        load_out_dirs_from_check: false,
+        with_proc_macro_server: ProcMacroServerChoice::None,
+        prefill_caches: false,
+    };
+    let (db, vfs, _) = load_workspace_at(project_dir, &cargo_config, &load_config, &|_| {})
+        .map_err(|e| format!("Failed to load {:?}: {}", project_dir, e))?;
+
+    let file_id: FileId = file_id(&vfs, file)?;
+    let range: FileRange = FileRange {
+        file_id,
+        range: TextRange::new(TextSize::from(start_idx), TextSize::from(end_idx)),
+    };
+
+    let assist: Assist = find_assist(&db, range)
+        .ok_or_else(|| format!("rust-analyzer can't extract {}..{} into a function", start_idx, end_idx))?;
+    let source_change: SourceChange = assist
+        .source_change
+        .ok_or_else(|| "rust-analyzer didn't resolve the extract function assist".to_string())?;
+    let (edit, _) = source_change
+        .get_source_and_snippet_edit(file_id)
+        .ok_or_else(|| format!("rust-analyzer's extract function didn't change {:?}", file))?;
+
+    let mut code: String = read_to_string(file).map_err(|e| format!("Failed to read {:?}: {}", file, e))?;
+    edit.apply(&mut code);
+    Ok(code)
+}
+
+/// The id the loaded workspace gave `file`.
+fn file_id(vfs: &Vfs, file: &Path) -> Result<FileId, String> {
+    let path: AbsPathBuf = convert_to_abs_path_buf(&file.to_string_lossy())
+        .map_err(|_| format!("{:?} is not a valid path", file))?;
+    vfs.file_id(&VfsPath::from(path))
+        .ok_or_else(|| format!("{:?} is not part of the loaded workspace", file))
+}
+
+/// The extract function assist for `range`, with its edit worked out.
+fn find_assist(db: &RootDatabase, range: FileRange) -> Option<Assist> {
+    assists(db, &assist_config(), AssistResolveStrategy::All, range)
+        .into_iter()
+        .find(|assist| assist.id.0 == EXTRACT_FUNCTION)
+}
+
+/// The configuration an editor would use by default. Snippets are turned off,
+/// so the edit is plain code, and the new function is called `fun_name`, the
+/// same as in REM's output.
+fn assist_config() -> AssistConfig {
+    AssistConfig {
+        snippet_cap: None,
+        allowed: None,
+        insert_use: InsertUseConfig {
+            granularity: ImportGranularity::Crate,
+            enforce_granularity: true,
+            prefix_kind: PrefixKind::Plain,
+            group: true,
+            skip_glob_imports: true,
+        },
+        prefer_no_std: false,
+        prefer_prelude: true,
+        prefer_absolute: false,
+        assist_emit_must_use: false,
+        term_search_fuel: 400,
+        term_search_borrowck: true,
+    }
+}
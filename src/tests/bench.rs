@@ -0,0 +1,428 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    io,
+    panic::{
+        self,
+        AssertUnwindSafe,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use colored::Colorize;
+
+use log::{
+    info,
+    warn,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::messages::version::VERSION;
+use crate::tests::{
+    extract::{
+        self,
+        format_duration,
+        TestFile,
+    },
+    harness::{
+        panic_message,
+        run_child,
+    },
+    pipeline::{
+        run_pipeline,
+        STAGES,
+    },
+    report::{
+        LatencyStats,
+        ReportFormat,
+        WriteReport,
+    },
+    table::{
+        Column,
+        Table,
+    },
+};
+
+/// Differences in the median smaller than this are never counted as a
+/// regression, however large they are relative to the baseline. Stages that
+/// take a fraction of a millisecond are too noisy to compare by percentage.
+const NOISE_FLOOR: Duration = Duration::from_millis(1);
+
+/// The name the whole pipeline is recorded under, alongside its stages.
+const PIPELINE: &str = "pipeline";
+
+/// The columns of a CSV or LaTeX report of a benchmark run. Each row is the
+/// latency of one stage on one fixture, or on `all` of them together.
+const LATENCY_COLUMNS: [Column; 8] = [
+    Column::text("fixture", "Fixture"),
+    Column::text("stage", "Stage"),
+    Column::number("samples", "Samples"),
+    Column::number("min_ms", "Min (ms)"),
+    Column::number("median_ms", "Median (ms)"),
+    Column::number("mean_ms", "Mean (ms)"),
+    Column::number("p95_ms", "p95 (ms)"),
+    Column::number("max_ms", "Max (ms)"),
+];
+
+/// Options for a benchmark run.
+/// - iterations: How many times each fixture is timed
+/// - warmup: How many untimed runs of each fixture come first
+/// - cases: Only benchmark these fixtures (all of them if empty)
+/// - baseline: A previous run to compare against
+/// - threshold: How much slower (in percent) the median of a stage may get
+///   before it is counted as a regression
+/// - save: Where to write this run, to be used as a later baseline
+/// - timeout: How long all the runs of one fixture may take before they are
+///   killed
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchOptions {
+    pub iterations: usize,
+    pub warmup: usize,
+    pub cases: Vec<String>,
+    pub baseline: Option<PathBuf>,
+    pub threshold: f64,
+    pub save: Option<PathBuf>,
+    pub timeout: Duration,
+}
+
+/// The results of a benchmark run, keyed by fixture and then by stage
+/// (`extraction`, `controller`, `borrower`, `repairer` and `pipeline`). This
+/// is also the format of the baseline file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub version: String,
+    pub iterations: usize,
+    pub fixtures: BTreeMap<String, BTreeMap<String, LatencyStats>>,
+}
+
+/// A stage that got slower than the threshold allows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub fixture: String,
+    pub stage: String,
+    pub baseline: f64,
+    pub current: f64,
+}
+
+impl BenchReport {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents: String = fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents: String = serde_json::to_string_pretty(self)
+            .map_err(io::Error::other)?;
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        fs::write(path, contents)
+    }
+
+    /// Every stage whose median is more than `threshold` percent (and more than
+    /// the noise floor) slower than in `baseline`. Stages that are only in one
+    /// of the two reports are skipped.
+    pub fn regressions(&self, baseline: &BenchReport, threshold: f64) -> Vec<Regression> {
+        let mut regressions: Vec<Regression> = Vec::new();
+        for (fixture, stages) in &self.fixtures {
+            for (stage, current) in stages {
+                let Some(previous) = baseline.fixtures.get(fixture).and_then(|stages| stages.get(stage)) else {
+                    continue;
+                };
+                let slowdown: f64 = current.median - previous.median;
+                if slowdown > NOISE_FLOOR.as_secs_f64() * 1000.0 && slowdown > previous.median * threshold / 100.0 {
+                    regressions.push(Regression {
+                        fixture: fixture.clone(),
+                        stage: stage.clone(),
+                        baseline: previous.median,
+                        current: current.median,
+                    });
+                }
+            }
+        }
+        regressions
+    }
+
+    /// The latencies of every stage, for a CSV or LaTeX report. The `all` rows
+    /// pool the samples of every fixture, and come first.
+    pub fn to_table(&self) -> Table {
+        let stages: Vec<&str> = STAGES.iter().copied().chain([PIPELINE]).collect();
+        let mut table: Table = Table::new(&LATENCY_COLUMNS);
+        for stage in &stages {
+            let samples: Vec<f64> = self
+                .fixtures
+                .values()
+                .filter_map(|fixture| fixture.get(*stage))
+                .flat_map(|stats| stats.samples.iter().copied())
+                .collect();
+            if let Some(stats) = LatencyStats::from_millis(samples) {
+                table.push(latency_row("all", stage, &stats));
+            }
+        }
+        for (fixture, fixture_stages) in &self.fixtures {
+            for stage in &stages {
+                if let Some(stats) = fixture_stages.get(*stage) {
+                    table.push(latency_row(fixture, stage, stats));
+                }
+            }
+        }
+        table
+    }
+}
+
+impl WriteReport for BenchReport {
+    fn render(&self, format: ReportFormat) -> io::Result<String> {
+        match format {
+            ReportFormat::Json => serde_json::to_string_pretty(self).map_err(io::Error::other),
+            ReportFormat::Csv => self.to_table().to_csv(),
+            ReportFormat::Latex => Ok(self.to_table().to_latex()),
+            ReportFormat::Junit => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Benchmarks can't be written as junit (expected json, csv or latex)",
+            )),
+        }
+    }
+}
+
+/// A row of a CSV or LaTeX report of a benchmark run.
+fn latency_row(fixture: &str, stage: &str, stats: &LatencyStats) -> Vec<String> {
+    vec![
+        fixture.to_string(),
+        stage.to_string(),
+        stats.samples.len().to_string(),
+        format!("{:.3}", stats.min),
+        format!("{:.3}", stats.median),
+        format!("{:.3}", stats.mean),
+        format!("{:.3}", stats.p95),
+        format!("{:.3}", stats.max),
+    ]
+}
+
+/// Times every stage of the pipeline, and the pipeline as a whole, on each of
+/// the extract fixtures. Each fixture is run in its own `rem-cli bench-case`
+/// process, one at a time so that runs don't compete with each other. A stage
+/// that fails or panics on a fixture has no timings for it, and neither do the
+/// stages after it. A fixture whose runs take longer than `options.timeout` in
+/// total is killed, and has no timings at all.
+pub fn run_bench(path: &Path, options: &BenchOptions) -> io::Result<BenchReport> {
+    let path: PathBuf = path.canonicalize()?;
+    let mut fixtures: Vec<TestFile> = extract::test_files(&path)?;
+    fixtures.retain(|fixture| fixture.is_main());
+    if !options.cases.is_empty() {
+        fixtures.retain(|fixture| options.cases.contains(&fixture.input_file));
+    }
+
+    let mut report: BenchReport = BenchReport {
+        version: VERSION.to_string(),
+        iterations: options.iterations,
+        fixtures: BTreeMap::new(),
+    };
+    for test_file in &fixtures {
+        let fixture: &str = &test_file.input_file;
+        let args: Vec<OsString> = vec![
+            "bench-case".into(),
+            path.clone().into(),
+            fixture.into(),
+            "--warmup".into(),
+            options.warmup.to_string().into(),
+            "--iterations".into(),
+            options.iterations.to_string().into(),
+        ];
+        let timings: FixtureTimings = match run_child(&args, fixture, options.timeout) {
+            Ok(Ok(timings)) => timings,
+            Ok(Err(failed)) => FixtureTimings::failed(failed.failure_message()),
+            Err(e) => FixtureTimings::failed(format!("Failed to run the benchmark: {}", e)),
+        };
+        print_fixture(fixture, &timings.stages, &timings.failures);
+        report.fixtures.insert(fixture.to_string(), timings.stages);
+    }
+
+    Ok(report)
+}
+
+/// What a `rem-cli bench-case` process reports about one fixture.
+/// - stages: The latencies of every stage that completed at least once
+/// - failures: Every distinct way the fixture failed, in the order they first
+///   happened
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct FixtureTimings {
+    stages: BTreeMap<String, LatencyStats>,
+    failures: Vec<String>,
+}
+
+impl FixtureTimings {
+    fn failed(failure: String) -> Self {
+        FixtureTimings {
+            stages: BTreeMap::new(),
+            failures: vec![failure],
+        }
+    }
+}
+
+/// Entry point for the hidden `bench-case` subcommand. Times `warmup +
+/// iterations` runs of the pipeline on one fixture, with the intermediate files
+/// written to the current directory, and writes the timings to `result_path`.
+/// `bench` kills this process if the runs take too long.
+pub fn run_bench_case(
+    path: &Path,
+    case: &str,
+    warmup: usize,
+    iterations: usize,
+    result_path: &Path,
+) -> io::Result<()> {
+    let Some((_, test_file)) = extract::load_test_file(path, case)? else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("No extract test named {}", case)));
+    };
+    let fixture_dir: PathBuf = path.join("extract/input").join(case);
+
+    let mut samples: BTreeMap<&'static str, Vec<Duration>> = BTreeMap::new();
+    let mut failures: Vec<String> = Vec::new();
+    for iteration in 0..warmup + iterations {
+        let mut timings: Vec<(&'static str, Duration)> = Vec::new();
+        let start: Instant = Instant::now();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            run_pipeline(&fixture_dir, case, test_file.start_idx, test_file.end_idx, &mut timings)
+        }));
+        let elapsed: Duration = start.elapsed();
+
+        if iteration < warmup {
+            continue;
+        }
+        let failure: String = match result {
+            Ok(Ok(_)) => {
+                samples.entry(PIPELINE).or_default().push(elapsed);
+                String::new()
+            }
+            Ok(Err((stage, message))) => format!("{} failed: {}", stage, message),
+            Err(payload) => format!(
+                "panicked after {} stages: {}",
+                timings.len(),
+                panic_message(payload.as_ref()),
+            ),
+        };
+        if !failure.is_empty() && !failures.contains(&failure) {
+            failures.push(failure);
+        }
+        for (stage, duration) in timings {
+            samples.entry(stage).or_default().push(duration);
+        }
+    }
+
+    let timings: FixtureTimings = FixtureTimings {
+        stages: samples
+            .into_iter()
+            .filter_map(|(stage, samples)| LatencyStats::from_samples(&samples).map(|stats| (stage.to_string(), stats)))
+            .collect(),
+        failures,
+    };
+    let contents: String = serde_json::to_string(&timings)
+        .map_err(io::Error::other)?;
+    fs::write(result_path, contents)
+}
+
+fn print_fixture(fixture: &str, stages: &BTreeMap<String, LatencyStats>, failures: &[String]) {
+    println!("{}", fixture.bold());
+    for (stage, stats) in stages {
+        let line: String = format!(
+            "    {:<12} median {:>10}  p95 {:>10}  min {:>10}  max {:>10}",
+            stage,
+            format_millis(stats.median),
+            format_millis(stats.p95),
+            format_millis(stats.min),
+            format_millis(stats.max),
+        );
+        println!("{}", line);
+        info!("{}: {}", fixture, line.trim());
+    }
+    for failure in failures {
+        println!("    {}", failure.lines().next().unwrap_or_default().yellow());
+        warn!("{}: {}", fixture, failure);
+    }
+}
+
+/// Prints how every stage compares with the baseline, and lists the
+/// regressions. Returns the regressions.
+pub fn compare_with_baseline(report: &BenchReport, baseline: &BenchReport, threshold: f64) -> Vec<Regression> {
+    println!("------------------------------------------------------------------");
+    println!("Compared with baseline (rem-cli {}, {} iterations):", baseline.version, baseline.iterations);
+    for (fixture, stages) in &report.fixtures {
+        for (stage, current) in stages {
+            let Some(previous) = baseline.fixtures.get(fixture).and_then(|stages| stages.get(stage)) else {
+                continue;
+            };
+            let change: f64 = if previous.median > 0.0 {
+                (current.median - previous.median) / previous.median * 100.0
+            } else {
+                0.0
+            };
+            let change_str: String = format!("{:+.1}%", change);
+            let change_str: String = if change > threshold {
+                change_str.red().to_string()
+            } else if change < -threshold {
+                change_str.green().to_string()
+            } else {
+                change_str
+            };
+            println!(
+                "    {:<40} {:<12} {:>10} -> {:>10} ({})",
+                fixture,
+                stage,
+                format_millis(previous.median),
+                format_millis(current.median),
+                change_str,
+            );
+        }
+    }
+    for (fixture, stages) in &baseline.fixtures {
+        for stage in stages.keys() {
+            if !report.fixtures.get(fixture).is_some_and(|stages| stages.contains_key(stage)) {
+                println!("    {:<40} {:<12} {}", fixture, stage, "no longer completes".yellow());
+            }
+        }
+    }
+
+    let regressions: Vec<Regression> = report.regressions(baseline, threshold);
+    println!("------------------------------------------------------------------");
+    if regressions.is_empty() {
+        println!("No stage is more than {}% slower than the baseline", threshold);
+        info!("No stage is more than {}% slower than the baseline", threshold);
+    } else {
+        println!("{} stages are more than {}% slower than the baseline:", regressions.len(), threshold);
+        for regression in &regressions {
+            println!(
+                "    {} {}: {} -> {}",
+                regression.fixture,
+                regression.stage,
+                format_millis(regression.baseline),
+                format_millis(regression.current),
+            );
+            warn!(
+                "Regression in {} {}: {:.3}ms -> {:.3}ms",
+                regression.fixture,
+                regression.stage,
+                regression.baseline,
+                regression.current,
+            );
+        }
+    }
+    println!("------------------------------------------------------------------");
+    regressions
+}
+
+fn format_millis(millis: f64) -> String {
+    format_duration(Duration::from_secs_f64(millis.max(0.0) / 1000.0))
+}
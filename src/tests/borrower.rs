@@ -1,102 +1,79 @@
-use rem_borrower::borrow;
-use crate::tests::utils::compile_file;
-use log::{
-    error,
-    info,
-};
-
-use colored::Colorize;
-use std::{
-    fs::{self, read_to_string},
-    io,
-    path::{
-        Path,
-        PathBuf,
-    },
-    time::SystemTime
-};
-
-use crate::tests::utils::{
-    cleanup_new_files,
-    list_files_in_dir,
-};
-
-use crate::refactor::throughput::Borrower;
-
-pub fn test(path: PathBuf) -> Result<u8, io::Error> {
-
-    let folder_path: String = match path.to_str() {
-        Some(path_str) => path_str.to_string(),
-        None => {
-            error!("Failed to conver path to string {:?}", path);
-            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid Path"));
-        }
-    };
-
-    if !path.is_dir() {
-        error!("The path provided is not a directory: {}", folder_path);
-        return Err(io::Error::new(io::ErrorKind::NotFound, "Path is not a directory"));
-    }
-
-    info!("Running tests from directory {}{}", folder_path, "borrower");
-
-    // Capture initial state
-    let current_dir: &Path = Path::new("./");
-    let initial_files: Vec<String> = list_files_in_dir(current_dir)?;
-
-    let mut total_failed_tests: u8 = 0;
-
-    for file in fs::read_dir(format!("{}/borrower/input", folder_path)).unwrap() {
-        let file = file?;
-        let test_name = file.file_name().to_owned();
-        let file_name = format!("{}/borrower/input/{}", folder_path, test_name.to_str().unwrap());
-        let new_file_name = format!("{}/borrower/output/{}", folder_path, test_name.to_str().unwrap());
-        let mut_method_call_expr_file =
-            format!("{}/borrower/method_call_mut/{}", folder_path, test_name.to_str().unwrap());
-        let pre_extract_file_name = format!("{}/borrower/pre_extract/{}", folder_path, test_name.to_str().unwrap());
-        let callee_fn_name = "bar";
-        let caller_fn_name = "new_foo";
-        let now = SystemTime::now();
-        let input_borrower: Borrower = Borrower::new(
-            read_to_string(file_name.as_str()).unwrap(),
-            read_to_string(pre_extract_file_name.as_str()).unwrap(),
-            None,
-            caller_fn_name.to_string(),
-            callee_fn_name.to_string(),
-            read_to_string(mut_method_call_expr_file.as_str()).unwrap(),
-        );
-        let result  = borrow::make_borrows(
-            input_borrower.into(),
-        );
-        let success: bool = match result {
-            Ok(_) => true,
-            Err(e) => {
-                error!("Borrower failed: {:?}", e);
-                false
-            }
-        };
-
-        let time_elapsed = now.elapsed().unwrap();
-        let args = vec![];
-        let mut compile_cmd = compile_file(new_file_name.as_str(), &args);
-        let out = compile_cmd.output().unwrap();
-        println!(
-            "{}: {} in {:#?}",
-            (if out.status.success() {
-                format!("PASSED").green()
-            } else {
-                format!("FAILED").red()
-            }),
-            test_name.to_str().unwrap(),
-            time_elapsed
-        );
-        println!("------------------------------------------------------------------\n");
-
-        // Update the number of failed tests.
-        total_failed_tests += if !out.status.success() || !success { 1 } else { 0 };
-    }
-
-    let _ = cleanup_new_files(initial_files, current_dir);
-
-    Ok(total_failed_tests)
-}
+use rem_borrower::borrow;
+use crate::tests::utils::{
+    compile_file,
+    list_files_in_dir,
+};
+use log::error;
+
+use colored::Colorize;
+use std::{
+    fs::read_to_string,
+    io,
+    path::Path,
+    time::SystemTime
+};
+
+use crate::refactor::throughput::Borrower;
+use crate::tests::report::CaseResult;
+
+/// Every file in `borrower/input` is a case. The matching `pre_extract` and
+/// `method_call_mut` files share its name.
+pub fn cases(path: &Path) -> Result<Vec<String>, io::Error> {
+    let mut cases: Vec<String> = list_files_in_dir(&path.join("borrower/input"))?;
+    cases.sort();
+    Ok(cases)
+}
+
+pub fn run_case(path: &Path, test_name: &str) -> Result<CaseResult, io::Error> {
+    let folder_path: &Path = &path.join("borrower");
+    let file_name = folder_path.join("input").join(test_name);
+    let new_file_name = folder_path.join("output").join(test_name);
+    let mut_method_call_expr_file = folder_path.join("method_call_mut").join(test_name);
+    let pre_extract_file_name = folder_path.join("pre_extract").join(test_name);
+    let callee_fn_name = "bar";
+    let caller_fn_name = "new_foo";
+    let now = SystemTime::now();
+    let input_borrower: Borrower = Borrower::new(
+        read_to_string(&file_name)?,
+        read_to_string(&pre_extract_file_name)?,
+        None,
+        caller_fn_name.to_string(),
+        callee_fn_name.to_string(),
+        read_to_string(&mut_method_call_expr_file)?,
+    );
+    let result  = borrow::make_borrows(
+        input_borrower.into(),
+    );
+    let (success, borrower_message): (bool, Option<String>) = match result {
+        Ok(_) => (true, None),
+        Err(e) => {
+            error!("Borrower failed: {:?}", e);
+            (false, Some(format!("{:?}", e)))
+        }
+    };
+
+    let time_elapsed = now.elapsed().unwrap();
+    let args = vec![];
+    let mut compile_cmd = compile_file(&new_file_name.to_string_lossy(), &args);
+    let out = compile_cmd.output()?;
+    println!(
+        "{}: {} in {:#?}",
+        (if out.status.success() {
+            format!("PASSED").green()
+        } else {
+            format!("FAILED").red()
+        }),
+        test_name,
+        time_elapsed
+    );
+    println!("------------------------------------------------------------------\n");
+
+    let mut case: CaseResult = CaseResult::new(test_name, time_elapsed);
+    case.add_check("borrower", success, borrower_message);
+    case.add_check(
+        "compile",
+        out.status.success(),
+        (!out.status.success()).then(|| String::from_utf8_lossy(&out.stderr).to_string()),
+    );
+    Ok(case)
+}
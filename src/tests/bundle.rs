@@ -0,0 +1,488 @@
+use std::{
+    env,
+    error::Error,
+    fs::{self, read_to_string},
+    io,
+    panic::{self, AssertUnwindSafe},
+    path::{
+        Path,
+        PathBuf,
+    },
+    process::{
+        self,
+        Command,
+    },
+    time::Duration,
+};
+
+use colored::Colorize;
+
+use log::{
+    error,
+    info,
+    warn,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use tempfile::TempDir;
+
+use toml_edit::DocumentMut;
+
+use crate::logging::{
+    current_log_file,
+    LOG_DIRECTORY,
+};
+use crate::tests::{
+    harness::panic_message,
+    import::standalone_manifest,
+    pipeline::{
+        run_pipeline_on_file,
+        stage_file,
+        STAGES,
+        TEMPORARY,
+        WORKING,
+    },
+    record::{
+        crate_versions,
+        timestamp,
+        CrateVersion,
+    },
+    verify::{
+        RunOutput,
+        ScratchProject,
+    },
+};
+
+/// The file in a bundle that describes the failure.
+pub const BUNDLE_MANIFEST: &str = "bundle.json";
+
+/// How long `cargo check` may take on the failing code before the diagnostics
+/// are left out of the bundle.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// The most lines of the log that are copied into a bundle. The last ones are
+/// kept.
+const MAX_LOG_LINES: usize = 2000;
+
+/// The message `main` logs first, which marks where a run starts in the log.
+const RUN_STARTED: &str = "Application Started";
+
+/// The code a stage was given and produced, as written by
+/// `run_pipeline_on_file`.
+/// - stage: The stage
+/// - working_code: The code it was given
+/// - temporary_code: The borrower's temporary (mutable method) code
+/// - output_code: The code it produced, unless it failed
+struct StageCode {
+    stage: &'static str,
+    working_code: String,
+    temporary_code: Option<String>,
+    output_code: Option<String>,
+}
+
+/// An extraction, as given to `extract`.
+/// - file: The file the selection is in
+/// - start_idx, end_idx: Where the selection is, as byte offsets into the file
+/// - new_fn_name: The name of the extracted function
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Refactoring {
+    pub file: PathBuf,
+    pub start_idx: u32,
+    pub end_idx: u32,
+    pub new_fn_name: String,
+}
+
+/// The stage a refactoring stopped at, and why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Failure {
+    pub stage: String,
+    pub message: String,
+}
+
+/// Everything in a bundle that isn't one of its files.
+/// - created_at: When the refactoring failed
+/// - args: rem-cli's command line
+/// - refactoring: The refactoring that failed, with `file` relative to
+///   `project/`
+/// - failure: The stage that failed, and why
+/// - stages: The stages whose code is in `stages/`, in the order they ran
+/// - crates: The versions of rem-cli and the rem crates it was built with
+/// - rustc: `rustc -V` for the project's toolchain
+/// - notes: Anything about the project that couldn't be carried over
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub created_at: String,
+    pub args: Vec<String>,
+    pub refactoring: Refactoring,
+    pub failure: Failure,
+    pub stages: Vec<String>,
+    pub crates: Vec<CrateVersion>,
+    pub rustc: Option<String>,
+    pub notes: Vec<String>,
+}
+
+/// Entry point for `extract`. Runs every stage of the pipeline on the
+/// selection, and writes the refactored file to `new_file_path`. If a stage
+/// fails or panics, a bundle is written to `bundles_dir` (unless it is `None`)
+/// and where it is printed. Returns whether the refactoring succeeded.
+pub fn run_extract(refactoring: &Refactoring, new_file_path: &Path, bundles_dir: Option<&Path>) -> io::Result<bool> {
+    let original_code: String = read_to_string(&refactoring.file)?;
+    let (result, stages) = run_refactoring(refactoring)?;
+
+    let failure: Failure = match result {
+        Ok(output_code) => {
+            fs::write(new_file_path, output_code)?;
+            info!("Extracted {}..{} of {:?} into {}", refactoring.start_idx, refactoring.end_idx, refactoring.file, refactoring.new_fn_name);
+            return Ok(true);
+        }
+        Err(failure) => failure,
+    };
+
+    error!("Extraction failed at {}: {}", failure.stage, failure.message);
+    eprintln!("{} at {}: {}", "FAILED".red(), failure.stage, failure.message);
+    if let Some(bundles_dir) = bundles_dir {
+        match write_bundle(bundles_dir, refactoring, &original_code, &failure, &stages) {
+            Ok(bundle) => {
+                info!("Wrote a bundle for the failure to {:?}", bundle);
+                eprintln!("Wrote a bundle for the failure to {}", bundle.display());
+                eprintln!("Attach it to bug reports. `rem-cli repro {}` re-runs it", bundle.display());
+            }
+            Err(e) => {
+                error!("Failed to write a bundle for the failure: {}", e);
+                eprintln!("Failed to write a bundle for the failure: {}", e);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Entry point for `repro`. Re-runs the refactoring in a bundle on a copy of
+/// its project, and reports whether it fails in the same way. Returns whether
+/// it still fails, at any stage.
+pub fn run_repro(bundle: &Path) -> Result<bool, Box<dyn Error>> {
+    let manifest_path: PathBuf = bundle.join(BUNDLE_MANIFEST);
+    let manifest: BundleManifest = read_to_string(&manifest_path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| serde_json::from_str(&contents).map_err(|e| e.to_string()))
+        .map_err(|e| format!("Failed to read {:?}: {}", manifest_path, e))?;
+    println!("Bundle from {}: {}", manifest.created_at, manifest.args.join(" "));
+    println!("Failed at {}: {}", manifest.failure.stage, manifest.failure.message);
+
+    for (recorded, current) in changed_versions(&manifest.crates, &crate_versions()) {
+        println!("{}: {} was {}, now {}", "Note".yellow(), current.name, recorded, current);
+    }
+    let project: TempDir = tempfile::Builder::new()
+        .prefix("rem-repro-")
+        .tempdir()?;
+    copy_package(&bundle.join("project"), project.path(), &[])?;
+    let rustc: Option<String> = rustc_version(project.path());
+    if rustc != manifest.rustc {
+        let recorded: &str = manifest.rustc.as_deref().unwrap_or("unknown");
+        println!("{}: rustc was {}, now {}", "Note".yellow(), recorded, rustc.as_deref().unwrap_or("unknown"));
+    }
+
+    let refactoring: Refactoring = Refactoring {
+        file: project.path().join(&manifest.refactoring.file),
+        ..manifest.refactoring.clone()
+    };
+    let (result, _) = run_refactoring(&refactoring)?;
+    let still_fails: bool = match result {
+        Ok(_) => {
+            println!("{}: every stage succeeded", "FIXED".green());
+            false
+        }
+        Err(failure) if failure == manifest.failure => {
+            println!("{}: failed at {} in the same way", "REPRODUCED".red(), failure.stage);
+            true
+        }
+        Err(failure) if failure.stage == manifest.failure.stage => {
+            println!("{}: failed at {}, with a different message: {}", "REPRODUCED".red(), failure.stage, failure.message);
+            true
+        }
+        Err(failure) => {
+            println!("{}: failed at {} instead: {}", "CHANGED".yellow(), failure.stage, failure.message);
+            true
+        }
+    };
+    info!("Reproduced {:?}: {}", bundle, if still_fails { "still fails" } else { "no longer fails" });
+    Ok(still_fails)
+}
+
+/// Runs the pipeline on the refactoring, with panics counted as a failure of
+/// the stage that was running. Returns the result along with the code of each
+/// stage that got that far, in order. The stages are written to a temporary
+/// directory, which is removed afterwards.
+fn run_refactoring(refactoring: &Refactoring) -> io::Result<(Result<String, Failure>, Vec<StageCode>)> {
+    let scratch: TempDir = tempfile::Builder::new()
+        .prefix("rem-pipeline-")
+        .tempdir()?;
+
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| run_pipeline_on_file(
+        &refactoring.file,
+        &refactoring.new_fn_name,
+        scratch.path(),
+        refactoring.start_idx,
+        refactoring.end_idx,
+        &mut Vec::new(),
+    )));
+    let stages: Vec<StageCode> = STAGES
+        .iter()
+        .map_while(|stage| {
+            let read = |part: Option<&str>| read_to_string(stage_file(scratch.path(), stage, part)).ok();
+            Some(StageCode {
+                stage,
+                working_code: read(Some(WORKING))?,
+                temporary_code: read(Some(TEMPORARY)),
+                output_code: read(None),
+            })
+        })
+        .collect();
+
+    let result: Result<String, Failure> = match outcome {
+        Ok(Ok(output_code)) => Ok(output_code),
+        Ok(Err((stage, message))) => Err(Failure {
+            stage: stage.to_string(),
+            message,
+        }),
+        Err(payload) => {
+            // The stage that panicked is the last one that was given code
+            let stage: &str = stages.last().map_or("extraction", |stage| stage.stage);
+            Err(Failure {
+                stage: stage.to_string(),
+                message: format!("panicked: {}", panic_message(payload.as_ref())),
+            })
+        }
+    };
+    Ok((result, stages))
+}
+
+/// Writes a bundle for a failed refactoring, and returns where it is:
+/// - `bundle.json`: The command line, the selection, the failure, and the
+///   versions of rem-cli, the rem crates and rustc
+/// - `project/`: The package the file is in, as it was before the refactoring,
+///   with a manifest that builds on its own
+/// - `stages/`: The code each stage that ran was given
+///   (`<stage>.working.rs`, and `<stage>.temporary.rs` for the borrower) and
+///   produced (`<stage>.rs`)
+/// - `diagnostics.txt`: `cargo check` on the output of the last stage that ran
+/// - `log.txt`: This run's part of the log, if it could be read
+fn write_bundle(
+    bundles_dir: &Path,
+    refactoring: &Refactoring,
+    original_code: &str,
+    failure: &Failure,
+    stages: &[StageCode],
+) -> Result<PathBuf, Box<dyn Error>> {
+    let file: PathBuf = refactoring.file.canonicalize()?;
+    let file_stem: String = file.file_stem().map_or("extract".into(), |stem| stem.to_string_lossy().to_string());
+    let (created_at, stem) = timestamp();
+    fs::create_dir_all(bundles_dir)?;
+    let mut bundle: PathBuf = bundles_dir.join(format!("{}_{}", stem, file_stem));
+    let mut suffix: usize = 1;
+    while bundle.exists() {
+        suffix += 1;
+        bundle = bundles_dir.join(format!("{}_{}_{}", stem, file_stem, suffix));
+    }
+    fs::create_dir_all(&bundle)?;
+
+    // Folders rem-cli itself writes to are left out in case they are inside
+    // the package, and so are the backups `main` makes of the file
+    let current_dir: PathBuf = env::current_dir()?;
+    let mut skip: Vec<PathBuf> = [
+        bundles_dir.to_path_buf(),
+        current_dir.join(LOG_DIRECTORY),
+    ]
+    .iter()
+    .filter_map(|dir| dir.canonicalize().ok())
+    .collect();
+    if let Some(parent) = file.parent() {
+        let backup_prefix: String = format!("{}_backup_", file_stem);
+        for entry in fs::read_dir(parent)? {
+            let path: PathBuf = entry?.path();
+            if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with(&backup_prefix)) {
+                skip.push(path);
+            }
+        }
+    }
+
+    let project: PathBuf = bundle.join("project");
+    let mut notes: Vec<String> = Vec::new();
+    let relative_file: PathBuf = match package_root(&file) {
+        Some(package_root) => {
+            copy_package(&package_root, &project, &skip)?;
+            notes.extend(write_standalone_manifest(&package_root, &project)?);
+            file.strip_prefix(&package_root)?.to_path_buf()
+        }
+        None => {
+            notes.push("The file isn't in a Cargo package, so only the file was copied".to_string());
+            PathBuf::from(file.file_name().ok_or("The file has no name")?)
+        }
+    };
+    let bundled_file: PathBuf = project.join(&relative_file);
+    if let Some(parent) = bundled_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&bundled_file, original_code)?;
+
+    let stages_dir: PathBuf = bundle.join("stages");
+    fs::create_dir_all(&stages_dir)?;
+    for stage in stages {
+        fs::write(stage_file(&stages_dir, stage.stage, Some(WORKING)), &stage.working_code)?;
+        if let Some(temporary_code) = &stage.temporary_code {
+            fs::write(stage_file(&stages_dir, stage.stage, Some(TEMPORARY)), temporary_code)?;
+        }
+        if let Some(output_code) = &stage.output_code {
+            fs::write(stage_file(&stages_dir, stage.stage, None), output_code)?;
+        }
+    }
+
+    let (checked, code) = stages
+        .iter()
+        .rev()
+        .find_map(|stage| Some((stage.stage, stage.output_code.as_deref()?)))
+        .unwrap_or(("the original file", original_code));
+    let diagnostics: String = match check_diagnostics(&project, &relative_file, code) {
+        Ok(diagnostics) => format!("cargo check with {} in place of {}:\n\n{}", checked, relative_file.display(), diagnostics),
+        Err(e) => {
+            warn!("Failed to check {} for the bundle: {}", checked, e);
+            format!("cargo check couldn't be run on {}: {}", checked, e)
+        }
+    };
+    fs::write(bundle.join("diagnostics.txt"), diagnostics)?;
+    match log_excerpt() {
+        Ok(log) => fs::write(bundle.join("log.txt"), log)?,
+        Err(e) => {
+            let log_file: PathBuf = current_dir.join(current_log_file());
+            warn!("Failed to read {:?} for the bundle: {}", log_file, e);
+            notes.push(format!("The log couldn't be read from {:?} ({}), so log.txt was left out", log_file, e));
+        }
+    }
+
+    let manifest: BundleManifest = BundleManifest {
+        created_at,
+        args: env::args().collect(),
+        refactoring: Refactoring {
+            file: relative_file,
+            ..refactoring.clone()
+        },
+        failure: failure.clone(),
+        stages: stages.iter().map(|stage| stage.stage.to_string()).collect(),
+        crates: crate_versions(),
+        rustc: file.parent().and_then(rustc_version),
+        notes,
+    };
+    fs::write(bundle.join(BUNDLE_MANIFEST), serde_json::to_string_pretty(&manifest)?)?;
+    Ok(bundle)
+}
+
+/// The closest folder above `file` with a manifest for a package.
+fn package_root(file: &Path) -> Option<PathBuf> {
+    file.ancestors()
+        .skip(1)
+        .find(|dir| read_manifest(&dir.join("Cargo.toml")).is_some_and(|manifest| manifest.contains_key("package")))
+        .map(Path::to_path_buf)
+}
+
+fn read_manifest(path: &Path) -> Option<DocumentMut> {
+    read_to_string(path).ok()?.parse().ok()
+}
+
+/// Copies a package from `source` to `dest`. Build output, hidden files and
+/// folders, and anything in `skip` are left out.
+fn copy_package(source: &Path, dest: &Path, skip: &[PathBuf]) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(source)? {
+        let entry: fs::DirEntry = entry?;
+        let path: PathBuf = entry.path();
+        let name: String = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || name == "target" || skip.contains(&path) {
+            continue;
+        }
+
+        if entry.file_type()?.is_dir() {
+            copy_package(&path, &dest.join(&name), skip)?;
+        } else if entry.file_type()?.is_file() {
+            fs::copy(&path, dest.join(&name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the copied manifest with one that builds outside of the workspace
+/// the package is in, and brings the workspace's lock file along. Returns
+/// anything that couldn't be carried over.
+fn write_standalone_manifest(package_root: &Path, project: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let manifest: String = read_to_string(package_root.join("Cargo.toml"))?;
+    let workspace: Option<(PathBuf, DocumentMut)> = package_root.ancestors().find_map(|dir| {
+        let manifest: DocumentMut = read_manifest(&dir.join("Cargo.toml"))?;
+        manifest.contains_key("workspace").then(|| (dir.to_path_buf(), manifest))
+    });
+    let (manifest, warnings) = standalone_manifest(&manifest, workspace.as_ref().map(|(_, manifest)| manifest))?;
+    fs::write(project.join("Cargo.toml"), manifest)?;
+
+    if let Some((workspace_root, _)) = &workspace {
+        let lock_file: PathBuf = workspace_root.join("Cargo.lock");
+        if lock_file.is_file() && !project.join("Cargo.lock").exists() {
+            fs::copy(lock_file, project.join("Cargo.lock"))?;
+        }
+    }
+    Ok(warnings)
+}
+
+/// The diagnostics from `cargo check` on a copy of `project` with `file`
+/// replaced by `code`.
+fn check_diagnostics(project: &Path, file: &Path, code: &str) -> io::Result<String> {
+    let scratch: TempDir = tempfile::Builder::new()
+        .prefix("rem-bundle-")
+        .tempdir()?;
+    let copy: PathBuf = scratch.path().join("project");
+    copy_package(project, &copy, &[])?;
+    fs::write(copy.join(file), code)?;
+
+    let output: RunOutput = ScratchProject::open(&copy)?.check(&scratch.path().join("target"), CHECK_TIMEOUT)?;
+    if output.timed_out {
+        return Ok(format!("Timed out after {}s", CHECK_TIMEOUT.as_secs()));
+    }
+    Ok(output.stderr)
+}
+
+/// This run's part of the log: everything since `main` last logged that it had
+/// started, up to `MAX_LOG_LINES` lines. The log is relative to the folder
+/// rem-cli was run from, so it may not be there.
+fn log_excerpt() -> io::Result<String> {
+    let log: String = read_to_string(current_log_file())?;
+    let lines: Vec<&str> = log.lines().collect();
+    let start: usize = lines.iter().rposition(|line| line.contains(RUN_STARTED)).unwrap_or(0);
+    let start: usize = start.max(lines.len().saturating_sub(MAX_LOG_LINES));
+    Ok(lines[start..].join("\n"))
+}
+
+/// `rustc -V` for the toolchain used in `dir`, which a `rust-toolchain` file
+/// can change.
+fn rustc_version(dir: &Path) -> Option<String> {
+    let output: process::Output = Command::new("rustc")
+        .arg("-V")
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The crates whose version or commit differs between `recorded` and
+/// `current`, as (recorded, current) pairs.
+fn changed_versions<'a>(recorded: &'a [CrateVersion], current: &'a [CrateVersion]) -> Vec<(&'a CrateVersion, &'a CrateVersion)> {
+    current
+        .iter()
+        .filter_map(|current| {
+            let recorded: &CrateVersion = recorded.iter().find(|recorded| recorded.name == current.name)?;
+            let changed: bool = recorded.version != current.version
+                || recorded.sha != current.sha
+                || recorded.dirty != current.dirty;
+            changed.then_some((recorded, current))
+        })
+        .collect()
+}
@@ -1,99 +1,146 @@
-use crate::tests::utils::compile_file;
-use rem_controller::non_local_controller;
-use std::{
-    fs,
-    path::{
-        Path,
-        PathBuf,
-    },
-    time::SystemTime,
-};
-
-use log::{
-    info,
-    error,
-};
-
-use colored::Colorize;
-use std::{
-    io,
-    fs::read_to_string,
-};
-use crate::tests::utils::{
-    list_files_in_dir,
-    cleanup_new_files,
-};
-use crate::refactor::throughput::Controller;
-
-pub fn test(path: PathBuf) -> Result<u8, io::Error> {
-    let folder_path: String = match path.to_str() {
-        Some(path_str) => path_str.to_string(),
-        None => {
-            error!("Failed to conver path to string {:?}", path);
-            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid Path"));
-        }
-    };
-
-    if !path.is_dir() {
-        error!("The path provided is not a directory: {}", folder_path);
-        return Err(io::Error::new(io::ErrorKind::NotFound, "Path is not a directory"));
-    }
-
-    info!("Running tests from directory {}{}", folder_path, "controller");
-
-    // Capture initial state
-    let current_dir: &Path = Path::new("./");
-    let initial_files: Vec<String> = list_files_in_dir(current_dir)?;
-
-    let mut total_failed_tests: u8 = 0;
-
-    for file in fs::read_dir(format!("{}/controller/input", folder_path))? {
-        let file = file?;
-        let test_name = file.file_name().to_owned();
-        let file_name = format!("{}/controller/input/{}", folder_path, test_name.to_str().unwrap());
-        let new_file_name = format!("{}/controller/output/{}", folder_path, test_name.to_str().unwrap());
-        let callee_fn_name = "bar";
-        let caller_fn_name = "new_foo";
-        let now = SystemTime::now();
-        let input_controller: Controller = Controller::new(
-            read_to_string(file_name.as_str()).unwrap(),
-            None,
-            caller_fn_name.to_string(),
-            callee_fn_name.to_string(),
-        );
-        let result = non_local_controller::make_controls(
-            input_controller.into(),
-        );
-
-        let success: bool = match result {
-            Ok(_) => true,
-            Err(e) => {
-                error!("Controller failed: {:?}", e);
-                false
-            }
-        };
-
-        let time_elapsed = now.elapsed().unwrap();
-        let args = vec![];
-        let mut compile_cmd = compile_file(new_file_name.as_str(), &args);
-        let out = compile_cmd.output()?;
-        println!(
-            "{}: {} in {:#?}",
-            (if out.status.success() && success {
-                format!("PASSED").green()
-            } else {
-                format!("FAILED").red()
-            }),
-            test_name.to_str().unwrap(),
-            time_elapsed
-        );
-        println!("------------------------------------------------------------------\n");
-
-        // Update the number of failed tests.
-        total_failed_tests += if !out.status.success() || !success { 1 } else { 0 };
-    }
-
-    let _ = cleanup_new_files(initial_files, current_dir);
-
-    Ok(total_failed_tests)
-}
+use crate::tests::utils::{
+    compile_file,
+    list_files_in_dir,
+};
+use rem_controller::non_local_controller;
+use rem_utils::{
+    remove_all_files,
+    strip_ansi_codes,
+};
+use std::{
+    path::Path,
+    time::SystemTime,
+};
+
+use log::{
+    error,
+    info,
+};
+
+use colored::Colorize;
+use std::{
+    io,
+    fs::{self, read_to_string},
+};
+use crate::refactor::throughput::Controller;
+use crate::tests::ast::compare_ast;
+use crate::tests::diff::explain_mismatch;
+use crate::tests::harness::HarnessOptions;
+use crate::tests::report::CaseResult;
+
+/// Every file in `controller/input` is a case. The expected result for each
+/// case is the file with the same name in `controller/expected`.
+pub fn cases(path: &Path) -> Result<Vec<String>, io::Error> {
+    let mut cases: Vec<String> = list_files_in_dir(&path.join("controller/input"))?;
+    cases.sort();
+    Ok(cases)
+}
+
+/// The generated files are written to `controller/output`, which is cleared
+/// before each run so that stale outputs are never compiled or compared.
+pub fn prepare(path: &Path) -> Result<(), io::Error> {
+    let output_dir = path.join("controller/output");
+    fs::create_dir_all(&output_dir)?;
+    remove_all_files(&output_dir);
+    Ok(())
+}
+
+pub fn run_case(path: &Path, test_name: &str, options: &HarnessOptions) -> Result<CaseResult, io::Error> {
+    let file_name = path.join("controller/input").join(test_name);
+    let new_file_name = path.join("controller/output").join(test_name);
+    let expected_file_name = path.join("controller/expected").join(test_name);
+    let callee_fn_name = "bar";
+    let caller_fn_name = "new_foo";
+    let now = SystemTime::now();
+    let input_controller: Controller = Controller::new(
+        read_to_string(&file_name)?,
+        None,
+        caller_fn_name.to_string(),
+        callee_fn_name.to_string(),
+    );
+    let result = non_local_controller::make_controls(
+        input_controller.into(),
+    );
+    let time_elapsed = now.elapsed().unwrap();
+
+    let mut case: CaseResult = CaseResult::new(test_name, time_elapsed);
+    let mut controller_status: String = "FAILED".red().to_string();
+    let mut comparison_status: String = "N/A".to_string();
+    let mut compile_status: String = "N/A".to_string();
+    let mut ast_diff: Option<String> = None;
+
+    match result {
+        Ok(output_code) => {
+            fs::write(&new_file_name, &output_code)?;
+            controller_status = "PASSED".green().to_string();
+            case.add_check("controller", true, None);
+
+            // Compare the output file with the expected file's AST
+            let expected_code = read_to_string(&expected_file_name)?;
+            match compare_ast(&output_code, &expected_code, &options.compare) {
+                Ok(is_identical) => {
+                    comparison_status = if is_identical {
+                        "PASSED".green().to_string()
+                    } else {
+                        ast_diff = Some(explain_mismatch(
+                            &output_code,
+                            &expected_code,
+                            &options.compare,
+                            &expected_file_name.to_string_lossy(),
+                        ));
+                        "FAILED".red().to_string()
+                    };
+                    case.add_check("ast", is_identical, ast_diff.clone());
+                }
+                Err(e) => {
+                    comparison_status = format!("Error: {}", e).red().to_string();
+                    case.add_check("ast", false, Some(e.to_string()));
+                }
+            }
+
+            let args = vec![];
+            let mut compile_cmd = compile_file(&new_file_name.to_string_lossy(), &args);
+            let out = compile_cmd.output()?;
+            compile_status = if out.status.success() {
+                "PASSED".green().to_string()
+            } else {
+                "FAILED".red().to_string()
+            };
+            case.add_check(
+                "compile",
+                out.status.success(),
+                (!out.status.success()).then(|| String::from_utf8_lossy(&out.stderr).to_string()),
+            );
+        }
+        Err(e) => {
+            error!("Controller failed: {:?}", e);
+            case.add_check("controller", false, Some(format!("{:?}", e)));
+        }
+    }
+
+    println!(
+        "{} | {} | {}: {} in {:#?}",
+        controller_status,
+        comparison_status,
+        compile_status,
+        test_name,
+        time_elapsed
+    );
+    info!(
+        "{} | {} | {}: {} in {:#?}",
+        strip_ansi_codes(&controller_status),
+        strip_ansi_codes(&comparison_status),
+        strip_ansi_codes(&compile_status),
+        test_name,
+        time_elapsed
+    );
+    if let Some(ast_diff) = ast_diff {
+        for line in ast_diff.lines() {
+            println!("    {}", line);
+        }
+        info!("{}", ast_diff);
+    }
+    println!("------------------------------------------------------------------\n");
+
+    Ok(case)
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    error::Error,
+    fs::read_to_string,
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use log::{
+    info,
+    warn,
+};
+
+use syn::{
+    FnArg,
+    Signature,
+};
+
+use crate::tests::{
+    ast::find_signature,
+    extract::{
+        test_files,
+        TestFile,
+    },
+    replay::{
+        line_range,
+        local_clone,
+        read_cases,
+        MinedCase,
+    },
+    report::Tally,
+    tags::{
+        tag_selection,
+        Tag,
+    },
+};
+use crate::utils::read_from_git;
+
+/// The buckets extracted regions are counted in, by how many lines they span.
+const SIZE_BUCKETS: [(usize, usize); 6] = [(1, 1), (2, 5), (6, 10), (11, 20), (21, 50), (51, usize::MAX)];
+
+/// The buckets extracted functions are counted in, by how many parameters they
+/// take (not counting `self`).
+const PARAM_BUCKETS: [(usize, usize); 6] = [(0, 0), (1, 1), (2, 2), (3, 3), (4, 4), (5, usize::MAX)];
+
+/// Options for summarising a corpus of mined cases.
+/// - repos: Folder holding a local clone of every repository in the cases file
+/// - fixtures: A test folder whose extract fixtures are summarised next to the
+///   mined cases, to see how well they represent them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorpusOptions {
+    pub repos: PathBuf,
+    pub fixtures: Option<PathBuf>,
+}
+
+/// What is known about one extraction, either mined or a fixture.
+/// - lines: How many lines the selection spans
+/// - params: How many parameters the extracted function takes, if it could be
+///   found
+/// - tags: The features used by the selection, see `tags::tag_selection`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Sample {
+    lines: usize,
+    params: Option<usize>,
+    tags: BTreeSet<Tag>,
+    generic: bool,
+    is_async: bool,
+}
+
+/// Reads every case in `cases_path` from its repository, and prints what the
+/// corpus is made of. Cases that can't be read are counted as skipped.
+pub fn run_corpus_stats(cases_path: &Path, options: &CorpusOptions) -> io::Result<()> {
+    let cases: Vec<MinedCase> = read_cases(cases_path)?;
+    info!("Summarising {} cases from {:?}", cases.len(), cases_path);
+
+    // The number of cases read from each repository, and the number skipped
+    let mut repos: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    let mut corpus: Vec<Sample> = Vec::new();
+    for mined in &cases {
+        let counts: &mut (usize, usize) = repos.entry(mined.repo.as_str()).or_default();
+        match mined_sample(mined, &options.repos) {
+            Ok(sample) => {
+                counts.0 += 1;
+                corpus.push(sample);
+            }
+            Err(e) => {
+                warn!("Skipping {}: {}", mined.case_name(), e);
+                counts.1 += 1;
+            }
+        }
+    }
+
+    let fixtures: Option<Vec<Sample>> = match &options.fixtures {
+        Some(path) => Some(fixture_samples(path)?),
+        None => None,
+    };
+
+    print_stats(&corpus, fixtures.as_deref(), &repos);
+    Ok(())
+}
+
+/// Reads a mined case's selection from the commit before the refactoring, and
+/// the extracted function from the commit after it.
+fn mined_sample(mined: &MinedCase, repos: &Path) -> Result<Sample, Box<dyn Error>> {
+    let local: PathBuf = local_clone(repos, &mined.repo)?;
+    let source: String = read_from_git(&local, &mined.pre_sha, &mined.pre_file)?;
+    let (start_idx, end_idx) = line_range(&source, mined.start_line, mined.end_line)
+        .ok_or_else(|| format!("Lines {}..={} are not in {}", mined.start_line, mined.end_line, mined.pre_file))?;
+    let tags: BTreeSet<Tag> = tag_selection(&source, start_idx, end_idx)?;
+
+    // The selection still counts if the function can't be found afterwards
+    let signature: Option<Signature> = read_from_git(&local, &mined.post_sha, &mined.post_file)
+        .ok()
+        .and_then(|source| find_signature(&source, &mined.fn_name).ok().flatten());
+
+    Ok(Sample {
+        lines: mined.end_line - mined.start_line + 1,
+        params: signature.as_ref().map(params),
+        tags,
+        generic: mined.generic,
+        is_async: mined.is_async,
+    })
+}
+
+/// Every extract fixture in `path` that can be read. The extracted function is
+/// looked up in the fixture's expected output.
+fn fixture_samples(path: &Path) -> io::Result<Vec<Sample>> {
+    let mut samples: Vec<Sample> = Vec::new();
+    for test_file in test_files(path)? {
+        match fixture_sample(path, &test_file) {
+            Ok(sample) => samples.push(sample),
+            Err(e) => warn!("Skipping fixture {}: {}", test_file.input_file, e),
+        }
+    }
+    Ok(samples)
+}
+
+fn fixture_sample(path: &Path, test_file: &TestFile) -> Result<Sample, Box<dyn Error>> {
+    let input_path: PathBuf = path.join("extract/input").join(&test_file.input_file).join(&test_file.file);
+    let source: String = read_to_string(&input_path)?;
+    let tags: BTreeSet<Tag> = tag_selection(&source, test_file.start_idx, test_file.end_idx)?;
+
+    let expected_path: PathBuf = path.join("extract/correct_output").join(&test_file.input_file).with_extension("rs");
+    let signature: Option<Signature> = read_to_string(expected_path)
+        .ok()
+        .and_then(|expected| find_signature(&expected, &test_file.new_fn_name).ok().flatten());
+
+    Ok(Sample {
+        lines: lines_spanned(&source, test_file.start_idx as usize, test_file.end_idx as usize),
+        params: signature.as_ref().map(params),
+        tags,
+        generic: signature.as_ref().is_some_and(|signature| !signature.generics.params.is_empty()),
+        is_async: signature.as_ref().is_some_and(|signature| signature.asyncness.is_some()),
+    })
+}
+
+/// The number of parameters a function takes, not counting `self`.
+fn params(signature: &Signature) -> usize {
+    signature.inputs.iter().filter(|input| matches!(input, FnArg::Typed(_))).count()
+}
+
+/// The number of lines the bytes `start..end` of `source` are on.
+fn lines_spanned(source: &str, start: usize, end: usize) -> usize {
+    let newlines = |end: usize| source.as_bytes()[..end.min(source.len())].iter().filter(|byte| **byte == b'\n').count();
+    newlines(end.max(start)) - newlines(start) + 1
+}
+
+/// How many of the samples match.
+fn count(samples: &[Sample], matches: &dyn Fn(&Sample) -> bool) -> Tally {
+    Tally {
+        passed: samples.iter().filter(|sample| matches(sample)).count(),
+        total: samples.len(),
+    }
+}
+
+fn in_bucket(value: usize, (low, high): (usize, usize)) -> bool {
+    low <= value && value <= high
+}
+
+fn bucket_label((low, high): (usize, usize)) -> String {
+    match high {
+        usize::MAX => format!("{}+", low),
+        _ if low == high => low.to_string(),
+        _ => format!("{}-{}", low, high),
+    }
+}
+
+fn median(samples: &[Sample]) -> String {
+    let mut lines: Vec<usize> = samples.iter().map(|sample| sample.lines).collect();
+    lines.sort();
+    lines.get(lines.len() / 2).map(usize::to_string).unwrap_or_else(|| "-".to_string())
+}
+
+/// Prints the corpus next to the fixtures (if there are any): each row is how
+/// many of the samples are in a size or parameter bucket, or have a feature.
+fn print_stats(corpus: &[Sample], fixtures: Option<&[Sample]>, repos: &BTreeMap<&str, (usize, usize)>) {
+    let columns: Vec<&[Sample]> = [Some(corpus), fixtures].into_iter().flatten().collect();
+    let row = |label: &str, matches: &dyn Fn(&Sample) -> bool| {
+        let cells: Vec<String> = columns.iter().map(|samples| format!("{:>18}", count(samples, matches).to_string())).collect();
+        println!("    {:<20}{}", label, cells.join(""));
+    };
+
+    let skipped: usize = repos.values().map(|(_, skipped)| skipped).sum();
+    println!("------------------------------------------------------------------");
+    println!("Corpus: {} cases from {} repositories ({} skipped)", corpus.len(), repos.len(), skipped);
+    info!("Corpus: {} cases from {} repositories ({} skipped)", corpus.len(), repos.len(), skipped);
+    for (repo, (cases, skipped)) in repos {
+        let share: Tally = Tally { passed: *cases, total: corpus.len() };
+        match skipped {
+            0 => println!("    {:<20}{:>18}", repo, share.to_string()),
+            _ => println!("    {:<20}{:>18}  ({} skipped)", repo, share.to_string(), skipped),
+        }
+    }
+
+    let header: Vec<String> = ["corpus", "fixtures"].iter().take(columns.len()).map(|name| format!("{:>18}", name)).collect();
+    println!("------------------------------------------------------------------");
+    println!("{:<24}{}", "Region size (lines)", header.join(""));
+    for bucket in SIZE_BUCKETS {
+        row(&bucket_label(bucket), &|sample| in_bucket(sample.lines, bucket));
+    }
+    let medians: Vec<String> = columns.iter().map(|samples| format!("{:>18}", median(samples))).collect();
+    println!("    {:<20}{}", "median", medians.join(""));
+
+    println!("{:<24}{}", "Parameters", header.join(""));
+    for bucket in PARAM_BUCKETS {
+        row(&bucket_label(bucket), &|sample| sample.params.is_some_and(|params| in_bucket(params, bucket)));
+    }
+    if columns.iter().any(|samples| samples.iter().any(|sample| sample.params.is_none())) {
+        row("not found", &|sample| sample.params.is_none());
+    }
+
+    println!("{:<24}{}", "Features", header.join(""));
+    for tag in Tag::ALL {
+        row(tag.name(), &|sample| sample.tags.contains(&tag));
+    }
+    row("generic fn", &|sample| sample.generic);
+    row("async fn", &|sample| sample.is_async);
+    println!("------------------------------------------------------------------");
+}
@@ -0,0 +1,615 @@
+use std::fmt;
+
+use proc_macro2::{
+    Span,
+    TokenStream,
+};
+
+use quote::ToTokens;
+
+use syn::{
+    spanned::Spanned,
+    Attribute,
+    FnArg,
+    ImplItem,
+    Item,
+    Local,
+    Pat,
+    ReturnType,
+    Signature,
+    Stmt,
+};
+
+use crate::tests::ast::{
+    normalise_file,
+    render,
+    CompareOptions,
+};
+
+/// A line and (1-based) column in one of the files being compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A single difference between the ASTs of the output and the expected file.
+/// - actual: Where the differing node is in the output, if it exists there
+/// - expected: Where the differing node is in the expected file, if it exists
+///   there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AstDifference {
+    pub description: String,
+    pub actual: Option<Location>,
+    pub expected: Option<Location>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl fmt::Display for AstDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = |location: Option<Location>| match location {
+            Some(location) => location.to_string(),
+            None => "-".to_string(),
+        };
+        write!(
+            f,
+            "[output {}, expected {}] {}",
+            location(self.actual),
+            location(self.expected),
+            self.description,
+        )
+    }
+}
+
+impl Location {
+    fn of(node: &impl Spanned) -> Location {
+        let span: Span = node.span();
+        Location {
+            line: span.start().line,
+            column: span.start().column + 1,
+        }
+    }
+}
+
+/// Explains why `actual` and `expected` have different ASTs, naming the
+/// differing nodes (items, parameters, return types, statements) and where
+/// they are in each file. Both files are normalised with `options` first, so
+/// only differences that `compare_ast` would also see are reported, and
+/// renamed bindings are shown under their normalised names.
+pub fn diff_ast(actual: &str, expected: &str, options: &CompareOptions) -> Result<Vec<AstDifference>, syn::Error> {
+    let actual_file: syn::File = normalise_file(actual, options)?;
+    let expected_file: syn::File = normalise_file(expected, options)?;
+
+    let mut differ: Differ = Differ {
+        options,
+        differences: Vec::new(),
+    };
+    differ.diff_items(&actual_file.items, &expected_file.items, "");
+    Ok(differ.differences)
+}
+
+/// Formats the differences one per line, for check messages and the console.
+pub fn describe_differences(differences: &[AstDifference]) -> String {
+    if differences.is_empty() {
+        return "ASTs differ, but no structural difference was found".to_string();
+    }
+    differences
+        .iter()
+        .map(|difference| difference.to_string())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// The message recorded against a failed AST check: which expected file the
+/// output didn't match, followed by the structural differences.
+pub fn explain_mismatch(actual: &str, expected: &str, options: &CompareOptions, expected_path: &str) -> String {
+    match diff_ast(actual, expected, options) {
+        Ok(differences) => format!(
+            "Output does not match {}:\n{}",
+            expected_path,
+            describe_differences(&differences),
+        ),
+        Err(e) => format!("Output does not match {} (no structural diff: {})", expected_path, e),
+    }
+}
+
+struct Differ<'a> {
+    options: &'a CompareOptions,
+    differences: Vec<AstDifference>,
+}
+
+impl Differ<'_> {
+    fn same(&self, actual: &impl ToTokens, expected: &impl ToTokens) -> bool {
+        render(actual, self.options) == render(expected, self.options)
+    }
+
+    fn push(&mut self, description: String, actual: Option<Location>, expected: Option<Location>) {
+        self.differences.push(AstDifference {
+            description,
+            actual,
+            expected,
+        });
+    }
+
+    /// Items are paired up by kind and name. Anything without a partner is
+    /// missing or extra, and paired items that differ are compared further.
+    fn diff_items(&mut self, actual: &[Item], expected: &[Item], scope: &str) {
+        let mut unmatched: Vec<&Item> = actual.iter().collect();
+
+        for expected_item in expected {
+            let key: String = item_key(expected_item);
+            match unmatched.iter().position(|item| item_key(item) == key) {
+                Some(position) => {
+                    let actual_item: &Item = unmatched.remove(position);
+                    self.diff_item(actual_item, expected_item, scope);
+                }
+                None => self.push(
+                    format!("missing {}{}", scope, key),
+                    None,
+                    Some(Location::of(expected_item)),
+                ),
+            }
+        }
+
+        for item in unmatched {
+            self.push(
+                format!("unexpected {}{}", scope, item_key(item)),
+                Some(Location::of(item)),
+                None,
+            );
+        }
+    }
+
+    fn diff_item(&mut self, actual: &Item, expected: &Item, scope: &str) {
+        if self.same(actual, expected) {
+            return;
+        }
+
+        match (actual, expected) {
+            (Item::Fn(actual), Item::Fn(expected)) => {
+                let name: String = format!("{}fn {}", scope, expected.sig.ident);
+                if !self.same(&attrs_tokens(&actual.attrs), &attrs_tokens(&expected.attrs)) || !self.same(&actual.vis, &expected.vis) {
+                    self.push(
+                        format!("{}: attributes or visibility changed", name),
+                        Some(Location::of(actual)),
+                        Some(Location::of(expected)),
+                    );
+                }
+                self.diff_signature(&actual.sig, &expected.sig, &name);
+                self.diff_stmts(&actual.block.stmts, &expected.block.stmts, &name);
+            }
+            (Item::Impl(actual), Item::Impl(expected)) if self.same(&actual.self_ty, &expected.self_ty) => {
+                let scope: String = format!("{}impl {}::", scope, render(&expected.self_ty, self.options));
+                let actual_fns: Vec<Item> = impl_fns(&actual.items);
+                let expected_fns: Vec<Item> = impl_fns(&expected.items);
+                if actual_fns.len() == actual.items.len() && expected_fns.len() == expected.items.len() {
+                    self.diff_items(&actual_fns, &expected_fns, &scope);
+                } else {
+                    self.item_changed(actual, expected, &scope);
+                }
+            }
+            (Item::Mod(actual), Item::Mod(expected)) => {
+                match (&actual.content, &expected.content) {
+                    (Some((_, actual_items)), Some((_, expected_items))) => {
+                        let scope: String = format!("{}{}::", scope, expected.ident);
+                        self.diff_items(actual_items, expected_items, &scope);
+                    }
+                    _ => self.item_changed(actual, expected, &format!("{}mod {}", scope, expected.ident)),
+                }
+            }
+            _ => self.item_changed(actual, expected, &format!("{}{}", scope, item_key(expected))),
+        }
+    }
+
+    fn item_changed(&mut self, actual: &impl ToTokens, expected: &impl ToTokens, name: &str) {
+        self.push(
+            format!("{} changed", name.trim_end_matches("::")),
+            Some(Location::of(actual)),
+            Some(Location::of(expected)),
+        );
+    }
+
+    fn diff_signature(&mut self, actual: &Signature, expected: &Signature, name: &str) {
+        if actual.asyncness.is_some() != expected.asyncness.is_some()
+            || actual.constness.is_some() != expected.constness.is_some()
+            || actual.unsafety.is_some() != expected.unsafety.is_some()
+        {
+            self.push(
+                format!(
+                    "{}: qualifiers changed from `{}` to `{}`",
+                    name,
+                    qualifiers(expected),
+                    qualifiers(actual),
+                ),
+                Some(Location::of(actual)),
+                Some(Location::of(expected)),
+            );
+        }
+
+        if !self.same(&actual.generics, &expected.generics)
+            || !self.same(&actual.generics.where_clause, &expected.generics.where_clause)
+        {
+            self.push(
+                format!(
+                    "{}: generics changed from `{}` to `{}`",
+                    name,
+                    self.render_generics(expected),
+                    self.render_generics(actual),
+                ),
+                Some(Location::of(&actual.generics)),
+                Some(Location::of(&expected.generics)),
+            );
+        }
+
+        for (index, (actual_arg, expected_arg)) in actual.inputs.iter().zip(expected.inputs.iter()).enumerate() {
+            self.diff_param(index, actual_arg, expected_arg, name);
+        }
+        for (index, extra) in actual.inputs.iter().enumerate().skip(expected.inputs.len()) {
+            self.push(
+                format!("{}: unexpected parameter {} `{}`", name, index + 1, render(extra, self.options)),
+                Some(Location::of(extra)),
+                None,
+            );
+        }
+        for (index, missing) in expected.inputs.iter().enumerate().skip(actual.inputs.len()) {
+            self.push(
+                format!("{}: missing parameter {} `{}`", name, index + 1, render(missing, self.options)),
+                None,
+                Some(Location::of(missing)),
+            );
+        }
+
+        if !self.same(&actual.output, &expected.output) {
+            self.push(
+                format!(
+                    "{}: return type changed from `{}` to `{}`",
+                    name,
+                    self.render_return(&expected.output),
+                    self.render_return(&actual.output),
+                ),
+                Some(Location::of(&return_span(actual))),
+                Some(Location::of(&return_span(expected))),
+            );
+        }
+    }
+
+    fn diff_param(&mut self, index: usize, actual: &FnArg, expected: &FnArg, name: &str) {
+        if self.same(actual, expected) {
+            return;
+        }
+        let param: String = format!("{}: parameter {}", name, index + 1);
+
+        match (actual, expected) {
+            (FnArg::Typed(actual), FnArg::Typed(expected)) => {
+                if !self.same(&actual.pat, &expected.pat) {
+                    let description: String = match mutability_change(&actual.pat, &expected.pat) {
+                        Some(true) => format!("{} `{}` has an extra `mut`", param, render(&expected.pat, self.options)),
+                        Some(false) => format!("{} `{}` is missing `mut`", param, render(&actual.pat, self.options)),
+                        None => format!(
+                            "{} pattern changed from `{}` to `{}`",
+                            param,
+                            render(&expected.pat, self.options),
+                            render(&actual.pat, self.options),
+                        ),
+                    };
+                    self.push(description, Some(Location::of(&actual.pat)), Some(Location::of(&expected.pat)));
+                }
+                if !self.same(&actual.ty, &expected.ty) {
+                    self.push(
+                        format!(
+                            "{} `{}` type changed from `{}` to `{}`",
+                            param,
+                            render(&expected.pat, self.options),
+                            render(&expected.ty, self.options),
+                            render(&actual.ty, self.options),
+                        ),
+                        Some(Location::of(&actual.ty)),
+                        Some(Location::of(&expected.ty)),
+                    );
+                }
+            }
+            _ => self.push(
+                format!(
+                    "{} changed from `{}` to `{}`",
+                    param,
+                    render(expected, self.options),
+                    render(actual, self.options),
+                ),
+                Some(Location::of(actual)),
+                Some(Location::of(expected)),
+            ),
+        }
+    }
+
+    /// Statements are aligned with a longest common subsequence. Unaligned
+    /// statements that appear on both sides have moved; a lone unaligned pair
+    /// in the same gap has changed; anything else is missing or extra.
+    fn diff_stmts(&mut self, actual: &[Stmt], expected: &[Stmt], name: &str) {
+        let actual_rendered: Vec<String> = actual.iter().map(|stmt| render(stmt, self.options)).collect();
+        let expected_rendered: Vec<String> = expected.iter().map(|stmt| render(stmt, self.options)).collect();
+        let aligned: Vec<(usize, usize)> = longest_common_subsequence(&actual_rendered, &expected_rendered);
+
+        // Gaps between aligned statements, including before the first and
+        // after the last
+        let mut gaps: Vec<(Vec<usize>, Vec<usize>)> = Vec::new();
+        let (mut next_actual, mut next_expected) = (0, 0);
+        for &(actual_index, expected_index) in aligned.iter().chain(std::iter::once(&(actual.len(), expected.len()))) {
+            gaps.push((
+                (next_actual..actual_index).collect(),
+                (next_expected..expected_index).collect(),
+            ));
+            next_actual = actual_index + 1;
+            next_expected = expected_index + 1;
+        }
+
+        let mut moved_actual: Vec<usize> = Vec::new();
+        let mut moved_expected: Vec<usize> = Vec::new();
+        let unaligned_actual: Vec<usize> = gaps.iter().flat_map(|(a, _)| a.clone()).collect();
+        let unaligned_expected: Vec<usize> = gaps.iter().flat_map(|(_, e)| e.clone()).collect();
+        for &expected_index in &unaligned_expected {
+            let partner: Option<usize> = unaligned_actual
+                .iter()
+                .copied()
+                .find(|actual_index| {
+                    !moved_actual.contains(actual_index)
+                        && actual_rendered[*actual_index] == expected_rendered[expected_index]
+                });
+            if let Some(actual_index) = partner {
+                moved_actual.push(actual_index);
+                moved_expected.push(expected_index);
+                self.push(
+                    format!(
+                        "{}: statement `{}` moved from position {} to {}",
+                        name,
+                        abbreviate(&expected_rendered[expected_index]),
+                        expected_index + 1,
+                        actual_index + 1,
+                    ),
+                    Some(Location::of(&actual[actual_index])),
+                    Some(Location::of(&expected[expected_index])),
+                );
+            }
+        }
+
+        for (gap_actual, gap_expected) in gaps {
+            let gap_actual: Vec<usize> = gap_actual.into_iter().filter(|i| !moved_actual.contains(i)).collect();
+            let gap_expected: Vec<usize> = gap_expected.into_iter().filter(|i| !moved_expected.contains(i)).collect();
+
+            if let ([actual_index], [expected_index]) = (gap_actual.as_slice(), gap_expected.as_slice()) {
+                self.diff_stmt(&actual[*actual_index], &expected[*expected_index], *expected_index, name);
+                continue;
+            }
+
+            for expected_index in gap_expected {
+                self.push(
+                    format!(
+                        "{}: missing statement {} `{}`",
+                        name,
+                        expected_index + 1,
+                        abbreviate(&expected_rendered[expected_index]),
+                    ),
+                    None,
+                    Some(Location::of(&expected[expected_index])),
+                );
+            }
+            for actual_index in gap_actual {
+                self.push(
+                    format!(
+                        "{}: unexpected statement {} `{}`",
+                        name,
+                        actual_index + 1,
+                        abbreviate(&actual_rendered[actual_index]),
+                    ),
+                    Some(Location::of(&actual[actual_index])),
+                    None,
+                );
+            }
+        }
+    }
+
+    fn diff_stmt(&mut self, actual: &Stmt, expected: &Stmt, index: usize, name: &str) {
+        if let (Stmt::Local(actual_local), Stmt::Local(expected_local)) = (actual, expected) {
+            if self.render_init(actual_local) == self.render_init(expected_local) {
+                if let Some(extra) = mutability_change(&actual_local.pat, &expected_local.pat) {
+                    self.push(
+                        format!(
+                            "{}: `let {}` {}",
+                            name,
+                            render(&expected_local.pat, self.options),
+                            if extra { "has an extra `mut`" } else { "is missing `mut`" },
+                        ),
+                        Some(Location::of(actual)),
+                        Some(Location::of(expected)),
+                    );
+                    return;
+                }
+            }
+        }
+
+        if let (Stmt::Item(actual_item), Stmt::Item(expected_item)) = (actual, expected) {
+            self.diff_item(actual_item, expected_item, &format!("{}::", name));
+            return;
+        }
+
+        self.push(
+            format!(
+                "{}: statement {} changed from `{}` to `{}`",
+                name,
+                index + 1,
+                abbreviate(&render(expected, self.options)),
+                abbreviate(&render(actual, self.options)),
+            ),
+            Some(Location::of(actual)),
+            Some(Location::of(expected)),
+        );
+    }
+
+    /// The initialiser of a `let`, including any `else` block.
+    fn render_init(&self, local: &Local) -> Option<String> {
+        local.init.as_ref().map(|init| match &init.diverge {
+            Some((_, diverge)) => format!("{} else {}", render(&init.expr, self.options), render(diverge, self.options)),
+            None => render(&init.expr, self.options),
+        })
+    }
+
+    fn render_generics(&self, signature: &Signature) -> String {
+        let generics: String = render(&signature.generics, self.options);
+        match &signature.generics.where_clause {
+            Some(where_clause) => format!("{} {}", generics, render(where_clause, self.options)),
+            None => generics,
+        }
+    }
+
+    fn render_return(&self, output: &ReturnType) -> String {
+        match output {
+            ReturnType::Default => "()".to_string(),
+            ReturnType::Type(_, ty) => render(ty, self.options),
+        }
+    }
+}
+
+/// Attributes on their own, so they can be compared separately from the item.
+fn attrs_tokens(attrs: &[Attribute]) -> TokenStream {
+    let mut tokens: TokenStream = TokenStream::new();
+    for attr in attrs {
+        attr.to_tokens(&mut tokens);
+    }
+    tokens
+}
+
+/// Identifies an item by its kind and name, e.g. `fn main` or `struct Foo`.
+fn item_key(item: &Item) -> String {
+    match item {
+        Item::Const(item) => format!("const {}", item.ident),
+        Item::Enum(item) => format!("enum {}", item.ident),
+        Item::ExternCrate(item) => format!("extern crate {}", item.ident),
+        Item::Fn(item) => format!("fn {}", item.sig.ident),
+        Item::Impl(item) => format!(
+            "impl {}{}",
+            item.trait_
+                .as_ref()
+                .map(|(_, path, _)| format!("{} for ", path.to_token_stream()))
+                .unwrap_or_default(),
+            item.self_ty.to_token_stream(),
+        ),
+        Item::Macro(item) => format!("macro {}", item.mac.path.to_token_stream()),
+        Item::Mod(item) => format!("mod {}", item.ident),
+        Item::Static(item) => format!("static {}", item.ident),
+        Item::Struct(item) => format!("struct {}", item.ident),
+        Item::Trait(item) => format!("trait {}", item.ident),
+        Item::TraitAlias(item) => format!("trait {}", item.ident),
+        Item::Type(item) => format!("type {}", item.ident),
+        Item::Union(item) => format!("union {}", item.ident),
+        Item::Use(item) => format!("use {}", item.tree.to_token_stream()),
+        other => other.to_token_stream().to_string(),
+    }
+}
+
+/// Methods of an impl block as free functions, so they can be diffed the same
+/// way.
+fn impl_fns(items: &[ImplItem]) -> Vec<Item> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Fn(method) => Some(Item::Fn(syn::ItemFn {
+                attrs: method.attrs.clone(),
+                vis: method.vis.clone(),
+                sig: method.sig.clone(),
+                block: Box::new(method.block.clone()),
+            })),
+            _ => None,
+        })
+        .collect()
+}
+
+/// If two patterns only differ in whether they bind `mut`, returns whether it
+/// is the actual pattern that has the extra `mut`.
+fn mutability_change(actual: &Pat, expected: &Pat) -> Option<bool> {
+    match (actual, expected) {
+        (Pat::Ident(actual), Pat::Ident(expected))
+            if actual.ident == expected.ident
+                && actual.by_ref.is_some() == expected.by_ref.is_some()
+                && actual.mutability.is_some() != expected.mutability.is_some() =>
+        {
+            Some(actual.mutability.is_some())
+        }
+        (Pat::Type(actual), Pat::Type(expected))
+            if actual.ty.to_token_stream().to_string() == expected.ty.to_token_stream().to_string() =>
+        {
+            mutability_change(&actual.pat, &expected.pat)
+        }
+        _ => None,
+    }
+}
+
+fn qualifiers(signature: &Signature) -> String {
+    let mut qualifiers: Vec<&str> = Vec::new();
+    if signature.constness.is_some() {
+        qualifiers.push("const");
+    }
+    if signature.asyncness.is_some() {
+        qualifiers.push("async");
+    }
+    if signature.unsafety.is_some() {
+        qualifiers.push("unsafe");
+    }
+    qualifiers.push("fn");
+    qualifiers.join(" ")
+}
+
+/// The return type, or the closing parenthesis of the parameters if there
+/// isn't one.
+fn return_span(signature: &Signature) -> TokenStream {
+    match &signature.output {
+        ReturnType::Default => {
+            let mut tokens: TokenStream = TokenStream::new();
+            signature.paren_token.surround(&mut tokens, |_| {});
+            tokens
+        }
+        ReturnType::Type(_, ty) => ty.to_token_stream(),
+    }
+}
+
+/// Keeps statements readable in a one line message.
+fn abbreviate(rendered: &str) -> String {
+    const MAX_LEN: usize = 60;
+    if rendered.chars().count() <= MAX_LEN {
+        rendered.to_string()
+    } else {
+        format!("{}...", rendered.chars().take(MAX_LEN).collect::<String>())
+    }
+}
+
+/// The index pairs of a longest common subsequence of `a` and `b`, in order.
+fn longest_common_subsequence(a: &[String], b: &[String]) -> Vec<(usize, usize)> {
+    let mut lengths: Vec<Vec<usize>> = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
@@ -0,0 +1,629 @@
+use std::{
+    collections::HashSet,
+    env,
+    io,
+    ops::Range,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use log::{
+    info,
+    warn,
+};
+
+use quote::ToTokens;
+
+use syn::{
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit::{
+        self,
+        Visit,
+    },
+    Expr,
+    FnArg,
+    Item,
+    ItemFn,
+    Pat,
+    Signature,
+    Token,
+    Type,
+    UnOp,
+};
+
+use crate::tests::{
+    ast::{
+        find_signature,
+        normalise_signature,
+    },
+    fuzz::{
+        byte_offset,
+        line_starts,
+    },
+    report::CaseResult,
+    verify::{
+        RunOutput,
+        ScratchProject,
+    },
+};
+
+/// The most inputs a harness calls the function with. Generated inputs are
+/// every combination of a few values per parameter, which grows quickly.
+const MAX_INPUTS: usize = 64;
+
+/// How long the harness may take to build and run natively, and under Miri,
+/// which is a lot slower. Each run is also cut short so that the case as a
+/// whole finishes `MARGIN` before its deadline, leaving time to write its
+/// result.
+const HARNESS_TIMEOUT: Duration = Duration::from_secs(60);
+const MIRI_TIMEOUT: Duration = Duration::from_secs(90);
+const MARGIN: Duration = Duration::from_secs(5);
+
+/// The tests in the harness: one comparing the function before and after the
+/// refactoring, and one running each version on its own, for Miri.
+const AGREE_TEST: &str = "rem_equivalence::agree";
+const ORIGINAL_TEST: &str = "rem_equivalence::original";
+const REFACTORED_TEST: &str = "rem_equivalence::refactored";
+
+/// Start the messages the agree test fails with, so that they can be found in
+/// cargo's output.
+const DIVERGED: &str = "REM-DIVERGED";
+const NOTHING_COMPARED: &str = "REM-NOTHING-COMPARED";
+
+/// The test module of the harness. `/* calls */` is replaced with the calls the
+/// harness makes, so that a divergence can say which input caused it. Each call
+/// runs on its own thread and is given up on after a while, as killing cargo
+/// doesn't kill a test stuck in a loop. An input the original doesn't return
+/// on says nothing about the refactoring, so it is skipped, and after a few of
+/// those so is the rest.
+const HARNESS_TESTS: &str = r#"#[cfg(test)]
+mod rem_equivalence {
+    use std::{
+        panic::catch_unwind,
+        sync::mpsc,
+        thread,
+        time::Duration,
+    };
+
+    const CALLS: &[&str] = &[/* calls */];
+
+    const CALL_TIMEOUT: Duration = Duration::from_secs(2);
+
+    const MAX_HANGS: usize = 3;
+
+    /// What the call returned, or `None` if it didn't return in time.
+    fn call(function: fn(usize) -> String, input: usize) -> Option<String> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let output: String = match catch_unwind(|| function(input)) {
+                Ok(output) => output,
+                Err(payload) => match payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                {
+                    Some(message) => format!("panicked with {:?}", message),
+                    None => "panicked".to_string(),
+                },
+            };
+            let _ = sender.send(output);
+        });
+        receiver.recv_timeout(CALL_TIMEOUT).ok()
+    }
+
+    fn call_all(function: fn(usize) -> String) {
+        let mut hangs: usize = 0;
+        for input in 0..CALLS.len() {
+            if call(function, input).is_none() {
+                hangs += 1;
+                if hangs == MAX_HANGS {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn agree() {
+        let mut diverged: Vec<String> = Vec::new();
+        let mut hangs: usize = 0;
+        let mut compared: usize = 0;
+        for (input, call_text) in CALLS.iter().enumerate() {
+            let Some(original) = call(super::original::rem_equivalence_call, input) else {
+                hangs += 1;
+                if hangs == MAX_HANGS {
+                    break;
+                }
+                continue;
+            };
+            let refactored: String = call(super::refactored::rem_equivalence_call, input)
+                .unwrap_or_else(|| format!("didn't return within {:?}", CALL_TIMEOUT));
+            compared += 1;
+            if original != refactored {
+                diverged.push(format!("{} gave {} before and {} after", call_text, original, refactored));
+            }
+        }
+        assert!(compared > 0, "REM-NOTHING-COMPARED");
+        let more: String = match diverged.len() {
+            0..=3 => String::new(),
+            count => format!(" (and {} more)", count - 3),
+        };
+        assert!(diverged.is_empty(), "REM-DIVERGED {}{}", diverged[..diverged.len().min(3)].join("; "), more);
+    }
+
+    #[test]
+    fn original() {
+        call_all(super::original::rem_equivalence_call);
+    }
+
+    #[test]
+    fn refactored() {
+        call_all(super::refactored::rem_equivalence_call);
+    }
+}
+"#;
+
+/// A program that calls the function a selection was extracted from, both
+/// before and after the refactoring, on the same inputs, and checks that the
+/// two agree.
+/// - caller: The function the selection was extracted from
+/// - calls: Each call the harness makes, as it would be written in the program
+/// - code: The harness's `src/main.rs`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Harness {
+    pub caller: String,
+    pub calls: Vec<String>,
+    pub code: String,
+}
+
+/// How an argument is passed to the caller. Values are always bound to a
+/// variable first, so that changes made through a `&mut` can be compared too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Passing {
+    Value,
+    Ref,
+    RefMut,
+}
+
+/// A parameter of the caller.
+/// - name: What it is called, to say what a `&mut` was left holding
+/// - values: The values generated for it, if it is of a primitive type
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Param {
+    name: String,
+    passing: Passing,
+    values: Option<Vec<String>>,
+}
+
+/// What Miri made of one version of the function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MiriVerdict {
+    Clean,
+    Undefined(String),
+    Unknown(String),
+}
+
+impl Passing {
+    /// How `value` is written as an argument.
+    fn pass(&self, value: &str) -> String {
+        match self {
+            Passing::Value => value.to_string(),
+            Passing::Ref => format!("&{}", value),
+            Passing::RefMut => format!("&mut {}", value),
+        }
+    }
+}
+
+/// Generates a harness for a refactoring of the selection `selection` in
+/// `input_code`, which became `output_code`. The inputs are the literal
+/// arguments the function is called with in `tests` (the fixture's own tests),
+/// and combinations of a few values for each parameter, if they are all of
+/// primitive types. Returns why there can't be a harness otherwise.
+pub fn generate_harness(
+    input_code: &str,
+    output_code: &str,
+    selection: Range<u32>,
+    tests: Option<&str>,
+) -> Result<Harness, String> {
+    let input: syn::File = syn::parse_file(input_code).map_err(|e| e.to_string())?;
+    let caller: &ItemFn = enclosing_fn(input_code, &input, selection)
+        .ok_or_else(|| "the selection isn't in a free function, so there is nothing to call".to_string())?;
+    let signature: &Signature = &caller.sig;
+    let name: String = signature.ident.to_string();
+    if !signature.generics.params.is_empty() {
+        return Err(format!("{} is generic", name));
+    }
+    if signature.asyncness.is_some() || signature.unsafety.is_some() {
+        return Err(format!("{} is async or unsafe", name));
+    }
+
+    let refactored: Signature = find_signature(output_code, &name)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("{} is not in the refactored program", name))?;
+    if normalise_signature(signature) != normalise_signature(&refactored) {
+        return Err(format!("the refactoring changed the signature of {}", name));
+    }
+
+    let params: Vec<Param> = signature
+        .inputs
+        .iter()
+        .enumerate()
+        .filter_map(|(index, input)| match input {
+            FnArg::Typed(typed) => {
+                let name: String = match &*typed.pat {
+                    Pat::Ident(ident) => ident.ident.to_string(),
+                    _ => format!("argument {}", index + 1),
+                };
+                Some(param(name, &typed.ty))
+            }
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+
+    let mut inputs: Vec<Vec<String>> = tests.map(|tests| test_inputs(tests, &name, &params)).unwrap_or_default();
+    inputs.extend(generated_inputs(&params));
+    let mut seen: HashSet<Vec<String>> = HashSet::new();
+    inputs.retain(|input| seen.insert(input.clone()));
+    inputs.truncate(MAX_INPUTS);
+    if inputs.is_empty() {
+        return Err(format!(
+            "{} takes parameters that can't be generated, and isn't called with literals in the fixture's tests",
+            name,
+        ));
+    }
+
+    let calls: Vec<String> = inputs
+        .iter()
+        .map(|input| {
+            let args: Vec<String> = input.iter().zip(&params).map(|(value, param)| param.passing.pass(value)).collect();
+            format!("{}({})", name, args.join(", "))
+        })
+        .collect();
+    let wrapper: String = call_wrapper(&name, &params, &inputs);
+    Ok(Harness {
+        code: harness_code(input_code, output_code, &wrapper, &calls),
+        caller: name,
+        calls,
+    })
+}
+
+/// Builds and runs a harness in the current directory, recording whether the
+/// function behaves the same before and after the refactoring
+/// (`equivalence`), and, if `miri` is set and Miri is installed, whether the
+/// refactoring introduced undefined behaviour (`miri`). A harness that doesn't
+/// build or finish says nothing about the refactoring, so it isn't recorded.
+/// Nothing is run past `deadline`, when the case would be killed.
+pub fn check_equivalence(
+    fixture_dir: &Path,
+    name: &str,
+    harness: &Harness,
+    miri: bool,
+    deadline: Instant,
+    case: &mut CaseResult,
+) -> io::Result<()> {
+    let scratch_dir: PathBuf = env::current_dir()?.join("equivalence").join(name);
+    let project: ScratchProject = ScratchProject::new(fixture_dir, &harness.code, &scratch_dir.join("harness"))?;
+    let target_dir: PathBuf = scratch_dir.join("target");
+
+    let Some(timeout) = budget(HARNESS_TIMEOUT, deadline) else {
+        info!("No time is left to run the equivalence harness for {}, skipping it", name);
+        return Ok(());
+    };
+    let agree: RunOutput = project.run_test(&target_dir, AGREE_TEST, false, timeout)?;
+    if agree.success() {
+        case.add_check("equivalence", true, None);
+    } else if let Some(divergence) = divergence(&agree) {
+        case.add_check("equivalence", false, Some(format!("Original vs refactored {}: {}", harness.caller, divergence)));
+    } else if agree.stdout.contains(NOTHING_COMPARED) {
+        info!("The original {} didn't return on any input, skipping the equivalence check", harness.caller);
+        return Ok(());
+    } else if agree.timed_out {
+        info!("The equivalence harness for {} timed out, skipping it", name);
+        return Ok(());
+    } else {
+        warn!("The equivalence harness for {} didn't build: {}", name, agree.stderr.trim());
+        return Ok(());
+    }
+
+    if !miri {
+        return Ok(());
+    }
+    if !project.miri_available() {
+        info!("Miri isn't installed, so {} isn't checked for undefined behaviour", name);
+        return Ok(());
+    }
+    let Some(timeout) = budget(MIRI_TIMEOUT, deadline) else {
+        info!("No time is left to run {} under Miri, skipping it", name);
+        return Ok(());
+    };
+    match miri_verdict(&project.run_test(&target_dir, REFACTORED_TEST, true, timeout)?) {
+        MiriVerdict::Clean => case.add_check("miri", true, None),
+        MiriVerdict::Undefined(report) => {
+            // Only undefined behaviour the refactoring introduced counts
+            let Some(timeout) = budget(MIRI_TIMEOUT, deadline) else {
+                info!("No time is left to run {} under Miri before refactoring, skipping the check", name);
+                return Ok(());
+            };
+            match miri_verdict(&project.run_test(&target_dir, ORIGINAL_TEST, true, timeout)?) {
+                MiriVerdict::Clean => case.add_check(
+                    "miri",
+                    false,
+                    Some(format!("Miri found undefined behaviour in {} after refactoring: {}", harness.caller, report)),
+                ),
+                _ => info!("Miri found undefined behaviour in {} before refactoring too, skipping the check", name),
+            }
+        }
+        MiriVerdict::Unknown(reason) => info!("Miri couldn't check {}: {}", name, reason),
+    }
+    Ok(())
+}
+
+/// How long a run limited to `limit` may take without running into the last
+/// `MARGIN` before `deadline`, or None if that time has already come.
+fn budget(limit: Duration, deadline: Instant) -> Option<Duration> {
+    let remaining: Duration = deadline.saturating_duration_since(Instant::now()).saturating_sub(MARGIN);
+    (!remaining.is_zero()).then(|| remaining.min(limit))
+}
+
+/// The free function at the top level of `file` whose body contains the
+/// selection.
+fn enclosing_fn<'a>(source: &str, file: &'a syn::File, selection: Range<u32>) -> Option<&'a ItemFn> {
+    let line_starts: Vec<usize> = line_starts(source);
+    file.items.iter().find_map(|item| match item {
+        Item::Fn(item_fn) => {
+            let start: usize = byte_offset(source, &line_starts, item_fn.block.span().start())?;
+            let end: usize = byte_offset(source, &line_starts, item_fn.block.span().end())?;
+            (start <= selection.start as usize && selection.end as usize <= end).then_some(item_fn)
+        }
+        _ => None,
+    })
+}
+
+fn param(name: String, ty: &Type) -> Param {
+    match ty {
+        Type::Reference(reference) if reference.mutability.is_none() && is_str(&reference.elem) => Param {
+            name,
+            passing: Passing::Value,
+            values: Some(vec!["\"\"".to_string(), "\"rem\"".to_string()]),
+        },
+        Type::Reference(reference) => Param {
+            name,
+            passing: if reference.mutability.is_some() { Passing::RefMut } else { Passing::Ref },
+            values: primitive_values(&reference.elem),
+        },
+        _ => Param {
+            name,
+            passing: Passing::Value,
+            values: primitive_values(ty),
+        },
+    }
+}
+
+fn is_str(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("str"))
+}
+
+/// A few values of a primitive type, including its edge cases.
+fn primitive_values(ty: &Type) -> Option<Vec<String>> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let name: String = path.path.get_ident()?.to_string();
+    let values: Vec<String> = match name.as_str() {
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => vec![
+            "0".to_string(),
+            "1".to_string(),
+            "-1".to_string(),
+            format!("{}::MIN", name),
+            format!("{}::MAX", name),
+        ],
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => vec!["0".to_string(), "1".to_string(), format!("{}::MAX", name)],
+        "f32" | "f64" => vec!["0.0".to_string(), "1.5".to_string(), "-1.5".to_string()],
+        "bool" => vec!["false".to_string(), "true".to_string()],
+        "char" => vec!["'a'".to_string(), "'0'".to_string()],
+        "String" => vec!["String::new()".to_string(), "String::from(\"rem\")".to_string()],
+        _ => return None,
+    };
+    Some(values)
+}
+
+/// Every combination of the parameters' values, or nothing if any of them
+/// can't be generated. A function without parameters is called once.
+fn generated_inputs(params: &[Param]) -> Vec<Vec<String>> {
+    let mut inputs: Vec<Vec<String>> = vec![Vec::new()];
+    for param in params {
+        let Some(values) = &param.values else {
+            return Vec::new();
+        };
+        inputs = inputs
+            .iter()
+            .flat_map(|input| values.iter().map(move |value| [input.as_slice(), std::slice::from_ref(value)].concat()))
+            .take(MAX_INPUTS)
+            .collect();
+    }
+    inputs
+}
+
+/// The arguments of every call to `caller` in `tests` that only passes
+/// literals. References are dropped from arguments passed by reference, as
+/// the harness adds them back itself.
+fn test_inputs(tests: &str, caller: &str, params: &[Param]) -> Vec<Vec<String>> {
+    let Ok(file) = syn::parse_file(tests) else {
+        return Vec::new();
+    };
+    let mut collector: CallCollector = CallCollector {
+        caller,
+        params,
+        inputs: Vec::new(),
+    };
+    collector.visit_file(&file);
+    collector.inputs
+}
+
+struct CallCollector<'a> {
+    caller: &'a str,
+    params: &'a [Param],
+    inputs: Vec<Vec<String>>,
+}
+
+impl<'ast> Visit<'ast> for CallCollector<'_> {
+    /// Tests mostly call the function inside `assert!`s, whose arguments syn
+    /// leaves as tokens. Those that are a list of expressions are visited too.
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        if let Ok(args) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        visit::visit_macro(self, mac);
+    }
+
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        let calls_caller: bool = matches!(
+            &*call.func,
+            Expr::Path(path) if path.path.segments.last().is_some_and(|segment| segment.ident == self.caller)
+        );
+        if calls_caller && call.args.len() == self.params.len() {
+            let input: Option<Vec<String>> = call
+                .args
+                .iter()
+                .zip(self.params)
+                .map(|(arg, param)| {
+                    let arg: &Expr = match (arg, param.passing) {
+                        (Expr::Reference(reference), Passing::Ref | Passing::RefMut) => &reference.expr,
+                        (arg, _) => arg,
+                    };
+                    is_literal(arg).then(|| arg.span().source_text().unwrap_or_else(|| arg.to_token_stream().to_string()))
+                })
+                .collect();
+            if let Some(input) = input {
+                self.inputs.push(input);
+            }
+        }
+        visit::visit_expr_call(self, call);
+    }
+}
+
+/// Whether an expression is made of literals only, e.g. `-1`, `"a"` or
+/// `&[1, 2]`.
+fn is_literal(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(_) => true,
+        Expr::Unary(unary) => matches!(unary.op, UnOp::Neg(_)) && is_literal(&unary.expr),
+        Expr::Reference(reference) => reference.mutability.is_none() && is_literal(&reference.expr),
+        Expr::Array(array) => array.elems.iter().all(is_literal),
+        Expr::Tuple(tuple) => tuple.elems.iter().all(is_literal),
+        Expr::Paren(paren) => is_literal(&paren.expr),
+        _ => false,
+    }
+}
+
+/// A function that makes the `input`th call, and describes what it returned
+/// and what it left behind in the values it was passed by `&mut`. The same
+/// function is added to both versions of the program.
+fn call_wrapper(caller: &str, params: &[Param], inputs: &[Vec<String>]) -> String {
+    let mut wrapper: String = String::from("\n    pub(crate) fn rem_equivalence_call(input: usize) -> String {\n        match input {\n");
+    for (index, input) in inputs.iter().enumerate() {
+        wrapper.push_str(&format!("            {} => {{\n", index));
+        let mut args: Vec<String> = Vec::new();
+        for (arg, (value, param)) in input.iter().zip(params).enumerate() {
+            wrapper.push_str(&format!("                let mut a{} = {};\n", arg, value));
+            args.push(param.passing.pass(&format!("a{}", arg)));
+        }
+        wrapper.push_str(&format!("                let result = {}({});\n", caller, args.join(", ")));
+        wrapper.push_str("                let mut output: String = format!(\"{:?}\", result);\n");
+        for (arg, param) in params.iter().enumerate().filter(|(_, param)| param.passing == Passing::RefMut) {
+            wrapper.push_str(&format!("                output.push_str(&format!(\", {} = {{:?}}\", a{}));\n", param.name, arg));
+        }
+        wrapper.push_str("                output\n            }\n");
+    }
+    wrapper.push_str("            _ => unreachable!(),\n        }\n    }\n");
+    wrapper
+}
+
+/// The harness: both versions of the program as modules, each with the call
+/// wrapper, and the tests that compare them. Inner attributes (e.g.
+/// `#![feature(...)]`) have to be at the root, so they are moved there.
+fn harness_code(input_code: &str, output_code: &str, wrapper: &str, calls: &[String]) -> String {
+    let mut attributes: Vec<String> = vec!["#![allow(warnings)]".to_string()];
+    let mut modules: String = String::new();
+    for (module, code) in [("original", input_code), ("refactored", output_code)] {
+        let mut body: String = String::new();
+        for line in code.lines() {
+            if line.trim_start().starts_with("#![") {
+                if !attributes.contains(&line.trim().to_string()) {
+                    attributes.push(line.trim().to_string());
+                }
+            } else {
+                body.push_str(line);
+                body.push('\n');
+            }
+        }
+        modules.push_str(&format!("mod {} {{\n{}{}}}\n\n", module, nest_crate_paths(&body, module), wrapper));
+    }
+
+    let calls: Vec<String> = calls.iter().map(|call| format!("{:?}", call)).collect();
+    format!(
+        "{}\n\n{}fn main() {{}}\n\n{}",
+        attributes.join("\n"),
+        modules,
+        HARNESS_TESTS.replace("/* calls */", &calls.join(", ")),
+    )
+}
+
+/// Points `crate::` paths at the module the program was moved into. `$crate`
+/// is left alone, as it already means the crate the macro is defined in.
+fn nest_crate_paths(code: &str, module: &str) -> String {
+    const CRATE: &str = "crate::";
+    let mut nested: String = String::with_capacity(code.len());
+    let mut rest: &str = code;
+    while let Some(position) = rest.find(CRATE) {
+        let (before, after) = rest.split_at(position);
+        nested.push_str(before);
+        let is_path: bool = !before.ends_with(|c: char| c == '$' || c == '_' || c.is_alphanumeric());
+        nested.push_str(CRATE);
+        if is_path {
+            nested.push_str(&format!("{}::", module));
+        }
+        rest = &after[CRATE.len()..];
+    }
+    nested.push_str(rest);
+    nested
+}
+
+/// The inputs the agree test found the two versions disagreeing on.
+fn divergence(output: &RunOutput) -> Option<String> {
+    output
+        .stdout
+        .lines()
+        .chain(output.stderr.lines())
+        .find_map(|line| line.split_once(DIVERGED))
+        .map(|(_, divergence)| divergence.trim().to_string())
+}
+
+fn miri_verdict(output: &RunOutput) -> MiriVerdict {
+    if output.success() {
+        return MiriVerdict::Clean;
+    }
+    if output.timed_out {
+        return MiriVerdict::Unknown("timed out".to_string());
+    }
+    let mut lines = output.stderr.lines().skip_while(|line| !line.contains("Undefined Behavior"));
+    match lines.next() {
+        Some(first) => {
+            let report: Vec<&str> = std::iter::once(first).chain(lines.take(4)).map(str::trim).collect();
+            MiriVerdict::Undefined(report.join(" "))
+        }
+        None => {
+            let error: &str = output.stderr.lines().find(|line| line.starts_with("error")).unwrap_or("no error message");
+            MiriVerdict::Unknown(error.trim().to_string())
+        }
+    }
+}
@@ -0,0 +1,531 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs::{self, read_to_string},
+    io,
+    ops::Range,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use clap::ValueEnum;
+
+use colored::Colorize;
+
+use log::{
+    error,
+    info,
+    warn,
+};
+
+use serde::Deserialize;
+
+use crate::tests::{
+    ast::{
+        compare_ast,
+        CompareOptions,
+    },
+    baseline::extract_function,
+    diff::explain_mismatch,
+    equivalence::{
+        check_equivalence,
+        generate_harness,
+        Harness,
+    },
+    extract::verify_output,
+    harness::{
+        run_in_child,
+        run_parallel,
+        write_result_catching_panics,
+        HarnessOptions,
+        DEFAULT_TIMEOUT_SECS,
+    },
+    pipeline::{
+        check_selection,
+        STAGES,
+    },
+    report::{
+        CaseResult,
+        SuiteReport,
+        TestReport,
+        Tally,
+    },
+    tags::tag_selection,
+};
+
+/// Options for an evaluation run.
+/// - jobs: The number of pairs that are evaluated at the same time
+/// - timeout: How long a single pair may run before it is killed
+/// - compare: How outputs are compared with the expected outputs
+/// - baseline: Also evaluate rust-analyzer's extract function on every pair
+/// - miri: Run each pair's equivalence harness under Miri too, if it is
+///   installed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalOptions {
+    pub jobs: usize,
+    pub timeout: Duration,
+    pub compare: CompareOptions,
+    pub baseline: bool,
+    pub miri: bool,
+}
+
+impl Default for EvalOptions {
+    fn default() -> Self {
+        Self {
+            jobs: 1,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            compare: CompareOptions::default(),
+            baseline: false,
+            miri: true,
+        }
+    }
+}
+
+/// What refactors the pairs: the REM pipeline, or rust-analyzer's extract
+/// function on its own as the baseline REM is meant to improve on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Tool {
+    Rem,
+    RustAnalyzer,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 2] = [Tool::Rem, Tool::RustAnalyzer];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::Rem => "rem",
+            Tool::RustAnalyzer => "rust-analyzer",
+        }
+    }
+
+    /// The suite the tool's results are reported under.
+    pub fn suite(&self) -> &'static str {
+        match self {
+            Tool::Rem => "eval",
+            Tool::RustAnalyzer => "eval-rust-analyzer",
+        }
+    }
+
+    /// The checks that make up a successful refactoring by the tool, before its
+    /// output is compiled and compared.
+    fn stages(&self) -> &'static [&'static str] {
+        match self {
+            Tool::Rem => &STAGES,
+            Tool::RustAnalyzer => &["extraction"],
+        }
+    }
+}
+
+/// One of the evaluation cases: `extraction/<id>_<name>.rs` is refactored and
+/// compared with `extraction/<id>s_<...>.rs`. If there is a
+/// `verification/<id>_<name>.rs`, it is added to both programs as a test
+/// module and their `cargo test` results are compared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalPair {
+    pub id: u32,
+    pub name: String,
+    pub input: PathBuf,
+    pub expected: PathBuf,
+    pub verification: Option<PathBuf>,
+    pub start_idx: u32,
+    pub end_idx: u32,
+}
+
+/// A row of `extraction/selections.csv`, giving the selection for an input
+/// file. The pairs don't carry their selection themselves.
+#[derive(Debug, Deserialize)]
+struct SelectionRow {
+    file: String,
+    start_idx: u32,
+    end_idx: u32,
+}
+
+impl EvalPair {
+    /// The name cases are reported under, e.g. `0_break_loop`.
+    pub fn case_name(&self) -> String {
+        format!("{}_{}", self.id, self.name)
+    }
+
+    /// The language features used by the selection, see `tags::tag_selection`.
+    pub fn tags(&self) -> Vec<String> {
+        let tags = read_to_string(&self.input)
+            .map_err(|e| e.to_string())
+            .and_then(|source| tag_selection(&source, self.start_idx, self.end_idx).map_err(|e| e.to_string()));
+        match tags {
+            Ok(tags) => tags.iter().map(|tag| tag.name().to_string()).collect(),
+            Err(e) => {
+                warn!("Failed to tag eval pair {}: {}", self.case_name(), e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Finds every pair in `<folder>/extraction`. Input files without an expected
+/// file or a selection are skipped with a warning.
+pub fn pairs(folder: &Path) -> io::Result<Vec<EvalPair>> {
+    let extraction_dir: PathBuf = folder.join("extraction");
+    let selections: HashMap<String, (u32, u32)> = read_selections(&extraction_dir.join("selections.csv"))?;
+
+    let mut inputs: Vec<(u32, String, PathBuf)> = Vec::new();
+    let mut expected: HashMap<u32, PathBuf> = HashMap::new();
+    for entry in fs::read_dir(&extraction_dir)? {
+        let path: PathBuf = entry?.path();
+        let Some(stem) = path.file_stem().map(|stem| stem.to_string_lossy().to_string()) else {
+            continue;
+        };
+        if path.extension().is_none_or(|extension| extension != "rs") {
+            continue;
+        }
+        let Some((prefix, name)) = stem.split_once('_') else {
+            continue;
+        };
+        if let Some(id) = prefix.strip_suffix('s').and_then(|id| id.parse::<u32>().ok()) {
+            expected.insert(id, path);
+        } else if let Ok(id) = prefix.parse::<u32>() {
+            inputs.push((id, name.to_string(), path));
+        }
+    }
+    inputs.sort();
+
+    let mut pairs: Vec<EvalPair> = Vec::new();
+    for (id, name, input) in inputs {
+        let file_name: String = input.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let Some(expected) = expected.remove(&id) else {
+            warn!("No expected output for {:?}, skipping it", input);
+            continue;
+        };
+        let Some(&(start_idx, end_idx)) = selections.get(&file_name) else {
+            warn!("No selection for {} in selections.csv, skipping it", file_name);
+            continue;
+        };
+        let verification: PathBuf = folder.join("verification").join(&file_name);
+        pairs.push(EvalPair {
+            id,
+            name,
+            input,
+            expected,
+            verification: verification.is_file().then_some(verification),
+            start_idx,
+            end_idx,
+        });
+    }
+    Ok(pairs)
+}
+
+fn read_selections(path: &Path) -> io::Result<HashMap<String, (u32, u32)>> {
+    let mut reader = csv::Reader::from_path(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut selections: HashMap<String, (u32, u32)> = HashMap::new();
+    for row in reader.deserialize::<SelectionRow>() {
+        let row: SelectionRow = row.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        selections.insert(row.file, (row.start_idx, row.end_idx));
+    }
+    Ok(selections)
+}
+
+/// Evaluates every pair in `folder`, each in its own `rem-cli eval-case`
+/// process, and prints a table of the results. With a baseline, every pair is
+/// also refactored by rust-analyzer, and its results are reported as a second
+/// suite.
+pub fn run_eval(folder: &Path, options: &EvalOptions) -> io::Result<TestReport> {
+    let folder: PathBuf = folder.canonicalize()?;
+    let pairs: Vec<EvalPair> = pairs(&folder)?;
+    let tools: &[Tool] = if options.baseline { &Tool::ALL } else { &[Tool::Rem] };
+    info!("Evaluating {} pairs from {:?} with {:?}", pairs.len(), folder, tools);
+    let start: Instant = Instant::now();
+
+    let runs: Vec<(&EvalPair, Tool)> = tools
+        .iter()
+        .flat_map(|tool| pairs.iter().map(move |pair| (pair, *tool)))
+        .collect();
+    let mut cases: Vec<CaseResult> = run_parallel(&runs, options.jobs, |(pair, tool)| run_pair(pair, *tool, options));
+
+    let mut report: TestReport = TestReport::default();
+    for tool in tools {
+        let mut suite: SuiteReport = SuiteReport::new(tool.suite());
+        suite.cases = cases.drain(..pairs.len()).collect();
+        report.suites.push(suite);
+    }
+
+    print_summary(&report, start.elapsed());
+    Ok(report)
+}
+
+/// Evaluates one pair with one tool in a `rem-cli eval-case` process.
+fn run_pair(pair: &EvalPair, tool: Tool, options: &EvalOptions) -> CaseResult {
+    let name: String = pair.case_name();
+    let mut args: Vec<OsString> = vec![
+        "eval-case".into(),
+        pair.input.clone().into(),
+        pair.expected.clone().into(),
+        pair.start_idx.to_string().into(),
+        pair.end_idx.to_string().into(),
+        "--tool".into(),
+        tool.name().into(),
+    ];
+    if let Some(verification) = &pair.verification {
+        args.push("--verification".into());
+        args.push(verification.into());
+    }
+    if !options.miri {
+        args.push("--no-miri".into());
+    }
+    args.push("--timeout".into());
+    args.push(options.timeout.as_secs().to_string().into());
+    args.extend(options.compare.to_args().into_iter().map(OsString::from));
+
+    let mut result: CaseResult = match run_in_child(&args, &name, options.timeout) {
+        Ok(result) => result,
+        Err(e) => {
+            error!("Failed to run eval case {} with {}: {}", name, tool.name(), e);
+            let mut result: CaseResult = CaseResult::new(&name, Duration::ZERO);
+            result.add_check("harness", false, Some(e.to_string()));
+            result
+        }
+    };
+    result.tags = pair.tags();
+    result
+}
+
+/// Entry point for the hidden `eval-case` subcommand. Turns the input file into
+/// a Cargo project in the current directory, refactors the selection with
+/// `tool`, and compares the output with the expected file. If the output
+/// compiles, the function the selection was in is also called before and after
+/// the refactoring by a generated harness, see `equivalence`, which is fitted
+/// into what is left of `options.timeout`. Only `compare`, `miri` and
+/// `timeout` are used from `options`. Writes the result to `result_path`.
+pub fn run_eval_case(
+    input: &Path,
+    expected: &Path,
+    verification: Option<&Path>,
+    selection: Range<u32>,
+    tool: Tool,
+    options: &EvalOptions,
+    result_path: &Path,
+) -> io::Result<()> {
+    let name: String = input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    // `eval` kills this process once the pair has run for `options.timeout`
+    let deadline: Instant = Instant::now() + options.timeout;
+
+    write_result_catching_panics(&name, result_path, || {
+        let tests: Option<String> = verification.map(read_to_string).transpose()?;
+        let verification_code: String = tests.as_deref().map(verification_module).unwrap_or_default();
+        let input_code: String = read_to_string(input)? + &verification_code;
+        let expected_code: String = read_to_string(expected)? + &verification_code;
+
+        let fixture_dir: PathBuf = std::env::current_dir()?.join("eval").join(&name);
+        fs::create_dir_all(fixture_dir.join("src"))?;
+        fs::write(fixture_dir.join("Cargo.toml"), manifest(&name))?;
+        fs::write(fixture_dir.join("src").join("main.rs"), &input_code)?;
+
+        let compare: &CompareOptions = &options.compare;
+        let harness_options: HarnessOptions = HarnessOptions {
+            cargo_test: verification.is_some(),
+            compare: *compare,
+            ..HarnessOptions::default()
+        };
+        let (mut case, output_code) = match tool {
+            Tool::Rem => check_selection(&fixture_dir, &name, selection.start, selection.end, &harness_options)?,
+            Tool::RustAnalyzer => check_baseline(&fixture_dir, &name, selection.clone(), &harness_options)?,
+        };
+
+        if let Some(output_code) = output_code {
+            match compare_ast(&output_code, &expected_code, compare) {
+                Ok(true) => case.add_check("ast", true, None),
+                Ok(false) => {
+                    let message: String = explain_mismatch(&output_code, &expected_code, compare, &expected.to_string_lossy());
+                    case.add_check("ast", false, Some(message));
+                }
+                Err(e) => case.add_check("ast", false, Some(e)),
+            }
+
+            // A harness around output that doesn't compile can't be built either
+            if case.checks.iter().any(|check| check.name == "compile" && check.passed && !check.skipped) {
+                let harness: Result<Harness, String> = generate_harness(&input_code, &output_code, selection, tests.as_deref());
+                match harness {
+                    Ok(harness) => check_equivalence(&fixture_dir, &name, &harness, options.miri, deadline, &mut case)?,
+                    Err(reason) => info!("No equivalence harness for {}: {}", name, reason),
+                }
+            }
+        }
+        Ok(case)
+    })
+}
+
+/// The baseline's counterpart to `check_selection`: runs rust-analyzer's
+/// extract function on the selection, and checks its output the same way REM's
+/// is checked. Also returns the output if the assist could be applied.
+fn check_baseline(
+    fixture_dir: &Path,
+    name: &str,
+    selection: Range<u32>,
+    options: &HarnessOptions,
+) -> io::Result<(CaseResult, Option<String>)> {
+    let start: Instant = Instant::now();
+    let input_path: PathBuf = fixture_dir.join("src").join("main.rs");
+    let result: Result<String, String> = extract_function(&fixture_dir.canonicalize()?, &input_path, selection.start, selection.end);
+    let mut case: CaseResult = CaseResult::new(name, start.elapsed());
+
+    match result {
+        Ok(output_code) => {
+            case.add_check("extraction", true, None);
+            verify_output(fixture_dir, name, &output_code, options, &mut case)?;
+            Ok((case, Some(output_code)))
+        }
+        Err(message) => {
+            case.add_check("extraction", false, Some(message));
+            Ok((case, None))
+        }
+    }
+}
+
+/// Wraps the contents of a verification file in a test module that can see
+/// everything in the program it is added to.
+fn verification_module(code: &str) -> String {
+    format!("\n#[cfg(test)]\nmod verification {{\n    use super::*;\n\n{}\n}}\n", code)
+}
+
+/// A manifest for a standalone evaluation program. The package name has to be
+/// a valid identifier, and the ids at the start of the names are numbers.
+fn manifest(name: &str) -> String {
+    format!(
+        "[package]\nname = \"eval_{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n",
+        name.replace('-', "_"),
+    )
+}
+
+/// Whether the given checks passed, or `None` if none of them were run (or
+/// they were all skipped).
+fn status(case: &CaseResult, checks: &[&str]) -> Option<bool> {
+    let mut results = case
+        .checks
+        .iter()
+        .filter(|check| checks.contains(&check.name.as_str()) && !check.skipped)
+        .map(|check| check.passed)
+        .peekable();
+    results.peek()?;
+    Some(results.all(|passed| passed))
+}
+
+/// Pads a status to `width` before colouring it, so the columns stay aligned.
+fn column(status: Option<bool>, width: usize) -> String {
+    match status {
+        Some(true) => format!("{:>width$}", "PASSED").green().to_string(),
+        Some(false) => format!("{:>width$}", "FAILED").red().to_string(),
+        None => format!("{:>width$}", "N/A"),
+    }
+}
+
+/// The results of one tool on one pair: whether it refactored the selection,
+/// whether the output compiles, whether it behaves the same as the input, and
+/// whether it matches the expected output.
+fn statuses(case: &CaseResult, tool: Tool) -> [Option<bool>; 4] {
+    let stages: &[&str] = tool.stages();
+    // A case that never got to record a stage (e.g. it timed out) failed
+    let success: bool = status(case, stages).unwrap_or(false)
+        && case.checks.iter().filter(|check| stages.contains(&check.name.as_str())).count() == stages.len();
+    [
+        Some(success),
+        status(case, &["compile"]),
+        status(case, &["behaviour", "cargo-test", "equivalence", "miri"]),
+        status(case, &["ast"]),
+    ]
+}
+
+/// The four status columns of one tool.
+fn columns(statuses: &[Option<bool>; 4]) -> String {
+    format!(
+        "{} {} {} {}",
+        column(statuses[0], 8),
+        column(statuses[1], 8),
+        column(statuses[2], 10),
+        column(statuses[3], 8),
+    )
+}
+
+/// Prints one row per pair: for each tool, whether it refactored the
+/// selection, whether the output compiles, whether it behaves the same as the
+/// input, and whether it matches the expected output. With a baseline the
+/// tools are side by side. Then prints how often each tool got each column
+/// right.
+pub fn print_summary(report: &TestReport, elapsed: Duration) {
+    let tools: Vec<Tool> = Tool::ALL
+        .into_iter()
+        .filter(|tool| report.suites.iter().any(|suite| suite.name == tool.suite()))
+        .collect();
+    let suites: Vec<&SuiteReport> = tools
+        .iter()
+        .filter_map(|tool| report.suites.iter().find(|suite| suite.name == tool.suite()))
+        .collect();
+    let Some(first) = suites.first() else {
+        return;
+    };
+    let case_width: usize = first.cases.iter().map(|case| case.name.len()).max().unwrap_or(0).max(4);
+
+    println!("------------------------------------------------------------------");
+    if tools.len() > 1 {
+        let groups: Vec<String> = tools.iter().map(|tool| format!("{:<37}", tool.name())).collect();
+        println!("{}", format!("{:<case_width$} {}", "", groups.join(" | ")).trim_end());
+    }
+    let header: String = format!("{:>8} {:>8} {:>10} {:>8}", "success", "compile", "equivalent", "expected");
+    println!("{:<case_width$} {} {:>9}", "case", vec![header; tools.len()].join(" | "), "time");
+
+    let mut tallies: Vec<[Tally; 4]> = vec![[Tally::default(); 4]; tools.len()];
+    for (index, case) in first.cases.iter().enumerate() {
+        let mut row: Vec<String> = Vec::new();
+        for ((tool, suite), tally) in tools.iter().zip(&suites).zip(tallies.iter_mut()) {
+            let Some(tool_case) = suite.cases.get(index) else {
+                continue;
+            };
+            let statuses: [Option<bool>; 4] = statuses(tool_case, *tool);
+            for (status, tally) in statuses.iter().zip(tally.iter_mut()) {
+                if let Some(passed) = status {
+                    tally.total += 1;
+                    tally.passed += usize::from(*passed);
+                }
+            }
+            row.push(columns(&statuses));
+            info!(
+                "Eval {} with {}: success {:?}, compile {:?}, equivalent {:?}, expected {:?}, {:.2}s",
+                case.name,
+                tool.name(),
+                statuses[0],
+                statuses[1],
+                statuses[2],
+                statuses[3],
+                tool_case.duration.as_secs_f64(),
+            );
+        }
+        println!("{:<case_width$} {} {:>8.2}s", case.name, row.join(" | "), case.duration.as_secs_f64());
+    }
+
+    println!("------------------------------------------------------------------");
+    for ((tool, suite), tally) in tools.iter().zip(&suites).zip(&tallies) {
+        let passed: usize = suite.cases.len() - suite.failed();
+        let line: String = format!(
+            "{:<14} {}/{} passed (success {}, compile {}, equivalent {}, expected {})",
+            tool.name(),
+            passed,
+            suite.cases.len(),
+            tally[0],
+            tally[1],
+            tally[2],
+            tally[3],
+        );
+        println!("{}", line);
+        info!("Eval: {}", line);
+    }
+    println!("Evaluated in {:.2}s", elapsed.as_secs_f64());
+    println!("------------------------------------------------------------------");
+}
@@ -0,0 +1,504 @@
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fs,
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        Duration,
+        Instant,
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use colored::Colorize;
+
+use log::{
+    error,
+    info,
+    warn,
+};
+
+use proc_macro2::{
+    LineColumn,
+    Span,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use syn::{
+    spanned::Spanned,
+    visit::{
+        self,
+        Visit,
+    },
+    Block,
+    Expr,
+};
+
+use crate::tests::{
+    extract::{
+        SelectionManifest,
+        SELECTION_MANIFEST,
+    },
+    harness::{
+        run_in_child,
+        run_parallel,
+        write_result_catching_panics,
+        HarnessOptions,
+    },
+    pipeline::{
+        check_selection,
+        is_stage,
+    },
+    reduce::rename_package,
+    report::{
+        CaseOutcome,
+        CaseResult,
+        SuiteReport,
+    },
+};
+
+/// Options for a fuzzing run.
+/// - iterations: How many selections to try
+/// - seed: Seeds the choice of selections, so a run can be repeated
+/// - jobs: The number of selections that are tried at the same time
+/// - timeout: How long a single selection may run before it is counted as a
+///   hang
+/// - out: The test folder flagged selections are added to as extract fixtures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzOptions {
+    pub iterations: usize,
+    pub seed: u64,
+    pub jobs: usize,
+    pub timeout: Duration,
+    pub out: PathBuf,
+}
+
+/// A selection in one of the fixture programs. `start_idx` and `end_idx` are
+/// byte offsets into its `src/main.rs`, in the same form as the extract
+/// suite's test table.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Selection {
+    pub fixture: String,
+    pub start_idx: u32,
+    pub end_idx: u32,
+}
+
+/// What happened to a single selection.
+/// - Passed: Every stage succeeded and the output behaves like the original
+/// - Rejected: A REM stage refused the selection. This is expected for many
+///   random selections and isn't a bug
+/// - Flagged: REM panicked or hung, or its output doesn't compile or behaves
+///   differently to the original
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Passed,
+    Rejected(String),
+    Flagged(String),
+}
+
+/// Written next to every saved fixture's selection manifest, recording where
+/// the selection came from and how it failed.
+#[derive(Debug, Serialize, Deserialize)]
+struct FuzzRecord {
+    selection: Selection,
+    seed: u64,
+    reason: String,
+    result: CaseResult,
+}
+
+/// A fixture, its name, and every selection that can be made in it.
+type FixtureSelections = (PathBuf, String, Vec<(u32, u32)>);
+
+/// A small, seedable PRNG (splitmix64). The selections only need to be
+/// spread out and repeatable, not cryptographically random.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z: u64 = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+impl Selection {
+    /// Names the selection, and its scratch and fixture directories.
+    pub fn name(&self) -> String {
+        format!("{}_{}_{}", self.fixture, self.start_idx, self.end_idx)
+    }
+}
+
+/// A seed taken from the clock, for when none is given.
+pub fn default_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// Classifies the result of a selection. Failing any REM stage is a
+/// rejection. Anything else that fails, including the harness itself, is
+/// flagged.
+pub fn verdict(result: &CaseResult) -> Verdict {
+    match result.outcome {
+        CaseOutcome::Passed => Verdict::Passed,
+        CaseOutcome::TimedOut => Verdict::Flagged("hang".to_string()),
+        CaseOutcome::Panicked => Verdict::Flagged("panic".to_string()),
+        CaseOutcome::Failed => match result.first_failure() {
+            Some(check) if is_stage(&check.name) => Verdict::Rejected(check.name.clone()),
+            Some(check) => Verdict::Flagged(check.name.clone()),
+            None => Verdict::Flagged("unknown".to_string()),
+        },
+    }
+}
+
+/// The fixture programs to fuzz: every Cargo project in `<folder>/extract/input`,
+/// or in `<folder>` itself if it has no `extract/input`.
+pub fn fixtures(folder: &Path) -> io::Result<Vec<PathBuf>> {
+    let extract_input: PathBuf = folder.join("extract/input");
+    let root: &Path = if extract_input.is_dir() { &extract_input } else { folder };
+
+    let mut fixtures: Vec<PathBuf> = Vec::new();
+    for entry in fs::read_dir(root)? {
+        let path: PathBuf = entry?.path();
+        if path.join("Cargo.toml").is_file() && path.join("src").join("main.rs").is_file() {
+            fixtures.push(path);
+        }
+    }
+    fixtures.sort();
+    Ok(fixtures)
+}
+
+/// Every selection in `source` that REM could be asked to extract: each run of
+/// consecutive statements in a block, and each expression, inside a function
+/// body. Returns `(start_idx, end_idx)` byte offsets.
+pub fn candidate_selections(source: &str) -> Result<Vec<(u32, u32)>, syn::Error> {
+    let file: syn::File = syn::parse_file(source)?;
+    let mut collector: SelectionCollector = SelectionCollector {
+        line_starts: line_starts(source),
+        source,
+        in_fn: 0,
+        selections: Vec::new(),
+    };
+    collector.visit_file(&file);
+
+    let mut selections: Vec<(u32, u32)> = collector.selections;
+    selections.sort();
+    selections.dedup();
+    Ok(selections)
+}
+
+struct SelectionCollector<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+    in_fn: usize,
+    selections: Vec<(u32, u32)>,
+}
+
+impl SelectionCollector<'_> {
+    fn push(&mut self, first: Span, last: Span) {
+        let start: Option<usize> = self.offset(first.start());
+        let end: Option<usize> = self.offset(last.end());
+        if let (Some(start), Some(end)) = (start, end) {
+            if start < end {
+                self.selections.push((start as u32, end as u32));
+            }
+        }
+    }
+
+    fn offset(&self, location: LineColumn) -> Option<usize> {
+        byte_offset(self.source, &self.line_starts, location)
+    }
+}
+
+/// Converts a line (1-based) and column (0-based, in characters) into a byte
+/// offset. `line_starts` is the offset of the start of every line.
+pub fn byte_offset(source: &str, line_starts: &[usize], location: LineColumn) -> Option<usize> {
+    let line_start: usize = *line_starts.get(location.line.checked_sub(1)?)?;
+    let line: &str = &source[line_start..];
+    let column: usize = line
+        .char_indices()
+        .nth(location.column)
+        .map(|(index, _)| index)
+        .unwrap_or(line.len());
+    Some(line_start + column)
+}
+
+impl<'ast> Visit<'ast> for SelectionCollector<'_> {
+    fn visit_item_fn(&mut self, item: &'ast syn::ItemFn) {
+        self.in_fn += 1;
+        visit::visit_item_fn(self, item);
+        self.in_fn -= 1;
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast syn::ImplItemFn) {
+        self.in_fn += 1;
+        visit::visit_impl_item_fn(self, item);
+        self.in_fn -= 1;
+    }
+
+    fn visit_block(&mut self, block: &'ast Block) {
+        if self.in_fn > 0 {
+            for first in 0..block.stmts.len() {
+                for last in first..block.stmts.len() {
+                    self.push(block.stmts[first].span(), block.stmts[last].span());
+                }
+            }
+        }
+        visit::visit_block(self, block);
+    }
+
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        if self.in_fn > 0 {
+            self.push(expr.span(), expr.span());
+        }
+        visit::visit_expr(self, expr);
+    }
+}
+
+pub fn line_starts(source: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+        .collect()
+}
+
+/// Picks up to `options.iterations` distinct selections at random, spread
+/// evenly over the fixtures rather than weighted towards the largest.
+pub fn choose_selections(fixtures: &[PathBuf], options: &FuzzOptions) -> Vec<(PathBuf, Selection)> {
+    let mut candidates: Vec<FixtureSelections> = Vec::new();
+    for fixture in fixtures {
+        let name: String = fixture
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let selections: Vec<(u32, u32)> = match fs::read_to_string(fixture.join("src").join("main.rs"))
+            .map_err(|e| e.to_string())
+            .and_then(|source| candidate_selections(&source).map_err(|e| e.to_string()))
+        {
+            Ok(selections) => selections,
+            Err(e) => {
+                warn!("Skipping fixture {:?}: {}", fixture, e);
+                continue;
+            }
+        };
+        if !selections.is_empty() {
+            candidates.push((fixture.clone(), name, selections));
+        }
+    }
+
+    let total: usize = candidates.iter().map(|(_, _, selections)| selections.len()).sum();
+    let wanted: usize = options.iterations.min(total);
+    let mut rng: Rng = Rng(options.seed);
+    let mut chosen: HashSet<Selection> = HashSet::new();
+    let mut selections: Vec<(PathBuf, Selection)> = Vec::new();
+
+    while selections.len() < wanted {
+        let (fixture, name, ranges) = &candidates[rng.below(candidates.len())];
+        let (start_idx, end_idx) = ranges[rng.below(ranges.len())];
+        let selection: Selection = Selection {
+            fixture: name.clone(),
+            start_idx,
+            end_idx,
+        };
+        if chosen.insert(selection.clone()) {
+            selections.push((fixture.clone(), selection));
+        }
+    }
+
+    selections
+}
+
+/// Runs the pipeline on randomly chosen selections, each in its own `rem-cli
+/// fuzz-case` process. Flagged selections are saved to `options.out`. The
+/// returned report holds every selection that wasn't rejected.
+pub fn run_fuzz(folder: &Path, options: &FuzzOptions) -> io::Result<SuiteReport> {
+    let folder: PathBuf = folder.canonicalize()?;
+    let fixtures: Vec<PathBuf> = fixtures(&folder)?;
+    info!("Fuzzing {} fixtures from {:?} with seed {}", fixtures.len(), folder, options.seed);
+    println!("Fuzzing {} fixtures with seed {}", fixtures.len(), options.seed);
+
+    let selections: Vec<(PathBuf, Selection)> = choose_selections(&fixtures, options);
+    let start: Instant = Instant::now();
+
+    let results: Vec<CaseResult> = run_parallel(&selections, options.jobs, |(fixture, selection)| {
+        let name: String = selection.name();
+        let result: CaseResult = run_selection(fixture, selection, options.timeout);
+
+        let verdict: Verdict = verdict(&result);
+        let status: String = match &verdict {
+            Verdict::Passed => "PASSED".green().to_string(),
+            Verdict::Rejected(stage) => format!("REJECTED by {}", stage).yellow().to_string(),
+            Verdict::Flagged(reason) => format!("FLAGGED ({})", reason).red().to_string(),
+        };
+        println!("{}: {}", status, name);
+
+        if let Verdict::Flagged(reason) = verdict {
+            if let Err(e) = save_fixture(fixture, selection, options, &reason, &result) {
+                error!("Failed to save fuzz case {}: {}", name, e);
+            }
+        }
+        result
+    });
+
+    let mut report: SuiteReport = SuiteReport::new("fuzz");
+    let mut rejected: usize = 0;
+    let mut flagged: Vec<(String, String)> = Vec::new();
+    for result in results {
+        match verdict(&result) {
+            Verdict::Passed => report.cases.push(result),
+            Verdict::Rejected(_) => rejected += 1,
+            Verdict::Flagged(reason) => {
+                flagged.push((result.name.clone(), reason));
+                report.cases.push(result);
+            }
+        }
+    }
+
+    print_summary(selections.len(), rejected, &flagged, start.elapsed(), options);
+    Ok(report)
+}
+
+/// Runs the pipeline on one selection in its own `rem-cli fuzz-case` process.
+/// Failing to start the process is recorded as a failed `harness` check.
+pub fn run_selection(fixture: &Path, selection: &Selection, timeout: Duration) -> CaseResult {
+    let name: String = selection.name();
+    let args: Vec<OsString> = vec![
+        "fuzz-case".into(),
+        fixture.into(),
+        selection.start_idx.to_string().into(),
+        selection.end_idx.to_string().into(),
+    ];
+    match run_in_child(&args, &name, timeout) {
+        Ok(result) => result,
+        Err(e) => {
+            error!("Failed to run fuzz case {}: {}", name, e);
+            let mut result: CaseResult = CaseResult::new(&name, Duration::ZERO);
+            result.add_check("harness", false, Some(e.to_string()));
+            result
+        }
+    }
+}
+
+/// Reads the selection from the selection manifest of a fixture, e.g. one
+/// saved by `fuzz` or `reduce`. The selection has to be in `src/main.rs`.
+pub fn load_selection(fixture: &Path) -> io::Result<Selection> {
+    let contents: String = fs::read_to_string(fixture.join(SELECTION_MANIFEST))?;
+    let manifest: SelectionManifest = serde_json::from_str(&contents)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if manifest.file != Path::new("src").join("main.rs") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("The selection is in {:?} rather than src/main.rs", manifest.file),
+        ));
+    }
+    Ok(Selection {
+        fixture: fixture
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        start_idx: manifest.start_idx,
+        end_idx: manifest.end_idx,
+    })
+}
+
+/// Copies the fixture's project to `<out>/extract/input/<selection>`, with
+/// its package renamed to match, a selection manifest so that `test` picks it
+/// up, and a `fuzz.json` recording the seed and how it failed.
+fn save_fixture(
+    fixture: &Path,
+    selection: &Selection,
+    options: &FuzzOptions,
+    reason: &str,
+    result: &CaseResult,
+) -> io::Result<()> {
+    let name: String = selection.name();
+    let dest: PathBuf = options.out.join("extract/input").join(&name);
+    fs::create_dir_all(dest.join("src"))?;
+    let manifest: String = fs::read_to_string(fixture.join("Cargo.toml"))?;
+    fs::write(dest.join("Cargo.toml"), rename_package(&manifest, &name))?;
+    fs::copy(fixture.join("src").join("main.rs"), dest.join("src").join("main.rs"))?;
+    SelectionManifest::new(selection.start_idx, selection.end_idx).write(&dest)?;
+
+    let record: FuzzRecord = FuzzRecord {
+        selection: selection.clone(),
+        seed: options.seed,
+        reason: reason.to_string(),
+        result: result.clone(),
+    };
+    let contents: String = serde_json::to_string_pretty(&record)
+        .map_err(io::Error::other)?;
+    fs::write(dest.join("fuzz.json"), contents)?;
+
+    info!("Saved flagged selection to {:?}", dest);
+    Ok(())
+}
+
+fn print_summary(
+    total: usize,
+    rejected: usize,
+    flagged: &[(String, String)],
+    elapsed: Duration,
+    options: &FuzzOptions,
+) {
+    let passed: usize = total - rejected - flagged.len();
+    println!("------------------------------------------------------------------");
+    println!(
+        "Tried {} selections in {:.2}s: {} passed, {} rejected, {} flagged",
+        total,
+        elapsed.as_secs_f64(),
+        passed,
+        rejected,
+        flagged.len(),
+    );
+    info!(
+        "Fuzzing finished: {} selections, {} passed, {} rejected, {} flagged (seed {})",
+        total,
+        passed,
+        rejected,
+        flagged.len(),
+        options.seed,
+    );
+
+    if !flagged.is_empty() {
+        println!("Flagged selections were saved to {:?}:", options.out);
+        for (name, reason) in flagged {
+            println!("    {} ({})", name, reason);
+        }
+        println!("Re-run with --seed {} to try the same selections again", options.seed);
+    }
+    println!("------------------------------------------------------------------");
+}
+
+/// Entry point for the hidden `fuzz-case` subcommand. Runs the pipeline on one
+/// selection in the current directory and writes the result to `result_path`.
+pub fn run_fuzz_case(fixture: &Path, start_idx: u32, end_idx: u32, result_path: &Path) -> io::Result<()> {
+    let selection: Selection = Selection {
+        fixture: fixture
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        start_idx,
+        end_idx,
+    };
+    let name: String = selection.name();
+
+    write_result_catching_panics(&name, result_path, || {
+        check_selection(fixture, &name, start_idx, end_idx, &HarnessOptions::default())
+            .map(|(result, _)| result)
+    })
+}
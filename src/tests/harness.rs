@@ -0,0 +1,465 @@
+use std::{
+    any::Any,
+    collections::BTreeSet,
+    ffi::OsString,
+    fmt,
+    fs,
+    io,
+    panic::{
+        self,
+        AssertUnwindSafe,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    process::{
+        Child,
+        Command,
+        ExitStatus,
+        Stdio,
+    },
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        Mutex,
+    },
+    thread::{
+        self,
+        JoinHandle,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use clap::ValueEnum;
+
+use colored::Colorize;
+
+use log::{
+    error,
+    info,
+    warn,
+};
+
+use serde::de::DeserializeOwned;
+
+use tempfile::TempDir;
+
+use crate::error::TestFailed;
+use crate::tests::{
+    ast::CompareOptions,
+    utils::{
+        in_own_process_group,
+        read_pipe,
+        wait_with_timeout,
+    },
+    borrower,
+    controller,
+    extract::{
+        self,
+        TestFile,
+    },
+    pipeline,
+    repairer,
+    report::{
+        CaseResult,
+        SuiteReport,
+        TestReport,
+    },
+    tags::{
+        tag_path,
+        Tag,
+    },
+};
+
+/// The suites that make up `rem-cli test`, in the order they are run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Suite {
+    Extract,
+    Controller,
+    Borrower,
+    Repairer,
+    Pipeline,
+}
+
+/// Options shared by every suite in a test run.
+/// - jobs: The number of cases that are run at the same time
+/// - timeout: How long a single case may run before it is killed
+/// - cargo_test: Also run `cargo test` on the original and refactored
+///   programs and compare the results
+/// - compare: How outputs are compared with the expected outputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HarnessOptions {
+    pub jobs: usize,
+    pub timeout: Duration,
+    pub cargo_test: bool,
+    pub compare: CompareOptions,
+}
+
+impl Suite {
+    pub const ALL: [Suite; 5] = [
+        Suite::Extract,
+        Suite::Controller,
+        Suite::Borrower,
+        Suite::Repairer,
+        Suite::Pipeline,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Suite::Extract => "extract",
+            Suite::Controller => "controller",
+            Suite::Borrower => "borrower",
+            Suite::Repairer => "repairer",
+            Suite::Pipeline => "pipeline",
+        }
+    }
+
+    /// Lists the names of every case in the suite. Each name can be passed
+    /// back to `run_case`.
+    pub fn cases(&self, path: &Path) -> io::Result<Vec<String>> {
+        match self {
+            Suite::Extract => extract::cases(path),
+            Suite::Controller => controller::cases(path),
+            Suite::Borrower => borrower::cases(path),
+            Suite::Repairer => repairer::cases(path),
+            Suite::Pipeline => pipeline::cases(path),
+        }
+    }
+
+    /// Runs a single case in the current process and directory.
+    pub fn run_case(&self, path: &Path, case: &str, options: &HarnessOptions) -> io::Result<CaseResult> {
+        match self {
+            Suite::Extract => extract::run_case(path, case, options),
+            Suite::Controller => controller::run_case(path, case, options),
+            Suite::Borrower => borrower::run_case(path, case),
+            Suite::Repairer => repairer::run_case(path, case),
+            Suite::Pipeline => pipeline::run_case(path, case, options),
+        }
+    }
+
+    /// Any setup that has to happen once before the cases are run.
+    fn prepare(&self, path: &Path) -> io::Result<()> {
+        match self {
+            Suite::Extract => extract::prepare(path),
+            Suite::Controller => controller::prepare(path),
+            Suite::Pipeline => pipeline::prepare(path),
+            _ => Ok(()),
+        }
+    }
+
+    /// The language features each of `cases` exercises, in the same order.
+    /// Cases with a selection are tagged by what is selected, the others by
+    /// their whole input file.
+    pub fn tags(&self, path: &Path, cases: &[String]) -> Vec<Vec<String>> {
+        // Read once for all of the cases, rather than for each one
+        let test_files: Vec<TestFile> = match self {
+            Suite::Extract | Suite::Pipeline => extract::test_files(path).unwrap_or_else(|e| {
+                warn!("Failed to list the {} cases: {}", self, e);
+                Vec::new()
+            }),
+            _ => Vec::new(),
+        };
+
+        cases.iter().map(|case| {
+            let tags: io::Result<BTreeSet<Tag>> = match self {
+                Suite::Extract | Suite::Pipeline => extract::find_test_file(&test_files, case)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No such extract test"))
+                    .and_then(|(_, test_file)| extract::tags(path, test_file)),
+                Suite::Controller => tag_path(&path.join("controller/input").join(case)),
+                Suite::Borrower => tag_path(&path.join("borrower/input").join(case)),
+                Suite::Repairer => repairer::input_file(path, case)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No such repairer test"))
+                    .and_then(|input| tag_path(&input)),
+            };
+            match tags {
+                Ok(tags) => tags.iter().map(|tag| tag.name().to_string()).collect(),
+                Err(e) => {
+                    warn!("Failed to tag {} case {}: {}", self, case, e);
+                    Vec::new()
+                }
+            }
+        }).collect()
+    }
+
+    /// Any summary that is printed once all of the cases have been run.
+    fn summarise(&self, report: &SuiteReport, elapsed: Duration) {
+        match self {
+            Suite::Extract => extract::print_summary(report, elapsed),
+            Suite::Repairer => repairer::print_summary(report, elapsed),
+            Suite::Pipeline => pipeline::print_summary(report, elapsed),
+            _ => {},
+        }
+    }
+
+    pub fn test_failed(&self, err: io::Error) -> TestFailed {
+        match self {
+            Suite::Extract => TestFailed::ExtractFailed(err),
+            Suite::Controller => TestFailed::ControllerFailed(err),
+            Suite::Borrower => TestFailed::BorrowerFailed(err),
+            Suite::Repairer => TestFailed::RepairerFailed(err),
+            Suite::Pipeline => TestFailed::PipelineFailed(err),
+        }
+    }
+}
+
+impl fmt::Display for Suite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl Default for HarnessOptions {
+    fn default() -> Self {
+        Self {
+            jobs: 1,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            cargo_test: false,
+            compare: CompareOptions::default(),
+        }
+    }
+}
+
+/// The default per-case timeout, in seconds.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// Runs every case of a suite. Each case is run by a separate `rem-cli
+/// test-case` process whose working directory is a fresh scratch directory,
+/// so that nothing REM (or rustc) writes ends up in the user's current
+/// directory. Up to `options.jobs` cases are run at once.
+pub fn run_suite(suite: Suite, path: &Path, options: &HarnessOptions) -> io::Result<SuiteReport> {
+    if !path.is_dir() {
+        error!("The path provided is not a directory: {:?}", path);
+        return Err(io::Error::new(io::ErrorKind::NotFound, "Path is not a directory"));
+    }
+
+    // The cases are run from a different working directory, so the fixtures
+    // have to be referred to absolutely.
+    let path: PathBuf = path.canonicalize()?;
+    info!("Running {} tests from directory {:?} with {} job(s)", suite, path, options.jobs);
+
+    suite.prepare(&path)?;
+    let cases: Vec<String> = suite.cases(&path)?;
+    let suite_start: Instant = Instant::now();
+
+    let mut report: SuiteReport = SuiteReport::new(suite.name());
+    report.cases = run_parallel(&cases, options.jobs, |case| {
+        match run_isolated(suite, &path, case, options) {
+            Ok(result) => result,
+            Err(e) => {
+                error!("Failed to run {} case {}: {}", suite, case, e);
+                let mut result: CaseResult = CaseResult::new(case, Duration::ZERO);
+                result.add_check("harness", false, Some(e.to_string()));
+                result
+            }
+        }
+    });
+    // Tagged here rather than by the case itself, so that cases which crash
+    // or time out are tagged too
+    for (result, tags) in report.cases.iter_mut().zip(suite.tags(&path, &cases)) {
+        result.tags = tags;
+    }
+
+    suite.summarise(&report, suite_start.elapsed());
+
+    Ok(report)
+}
+
+/// Prints the pass rate of every check for the cases with each tag, one
+/// table per suite.
+pub fn print_capabilities(report: &TestReport) {
+    for suite in &report.suites {
+        let rows = suite.capabilities();
+        if rows.is_empty() {
+            continue;
+        }
+        let checks: Vec<String> = suite.check_names();
+
+        println!("------------------------------------------------------------------");
+        println!("Capabilities ({}):", suite.name);
+        let mut header: String = format!("    {:<16} {:>14}", "tag", "cases");
+        for check in &checks {
+            header.push_str(&format!(" {:>14}", check));
+        }
+        println!("{}", header.bold());
+        for row in rows {
+            let mut line: String = format!("    {:<16} {:>14}", row.tag, row.cases.to_string());
+            for check in &checks {
+                let tally: String = row.checks.get(check).map(|tally| tally.to_string()).unwrap_or("-".to_string());
+                line.push_str(&format!(" {:>14}", tally));
+            }
+            println!("{}", line);
+            info!("{} capabilities: {}", suite.name, line.trim());
+        }
+    }
+    println!("------------------------------------------------------------------");
+}
+
+/// Calls `run` on every item, with up to `jobs` items being run at the same
+/// time. The results are returned in the same order as the items.
+pub fn run_parallel<T: Sync>(items: &[T], jobs: usize, run: impl Fn(&T) -> CaseResult + Sync) -> Vec<CaseResult> {
+    let next_item: AtomicUsize = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<CaseResult>>> = Mutex::new(vec![None; items.len()]);
+    let workers: usize = jobs.clamp(1, items.len().max(1));
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index: usize = next_item.fetch_add(1, Ordering::SeqCst);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+
+                let result: CaseResult = run(item);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Runs a single case in a child process inside its own scratch directory.
+fn run_isolated(suite: Suite, path: &Path, case: &str, options: &HarnessOptions) -> io::Result<CaseResult> {
+    let mut args: Vec<OsString> = vec![
+        "test-case".into(),
+        suite.name().into(),
+        path.into(),
+        case.into(),
+    ];
+    if options.cargo_test {
+        args.push("--cargo-test".into());
+    }
+    args.extend(options.compare.to_args().into_iter().map(OsString::from));
+
+    run_in_child(&args, case, options.timeout)
+}
+
+/// Runs `rem-cli <args> --result <file>` inside a fresh scratch directory, and
+/// reads back the `CaseResult` it writes. The child is killed if it runs for
+/// longer than `timeout`, and a child that exits without writing a result
+/// (e.g. it aborted) is recorded as a panic. The scratch directory is removed
+/// when this function returns, whether or not the case succeeded.
+pub fn run_in_child(args: &[OsString], case: &str, timeout: Duration) -> io::Result<CaseResult> {
+    Ok(run_child::<CaseResult>(args, case, timeout)?.unwrap_or_else(|failed| failed))
+}
+
+/// Like `run_in_child`, for subcommands that write some other result. Returns
+/// `Err` with the `CaseResult` recording the failure if the child timed out or
+/// exited without writing a result.
+pub fn run_child<T: DeserializeOwned>(
+    args: &[OsString],
+    case: &str,
+    timeout: Duration,
+) -> io::Result<Result<T, CaseResult>> {
+    let scratch: TempDir = tempfile::Builder::new()
+        .prefix("rem-test-")
+        .tempdir()?;
+    let result_path: PathBuf = scratch.path().join("result.json");
+
+    let start: Instant = Instant::now();
+    let mut child: Child = in_own_process_group(&mut Command::new(std::env::current_exe()?))
+        .args(args)
+        .arg("--result")
+        .arg(&result_path)
+        .current_dir(scratch.path())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain the pipes on their own threads so a chatty case can't block on a
+    // full pipe while we wait for it.
+    let stdout: JoinHandle<String> = read_pipe(child.stdout.take());
+    let stderr: JoinHandle<String> = read_pipe(child.stderr.take());
+
+    let status: Option<ExitStatus> = wait_with_timeout(&mut child, timeout)?;
+    let elapsed: Duration = start.elapsed();
+
+    let Some(status) = status else {
+        // Anything the killed process spawned may still hold the pipes open,
+        // so the reader threads are left to finish on their own.
+        error!("Case {} timed out after {:?}", case, elapsed);
+        println!("{}: {} after {:.2}s", "TIMED OUT".red(), case, elapsed.as_secs_f64());
+        return Ok(Err(CaseResult::timed_out(case, elapsed)));
+    };
+
+    // Print the child's output in one go so that parallel cases don't
+    // interleave their lines.
+    print!("{}", stdout.join().unwrap_or_default());
+    let stderr: String = stderr.join().unwrap_or_default();
+
+    match fs::read_to_string(&result_path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map(Ok)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(_) => {
+            error!("Case {} produced no result ({}): {}", case, status, stderr);
+            println!("{}: {} ({})", "PANICKED".red(), case, status);
+            Ok(Err(CaseResult::panicked(
+                case,
+                elapsed,
+                format!("Test process exited with {} without a result: {}", status, stderr.trim()),
+            )))
+        }
+    }
+}
+
+/// Entry point for the hidden `test-case` subcommand. Runs the case in the
+/// current directory and writes its result as JSON to `result_path`. Panics
+/// from the REM crates are caught and recorded against the case instead of
+/// being lost with the process.
+pub fn run_case_to_file(
+    suite: Suite,
+    path: &Path,
+    case: &str,
+    result_path: &Path,
+    options: &HarnessOptions,
+) -> io::Result<()> {
+    write_result_catching_panics(case, result_path, || suite.run_case(path, case, options))
+}
+
+/// Runs `run` and writes its result as JSON to `result_path`. A panic is
+/// recorded as the result instead.
+pub fn write_result_catching_panics(
+    case: &str,
+    result_path: &Path,
+    run: impl FnOnce() -> io::Result<CaseResult>,
+) -> io::Result<()> {
+    let start: Instant = Instant::now();
+    let result: CaseResult = match panic::catch_unwind(AssertUnwindSafe(run)) {
+        Ok(result) => result?,
+        Err(payload) => {
+            let message: String = panic_message(payload.as_ref());
+            error!("Case {} panicked: {}", case, message);
+            println!("{}: {}: {}", "PANICKED".red(), case, message);
+            CaseResult::panicked(case, start.elapsed(), message)
+        }
+    };
+
+    let contents: String = serde_json::to_string(&result)
+        .map_err(io::Error::other)?;
+    fs::write(result_path, contents)
+}
+
+/// Panics carry either a `&str` or a `String`, depending on whether the panic
+/// message was formatted.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic".to_string()
+    }
+}
@@ -0,0 +1,360 @@
+use std::{
+    error::Error,
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use colored::Colorize;
+
+use git2::{
+    Blob,
+    Commit,
+    ObjectType,
+    Oid,
+    Repository,
+    Tree,
+    TreeWalkMode,
+    TreeWalkResult,
+};
+
+use log::{
+    error,
+    info,
+    warn,
+};
+
+use toml_edit::{
+    DocumentMut,
+    InlineTable,
+    Item,
+    Table,
+    TableLike,
+    Value,
+};
+
+use crate::tests::{
+    extract::SelectionManifest,
+    replay::{
+        line_range,
+        local_clone,
+        read_cases,
+        MinedCase,
+    },
+};
+
+/// The tables of a manifest that list dependencies. They can also appear
+/// under `[target.<cfg>]`.
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// The mode git gives symbolic links, which aren't copied into fixtures.
+const SYMLINK_MODE: i32 = 0o120000;
+
+/// Options for importing mined cases as extract fixtures.
+/// - repos: Folder holding a local clone of every repository in the cases file
+/// - out: The test folder the fixtures are written to (e.g. `src_tests`)
+/// - cases: Only import the cases with these names. Every case if empty
+/// - force: Replace fixtures that already exist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportOptions {
+    pub repos: PathBuf,
+    pub out: PathBuf,
+    pub cases: Vec<String>,
+    pub force: bool,
+}
+
+/// Imports every selected case in `cases_path` into the extract suite, and
+/// returns how many of them couldn't be imported.
+pub fn run_import(cases_path: &Path, options: &ImportOptions) -> Result<usize, Box<dyn Error>> {
+    let cases: Vec<MinedCase> = read_cases(cases_path)?;
+    let selected: Vec<&MinedCase> = cases
+        .iter()
+        .filter(|mined| options.cases.is_empty() || options.cases.contains(&mined.case_name()))
+        .collect();
+    if selected.is_empty() {
+        return Err(format!("No cases to import from {:?}", cases_path).into());
+    }
+
+    let mut failed: usize = 0;
+    for mined in &selected {
+        let name: String = mined.case_name();
+        match import_case(mined, &name, options) {
+            Ok(warnings) => {
+                println!("{}: {}", "IMPORTED".green(), name);
+                info!("Imported {} into {:?}", name, options.out);
+                for warning in warnings {
+                    println!("    warning: {}", warning);
+                    warn!("{}: {}", name, warning);
+                }
+            }
+            Err(e) => {
+                println!("{}: {}: {}", "FAILED".red(), name, e);
+                error!("Failed to import {}: {}", name, e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("------------------------------------------------------------------");
+    println!("Imported {}/{} cases into {:?}", selected.len() - failed, selected.len(), options.out.join("extract"));
+    println!("------------------------------------------------------------------");
+    Ok(failed)
+}
+
+/// Writes one case out as an extract fixture:
+/// - `extract/input/<name>/`: The package the selection is in, as it was
+///   before the refactoring, with a manifest that builds on its own
+/// - `extract/input/<name>/selection.json`: Where the selection is, and the
+///   name of the extracted function
+/// - `extract/correct_output/<name>.rs`: The file after the refactoring
+///
+/// Returns anything that couldn't be carried over into the manifest.
+fn import_case(mined: &MinedCase, name: &str, options: &ImportOptions) -> Result<Vec<String>, Box<dyn Error>> {
+    let fixture_dir: PathBuf = options.out.join("extract/input").join(name);
+    let expected_path: PathBuf = options.out.join("extract/correct_output").join(name).with_extension("rs");
+    if fixture_dir.exists() && !options.force {
+        return Err(format!("{:?} already exists (use --force to replace it)", fixture_dir).into());
+    }
+
+    let local: PathBuf = local_clone(&options.repos, &mined.repo)?;
+    let repository: Repository = Repository::open(&local)?;
+    let pre_tree: Tree = repository.revparse_single(&mined.pre_sha)?.peel_to_commit()?.tree()?;
+    let post_commit: Commit = repository.revparse_single(&mined.post_sha)?.peel_to_commit()?;
+
+    let pre_file: &Path = Path::new(&mined.pre_file);
+    let package_root: PathBuf = package_root(&repository, &pre_tree, pre_file)
+        .ok_or_else(|| format!("{} is not in a Cargo package", mined.pre_file))?;
+    let source: String = read_tree_file(&repository, &pre_tree, pre_file)
+        .ok_or_else(|| format!("Failed to read {} at {}", mined.pre_file, mined.pre_sha))?;
+    let (start_idx, end_idx) = line_range(&source, mined.start_line, mined.end_line)
+        .ok_or_else(|| format!("Lines {}..={} are not in {}", mined.start_line, mined.end_line, mined.pre_file))?;
+    let expected: String = read_tree_file(&repository, &post_commit.tree()?, Path::new(&mined.post_file))
+        .ok_or_else(|| format!("Failed to read {} at {}", mined.post_file, mined.post_sha))?;
+
+    let manifest: String = read_tree_file(&repository, &pre_tree, &package_root.join("Cargo.toml"))
+        .ok_or_else(|| format!("Failed to read the manifest in {:?}", package_root))?;
+    let workspace: Option<(PathBuf, DocumentMut)> = workspace_manifest(&repository, &pre_tree, &package_root);
+    let (manifest, warnings) = standalone_manifest(&manifest, workspace.as_ref().map(|(_, manifest)| manifest))?;
+
+    if fixture_dir.exists() {
+        fs::remove_dir_all(&fixture_dir)?;
+    }
+    let package_tree: Tree = if package_root.as_os_str().is_empty() {
+        pre_tree.clone()
+    } else {
+        pre_tree.get_path(&package_root)?.to_object(&repository)?.peel_to_tree()?
+    };
+    copy_tree(&repository, &package_tree, &fixture_dir)?;
+    fs::write(fixture_dir.join("Cargo.toml"), manifest)?;
+
+    // A package in a workspace shares the workspace's lock file
+    if let Some((workspace_root, _)) = &workspace {
+        if let Some(lock_file) = read_tree_file(&repository, &pre_tree, &workspace_root.join("Cargo.lock")) {
+            if !fixture_dir.join("Cargo.lock").exists() {
+                fs::write(fixture_dir.join("Cargo.lock"), lock_file)?;
+            }
+        }
+    }
+
+    let selection: SelectionManifest = SelectionManifest {
+        file: pre_file.strip_prefix(&package_root)?.to_path_buf(),
+        start_idx,
+        end_idx,
+        new_fn_name: mined.fn_name.clone(),
+        source: Some((*mined).clone()),
+    };
+    selection.write(&fixture_dir)?;
+
+    if let Some(parent) = expected_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&expected_path, expected)?;
+
+    Ok(warnings)
+}
+
+/// The contents of a file in a tree, if it is there and is UTF-8.
+fn read_tree_file(repository: &Repository, tree: &Tree, path: &Path) -> Option<String> {
+    let blob: Blob = tree.get_path(path).ok()?.to_object(repository).ok()?.peel_to_blob().ok()?;
+    String::from_utf8(blob.content().to_vec()).ok()
+}
+
+/// The closest folder above `file` with a manifest for a package. The folder
+/// is relative to the root of the repository, which is the empty path.
+fn package_root(repository: &Repository, tree: &Tree, file: &Path) -> Option<PathBuf> {
+    file.ancestors()
+        .skip(1)
+        .find(|dir| {
+            read_tree_file(repository, tree, &dir.join("Cargo.toml"))
+                .and_then(|manifest| manifest.parse::<DocumentMut>().ok())
+                .is_some_and(|manifest| manifest.contains_key("package"))
+        })
+        .map(Path::to_path_buf)
+}
+
+/// The closest workspace manifest at or above `package_root`, along with the
+/// folder it is in.
+fn workspace_manifest(repository: &Repository, tree: &Tree, package_root: &Path) -> Option<(PathBuf, DocumentMut)> {
+    package_root.ancestors().find_map(|dir| {
+        let manifest: DocumentMut = read_tree_file(repository, tree, &dir.join("Cargo.toml"))?.parse().ok()?;
+        manifest.contains_key("workspace").then(|| (dir.to_path_buf(), manifest))
+    })
+}
+
+/// Writes out every file in `tree` under `dest`. Submodules and symbolic links
+/// are skipped.
+fn copy_tree(repository: &Repository, tree: &Tree, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let mut files: Vec<(PathBuf, Oid)> = Vec::new();
+    tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+        if let (Some(ObjectType::Blob), Some(name)) = (entry.kind(), entry.name()) {
+            if entry.filemode() != SYMLINK_MODE {
+                files.push((Path::new(dir).join(name), entry.id()));
+            }
+        }
+        TreeWalkResult::Ok
+    })?;
+
+    for (path, id) in files {
+        let dest_path: PathBuf = dest.join(path);
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(dest_path, repository.find_blob(id)?.content())?;
+    }
+    Ok(())
+}
+
+/// Makes a package's manifest build outside of its repository. This is what
+/// `fix_tests.py` does for fixtures put together by hand, but with the
+/// workspace's own values rather than placeholders:
+/// - Keys and dependencies inherited from the workspace are copied in from
+///   the workspace's manifest
+/// - Path dependencies lose their path, as the other package isn't copied
+/// - `[lints]` inherited from the workspace is removed
+/// - The package is made its own workspace
+///
+/// Returns the new manifest, and anything that couldn't be carried over.
+pub fn standalone_manifest(manifest: &str, workspace: Option<&DocumentMut>) -> Result<(String, Vec<String>), toml_edit::TomlError> {
+    let mut manifest: DocumentMut = manifest.parse()?;
+    let mut warnings: Vec<String> = Vec::new();
+    let workspace_table = |key: &str| -> Option<&dyn TableLike> {
+        workspace?.get("workspace")?.get(key)?.as_table_like()
+    };
+
+    if let Some(package) = manifest.get_mut("package").and_then(Item::as_table_like_mut) {
+        package.remove("workspace");
+        for (key, item) in package.iter_mut() {
+            if !inherits(item) {
+                continue;
+            }
+            match workspace_table("package").and_then(|package| package.get(key.get())) {
+                Some(inherited) => *item = inherited.clone(),
+                None => {
+                    warnings.push(format!("package.{} is inherited from a workspace that doesn't set it", key.get()));
+                    *item = Item::None;
+                }
+            }
+        }
+    }
+
+    for kind in DEPENDENCY_TABLES {
+        if let Some(dependencies) = manifest.get_mut(kind).and_then(Item::as_table_like_mut) {
+            standalone_dependencies(dependencies, workspace_table("dependencies"), &mut warnings);
+        }
+    }
+    if let Some(targets) = manifest.get_mut("target").and_then(Item::as_table_like_mut) {
+        for (_, target) in targets.iter_mut() {
+            for kind in DEPENDENCY_TABLES {
+                if let Some(dependencies) = target.get_mut(kind).and_then(Item::as_table_like_mut) {
+                    standalone_dependencies(dependencies, workspace_table("dependencies"), &mut warnings);
+                }
+            }
+        }
+    }
+
+    if manifest.get("lints").is_some_and(inherits) {
+        manifest.remove("lints");
+    }
+    manifest.insert("workspace", Item::Table(Table::new()));
+
+    Ok((manifest.to_string(), warnings))
+}
+
+fn standalone_dependencies(dependencies: &mut dyn TableLike, inherited: Option<&dyn TableLike>, warnings: &mut Vec<String>) {
+    let mut dropped: Vec<String> = Vec::new();
+    for (name, item) in dependencies.iter_mut() {
+        if inherits(item) {
+            match inherited.and_then(|inherited| inherited.get(name.get())) {
+                Some(inherited) => *item = merge_dependency(inherited, item),
+                None => {
+                    warnings.push(format!("{} is inherited from a workspace that doesn't list it", name.get()));
+                    continue;
+                }
+            }
+        }
+
+        let Some(table) = item.as_table_like_mut() else {
+            continue;
+        };
+        if table.remove("path").is_none() {
+            continue;
+        }
+        // Without its path, a dependency that has nothing else to go on isn't
+        // valid, so it's dropped and the fixture only builds if it's unused.
+        // One that does now comes from somewhere else, which may not have the
+        // same code as the local copy.
+        if table.contains_key("git") {
+            warnings.push(format!("{} is a path dependency, so it now comes from its git repository instead", name.get()));
+        } else if let Some(version) = table.get("version").and_then(Item::as_str) {
+            warnings.push(format!("{} is a path dependency, so it now comes from crates.io ({}) instead", name.get(), version));
+        } else {
+            warnings.push(format!("{} is a path dependency without a version, so it was dropped", name.get()));
+            dropped.push(name.get().to_string());
+        }
+    }
+    for name in dropped {
+        dependencies.remove(&name);
+    }
+}
+
+/// Whether an item is `{ workspace = true }`, in any of its spellings.
+fn inherits(item: &Item) -> bool {
+    item.get("workspace").and_then(Item::as_bool) == Some(true)
+}
+
+/// A dependency that inherits from the workspace: the workspace's entry, with
+/// the package's own keys on top. Features are added to the workspace's ones.
+fn merge_dependency(inherited: &Item, local: &Item) -> Item {
+    let mut merged: InlineTable = InlineTable::new();
+    match inherited.as_value() {
+        Some(Value::String(version)) => {
+            merged.insert("version", Value::from(version.value().as_str()));
+        }
+        _ => {
+            for (key, value) in inherited.as_table_like().into_iter().flat_map(|table| table.iter()) {
+                if let Some(value) = value.as_value() {
+                    merged.insert(key, value.clone());
+                }
+            }
+        }
+    }
+
+    for (key, value) in local.as_table_like().into_iter().flat_map(|table| table.iter()) {
+        let Some(value) = value.as_value() else {
+            continue;
+        };
+        match (key, merged.get_mut("features"), value.as_array()) {
+            ("workspace", _, _) => {}
+            ("features", Some(Value::Array(features)), Some(local_features)) => {
+                features.extend(local_features.iter().cloned());
+                features.fmt();
+            }
+            _ => {
+                merged.insert(key, value.clone());
+            }
+        }
+    }
+    Item::Value(Value::InlineTable(merged))
+}
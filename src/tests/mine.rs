@@ -0,0 +1,377 @@
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    error::Error,
+    fs::{
+        File,
+        OpenOptions,
+    },
+    ops::RangeInclusive,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::Instant,
+};
+
+use git2::{
+    Blob,
+    Commit,
+    Delta,
+    Diff,
+    DiffDelta,
+    DiffLine,
+    Oid,
+    Patch,
+    Repository,
+    Revwalk,
+    Sort,
+    Time,
+};
+
+use log::{
+    info,
+    warn,
+};
+
+use serde::{
+    Serialize,
+    Serializer,
+};
+
+use syn::{
+    spanned::Spanned,
+    visit::{
+        self,
+        Visit,
+    },
+    Block,
+    Signature,
+};
+
+/// Options for mining a repository.
+/// - out: The CSV file the cases are appended to
+/// - depth: How many commits back from HEAD are looked at
+/// - max_cases: The most cases recorded for one repository
+/// - min_match: How much of the removed code (as a percentage of its lines)
+///   has to reappear in the new function's body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MineOptions {
+    pub out: PathBuf,
+    pub depth: usize,
+    pub max_cases: usize,
+    pub min_match: usize,
+}
+
+/// A row of the CSV file, with the same columns `scraping/0_scraper.py`
+/// writes. `replay` reads them back as `MinedCase`s.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MinedRow {
+    pub repo: String,
+    pub pre_sha: String,
+    pub post_sha: String,
+    pub pre_file: String,
+    pub post_file: String,
+    #[serde(rename = "fn")]
+    pub fn_name: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub match_pct: usize,
+    #[serde(serialize_with = "python_bool")]
+    pub generic: bool,
+    #[serde(rename = "async", serialize_with = "python_bool")]
+    pub is_async: bool,
+    pub author: String,
+    pub date: String,
+}
+
+/// Flags are written the way the Python scraper writes them, so that files
+/// from either can be mixed.
+fn python_bool<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(if *value { "True" } else { "False" })
+}
+
+/// A function defined in a file, with the lines its body's statements span.
+struct DefinedFn {
+    name: String,
+    generic: bool,
+    is_async: bool,
+    body: Option<RangeInclusive<usize>>,
+}
+
+/// Collects every function with a body: free functions, methods, and default
+/// methods in traits, including ones nested in modules or other functions.
+#[derive(Default)]
+struct FnCollector {
+    fns: Vec<DefinedFn>,
+}
+
+impl FnCollector {
+    fn add(&mut self, sig: &Signature, block: &Block) {
+        let body: Option<RangeInclusive<usize>> = match (block.stmts.first(), block.stmts.last()) {
+            (Some(first), Some(last)) => Some(first.span().start().line..=last.span().end().line),
+            _ => None,
+        };
+        self.fns.push(DefinedFn {
+            name: sig.ident.to_string(),
+            generic: !sig.generics.params.is_empty(),
+            is_async: sig.asyncness.is_some(),
+            body,
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for FnCollector {
+    fn visit_item_fn(&mut self, item: &'ast syn::ItemFn) {
+        self.add(&item.sig, &item.block);
+        visit::visit_item_fn(self, item);
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast syn::ImplItemFn) {
+        self.add(&item.sig, &item.block);
+        visit::visit_impl_item_fn(self, item);
+    }
+
+    fn visit_trait_item_fn(&mut self, item: &'ast syn::TraitItemFn) {
+        if let Some(block) = &item.default {
+            self.add(&item.sig, block);
+        }
+        visit::visit_trait_item_fn(self, item);
+    }
+}
+
+fn defined_fns(source: &str) -> Result<Vec<DefinedFn>, syn::Error> {
+    let file: syn::File = syn::parse_file(source)?;
+    let mut collector: FnCollector = FnCollector::default();
+    collector.visit_file(&file);
+    Ok(collector.fns)
+}
+
+/// Walks back through the history of the repository at `repo_path`, appends
+/// every extract-method refactoring it finds to `options.out`, and returns how
+/// many it found.
+///
+/// A commit counts as an extract-method refactoring of a file if it adds a
+/// function to the file whose body is made up of at least `min_match`% of the
+/// lines removed from the file. The line range recorded is the removed lines
+/// that reappear in the new function, in the file before the commit.
+pub fn run_mine(repo_path: &Path, options: &MineOptions) -> Result<usize, Box<dyn Error>> {
+    let repository: Repository = Repository::open(repo_path)?;
+    let repo_name: String = repo_path
+        .canonicalize()?
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    info!("Mining {} ({} commits back)", repo_name, options.depth);
+    let start: Instant = Instant::now();
+
+    let mut revwalk: Revwalk = repository.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+
+    let mut rows: Vec<MinedRow> = Vec::new();
+    for oid in revwalk.take(options.depth) {
+        if rows.len() >= options.max_cases {
+            break;
+        }
+        let commit: Commit = repository.find_commit(oid?)?;
+        let Ok(parent) = commit.parent(0) else {
+            continue;
+        };
+        let diff: Diff = repository.diff_tree_to_tree(Some(&parent.tree()?), Some(&commit.tree()?), None)?;
+
+        for index in 0..diff.deltas().len() {
+            if rows.len() >= options.max_cases {
+                break;
+            }
+            let Some(patch) = Patch::from_diff(&diff, index)? else {
+                continue;
+            };
+            for row in mine_patch(&repository, &patch, options.min_match)? {
+                let row: MinedRow = MinedRow {
+                    repo: repo_name.clone(),
+                    pre_sha: parent.id().to_string(),
+                    post_sha: commit.id().to_string(),
+                    author: commit.author().email().unwrap_or_default().to_string(),
+                    date: iso_8601(commit.time()),
+                    ..row
+                };
+                println!(
+                    "{} {}: {} in {} (lines {}..={}, {}% match)",
+                    row.repo,
+                    &row.post_sha[..7],
+                    row.fn_name,
+                    row.post_file,
+                    row.start_line,
+                    row.end_line,
+                    row.match_pct,
+                );
+                rows.push(row);
+            }
+        }
+    }
+    rows.truncate(options.max_cases);
+
+    write_rows(&options.out, &rows)?;
+    println!("Mined {} cases from {} into {:?} in {:.2}s", rows.len(), repo_name, options.out, start.elapsed().as_secs_f64());
+    info!("Mined {} cases from {} into {:?}", rows.len(), repo_name, options.out);
+    Ok(rows.len())
+}
+
+/// The refactorings in one file's changes. The commit columns of each row are
+/// left for the caller to fill in.
+fn mine_patch(repository: &Repository, patch: &Patch, min_match: usize) -> Result<Vec<MinedRow>, Box<dyn Error>> {
+    let delta: DiffDelta = patch.delta();
+    if delta.status() != Delta::Modified {
+        return Ok(Vec::new());
+    }
+    let (Some(pre_file), Some(post_file)) = (delta.old_file().path(), delta.new_file().path()) else {
+        return Ok(Vec::new());
+    };
+    if post_file.extension().is_none_or(|extension| extension != "rs") {
+        return Ok(Vec::new());
+    }
+
+    // Files that aren't valid UTF-8 or don't parse (e.g. test fixtures with
+    // deliberate syntax errors) are skipped
+    let (Some(pre_source), Some(post_source)) = (
+        blob_text(repository, delta.old_file().id()),
+        blob_text(repository, delta.new_file().id()),
+    ) else {
+        return Ok(Vec::new());
+    };
+    let (Ok(pre_fns), Ok(post_fns)) = (defined_fns(&pre_source), defined_fns(&post_source)) else {
+        warn!("Skipping {:?}, which doesn't parse", post_file);
+        return Ok(Vec::new());
+    };
+
+    let removed: Vec<(usize, String)> = removed_lines(patch)?;
+    if removed.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // A function is new if the file has more functions with its name than it
+    // had before
+    let mut existing: HashMap<&str, usize> = HashMap::new();
+    for defined in &pre_fns {
+        *existing.entry(defined.name.as_str()).or_insert(0) += 1;
+    }
+
+    let post_lines: Vec<&str> = post_source.lines().collect();
+    let mut rows: Vec<MinedRow> = Vec::new();
+    for defined in &post_fns {
+        if let Some(count) = existing.get_mut(defined.name.as_str()).filter(|count| **count > 0) {
+            *count -= 1;
+            continue;
+        }
+        let Some(body) = &defined.body else {
+            continue;
+        };
+
+        let body_lines: HashSet<&str> = post_lines
+            .get(body.start() - 1..*body.end())
+            .unwrap_or_default()
+            .iter()
+            .map(|line| line.trim())
+            .collect();
+        let moved: Vec<usize> = removed
+            .iter()
+            .filter(|(_, line)| body_lines.contains(line.as_str()))
+            .map(|(line_number, _)| *line_number)
+            .collect();
+        let match_pct: usize = moved.len() * 100 / removed.len();
+        if match_pct < min_match {
+            continue;
+        }
+        let (Some(&start_line), Some(&end_line)) = (moved.iter().min(), moved.iter().max()) else {
+            continue;
+        };
+
+        rows.push(MinedRow {
+            repo: String::new(),
+            pre_sha: String::new(),
+            post_sha: String::new(),
+            pre_file: pre_file.to_string_lossy().to_string(),
+            post_file: post_file.to_string_lossy().to_string(),
+            fn_name: defined.name.clone(),
+            start_line,
+            end_line,
+            match_pct,
+            generic: defined.generic,
+            is_async: defined.is_async,
+            author: String::new(),
+            date: String::new(),
+        });
+    }
+    Ok(rows)
+}
+
+fn blob_text(repository: &Repository, id: Oid) -> Option<String> {
+    let blob: Blob = repository.find_blob(id).ok()?;
+    String::from_utf8(blob.content().to_vec()).ok()
+}
+
+/// The lines removed from a file that aren't blank, trimmed, with their line
+/// numbers in the file before the change.
+fn removed_lines(patch: &Patch) -> Result<Vec<(usize, String)>, git2::Error> {
+    let mut removed: Vec<(usize, String)> = Vec::new();
+    for hunk in 0..patch.num_hunks() {
+        for index in 0..patch.num_lines_in_hunk(hunk)? {
+            let line: DiffLine = patch.line_in_hunk(hunk, index)?;
+            let Some(line_number) = line.old_lineno().filter(|_| line.origin() == '-') else {
+                continue;
+            };
+            let content: String = String::from_utf8_lossy(line.content()).trim().to_string();
+            if !content.is_empty() {
+                removed.push((line_number as usize, content));
+            }
+        }
+    }
+    Ok(removed)
+}
+
+/// Appends the rows to a CSV file, writing the header first if the file is new.
+fn write_rows(out: &Path, rows: &[MinedRow]) -> Result<(), Box<dyn Error>> {
+    let new: bool = out.metadata().map_or(true, |metadata| metadata.len() == 0);
+    let file: File = OpenOptions::new().create(true).append(true).open(out)?;
+    let mut writer: csv::Writer<File> = csv::WriterBuilder::new().has_headers(new).from_writer(file);
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Formats a commit time the way Python's `datetime.isoformat` does, e.g.
+/// `2024-10-11T09:30:00+10:00`.
+pub fn iso_8601(time: Time) -> String {
+    let offset_minutes: i64 = time.offset_minutes() as i64;
+    let local: i64 = time.seconds() + offset_minutes * 60;
+    let (days, seconds) = (local.div_euclid(86_400), local.rem_euclid(86_400));
+
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm)
+    let z: i64 = days + 719_468;
+    let era: i64 = z.div_euclid(146_097);
+    let day_of_era: i64 = z.rem_euclid(146_097);
+    let year_of_era: i64 = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year: i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index: i64 = (5 * day_of_year + 2) / 153;
+    let day: i64 = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month: i64 = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year: i64 = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        if offset_minutes < 0 { '-' } else { '+' },
+        offset_minutes.abs() / 60,
+        offset_minutes.abs() % 60,
+    )
+}
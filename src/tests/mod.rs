@@ -1,6 +1,26 @@
-pub mod controller;
-pub mod repairer;
-pub mod borrower;
-pub mod extract;
-
+pub mod controller;
+pub mod repairer;
+pub mod borrower;
+pub mod extract;
+pub mod report;
+pub mod table;
+pub mod harness;
+pub mod verify;
+pub mod equivalence;
+pub mod pipeline;
+pub mod fuzz;
+pub mod reduce;
+pub mod bench;
+pub mod tags;
+pub mod eval;
+pub mod record;
+pub mod bundle;
+pub mod baseline;
+pub mod replay;
+pub mod mine;
+pub mod import;
+pub mod corpus;
+pub mod ast;
+pub mod diff;
+
 mod utils;
\ No newline at end of file
@@ -0,0 +1,330 @@
+use rem_extract::extraction::{
+    ExtractionInput,
+    extract_method,
+};
+use rem_repairer::common::RepairResult;
+use rem_utils::{
+    remove_all_files,
+    strip_ansi_codes,
+};
+
+use colored::Colorize;
+
+use log::{
+    error,
+    info,
+};
+
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, read_to_string},
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use crate::refactor::{
+    borrow::borrow,
+    non_local_controller::non_local_controller,
+    throughput::{
+        Borrower,
+        Controller,
+    },
+};
+
+use crate::tests::{
+    extract::{
+        self,
+        verify_output,
+    },
+    harness::HarnessOptions,
+    repairer::repair_systems,
+    report::{
+        CaseResult,
+        SuiteReport,
+    },
+};
+
+/// The stages of the pipeline, in the order they run. The name of each stage is
+/// also the name of its check in the report.
+pub const STAGES: [&str; 4] = ["extraction", "controller", "borrower", "repairer"];
+
+/// The check recorded when the pipeline couldn't be run at all, e.g. because
+/// the input couldn't be read or a scratch file couldn't be written. It comes
+/// before the stages, and isn't one of them.
+pub const SETUP: &str = "setup";
+
+/// The parts of a stage's code that are written to the scratch directory
+/// besides its output. See `stage_file`.
+pub const WORKING: &str = "working";
+pub const TEMPORARY: &str = "temporary";
+
+/// Runs the whole pipeline (extract, controller, borrower, repairer) on every
+/// extract fixture, starting from the original program and its selection
+/// rather than from hand-prepared intermediate files. Imported fixtures whose
+/// selection isn't in `src/main.rs` are left out.
+pub fn cases(path: &Path) -> Result<Vec<String>, io::Error> {
+    extract::main_cases(path)
+}
+
+/// Final outputs are written to `pipeline/output`, which is cleared before
+/// each run.
+pub fn prepare(path: &Path) -> Result<(), io::Error> {
+    let output_dir = path.join("pipeline/output");
+    fs::create_dir_all(&output_dir)?;
+    remove_all_files(&output_dir);
+    Ok(())
+}
+
+pub fn run_case(path: &Path, case: &str, options: &HarnessOptions) -> Result<CaseResult, io::Error> {
+    let (index, test_file) = match extract::load_test_file(path, case)? {
+        Some(found) => found,
+        None => {
+            error!("No pipeline test named {}", case);
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("No pipeline test named {}", case)));
+        }
+    };
+
+    let fixture_dir: PathBuf = path.join("extract/input").join(&test_file.input_file);
+    let (case, output_code) = check_selection(
+        &fixture_dir,
+        &test_file.input_file,
+        test_file.start_idx,
+        test_file.end_idx,
+        options,
+    )?;
+
+    if let Some(output_code) = output_code {
+        let output_path: PathBuf = path.join("pipeline/output")
+            .join(&test_file.input_file)
+            .with_extension("rs");
+        fs::write(&output_path, &output_code)?;
+    }
+
+    let status: String = match case.first_failure() {
+        Some(check) => format!("FAILED at {}", check.name).red().to_string(),
+        None => "PASSED".green().to_string(),
+    };
+    let elapsed: f64 = case.duration.as_secs_f64();
+    println!("Test {} | {}: {} in {:.2}s", index + 1, status, test_file.input_file, elapsed);
+    info!("Test {} | {}: {} in {:.2}s", index + 1, strip_ansi_codes(&status), test_file.input_file, elapsed);
+    if let Some(check) = case.first_failure() {
+        if let Some(message) = &check.message {
+            for line in message.lines() {
+                println!("    {}", line);
+            }
+        }
+    }
+
+    Ok(case)
+}
+
+/// Passes the output of each stage on to the next one, stopping at the first
+/// stage that fails. If every stage succeeds, the final output is built, run
+/// and compared with the original program in the same way as the extract
+/// suite. The result gets one check per stage that was run, so the first
+/// failing check is the first failing stage. Also returns the final output if
+/// every stage succeeded.
+/// - fixture_dir: A Cargo project whose `src/main.rs` is refactored
+/// - name: Names the case, and its scratch directories
+pub fn check_selection(
+    fixture_dir: &Path,
+    name: &str,
+    start_idx: u32,
+    end_idx: u32,
+    options: &HarnessOptions,
+) -> Result<(CaseResult, Option<String>), io::Error> {
+    let start: Instant = Instant::now();
+    let result: Result<String, (&str, String)> = run_pipeline(fixture_dir, name, start_idx, end_idx, &mut Vec::new());
+    let mut case: CaseResult = CaseResult::new(name, start.elapsed());
+
+    match add_stage_checks(&mut case, result) {
+        Some(output_code) => {
+            verify_output(fixture_dir, name, &output_code, options, &mut case)?;
+            Ok((case, Some(output_code)))
+        }
+        None => Ok((case, None)),
+    }
+}
+
+/// Adds a check for each stage that was run to `case`, given the result of
+/// `run_pipeline`. Returns the final output if every stage succeeded. If the
+/// pipeline couldn't be set up, only the failed `setup` check is added.
+pub fn add_stage_checks(case: &mut CaseResult, result: Result<String, (&'static str, String)>) -> Option<String> {
+    match result {
+        Ok(output_code) => {
+            for stage in STAGES {
+                case.add_check(stage, true, None);
+            }
+            Some(output_code)
+        }
+        Err((SETUP, message)) => {
+            case.add_check(SETUP, false, Some(message));
+            None
+        }
+        Err((failed_stage, message)) => {
+            for stage in STAGES.iter().take_while(|stage| **stage != failed_stage) {
+                case.add_check(stage, true, None);
+            }
+            case.add_check(failed_stage, false, Some(message));
+            None
+        }
+    }
+}
+
+/// Whether a check is one of the REM stages, as opposed to one of the checks
+/// on the final output.
+pub fn is_stage(check: &str) -> bool {
+    STAGES.contains(&check)
+}
+
+/// Runs each stage in turn. Intermediate files are kept in the case's scratch
+/// directory. Returns the final code, or the stage that failed and why. The
+/// time taken by each stage that succeeds is pushed to `timings`.
+pub fn run_pipeline(
+    fixture_dir: &Path,
+    name: &str,
+    start_idx: u32,
+    end_idx: u32,
+    timings: &mut Vec<(&'static str, Duration)>,
+) -> Result<String, (&'static str, String)> {
+    let input_path: PathBuf = fixture_dir.join("src").join("main.rs");
+    let scratch_dir: PathBuf = scratch_dir(name).map_err(|e| (SETUP, e.to_string()))?;
+    run_pipeline_on_file(&input_path, "fun_name", &scratch_dir, start_idx, end_idx, timings)
+}
+
+/// Runs each stage in turn on any file in a Cargo project, extracting the
+/// selection into a function called `new_fn_name`. Used by `run_pipeline`, and
+/// by `replay` for files in real-world crates. The controller and borrower are
+/// run through the same wrappers as the CLI. The code each stage is given
+/// (its working code, and the borrower's temporary code) and the code it
+/// produces are written to `scratch_dir` as it goes, see `stage_file`. Failing
+/// to read the input or to write a scratch file is reported as `setup` rather
+/// than as a stage.
+pub fn run_pipeline_on_file(
+    input_path: &Path,
+    new_fn_name: &str,
+    scratch_dir: &Path,
+    start_idx: u32,
+    end_idx: u32,
+    timings: &mut Vec<(&'static str, Duration)>,
+) -> Result<String, (&'static str, String)> {
+    let original_code: String = read_to_string(input_path)
+        .map_err(|e| (SETUP, format!("Failed to read {:?}: {}", input_path, e)))?;
+
+    fs::create_dir_all(scratch_dir)
+        .map_err(|e| (SETUP, format!("Failed to create {:?}: {}", scratch_dir, e)))?;
+
+    // Extraction
+    write_stage(scratch_dir, "extraction", Some(WORKING), &original_code)?;
+    let stage_start: Instant = Instant::now();
+    let (extracted_code, caller_fn_name) = extract_method(ExtractionInput {
+        file_path: input_path.to_string_lossy().to_string(),
+        new_fn_name: new_fn_name.to_string(),
+        start_idx,
+        end_idx,
+    }).map_err(|e| ("extraction", e.to_string()))?;
+    timings.push(("extraction", stage_start.elapsed()));
+    write_stage(scratch_dir, "extraction", None, &extracted_code)?;
+
+    // Non local control flow
+    write_stage(scratch_dir, "controller", Some(WORKING), &extracted_code)?;
+    let stage_start: Instant = Instant::now();
+    let controlled_code: String = non_local_controller(Controller::new(
+        extracted_code,
+        None,
+        caller_fn_name.clone(),
+        new_fn_name.to_string(),
+    )).map_err(|e| ("controller", e.to_string()))?;
+    timings.push(("controller", stage_start.elapsed()));
+    write_stage(scratch_dir, "controller", None, &controlled_code)?;
+
+    // Borrowing. Nothing earlier in the pipeline produces the mutable method
+    // call annotations (the hand-written `method_call_mut` files in the
+    // borrower suite), so the controller's output is passed in their place.
+    write_stage(scratch_dir, "borrower", Some(WORKING), &controlled_code)?;
+    write_stage(scratch_dir, "borrower", Some(TEMPORARY), &controlled_code)?;
+    let stage_start: Instant = Instant::now();
+    let borrowed_code: String = borrow(Borrower::new(
+        controlled_code.clone(),
+        original_code,
+        None,
+        caller_fn_name,
+        new_fn_name.to_string(),
+        controlled_code,
+    )).map_err(|e| ("borrower", e.to_string()))?;
+    timings.push(("borrower", stage_start.elapsed()));
+    write_stage(scratch_dir, "borrower", None, &borrowed_code)?;
+
+    // Lifetime repair. Each repair system is tried in turn until one succeeds.
+    let borrowed_path: PathBuf = write_stage(scratch_dir, "repairer", Some(WORKING), &borrowed_code)?;
+    let stage_start: Instant = Instant::now();
+    let repaired_path: PathBuf = stage_file(scratch_dir, "repairer", None);
+    let mut attempted: Vec<&str> = Vec::new();
+    for repair_system in repair_systems() {
+        let RepairResult { success, .. } = repair_system.repair_function(
+            &borrowed_path.to_string_lossy(),
+            &repaired_path.to_string_lossy(),
+            new_fn_name,
+        );
+        if success {
+            timings.push(("repairer", stage_start.elapsed()));
+            return read_to_string(&repaired_path)
+                .map_err(|e| ("repairer", format!("Failed to read {:?}: {}", repaired_path, e)));
+        }
+        attempted.push(repair_system.name());
+    }
+
+    Err(("repairer", format!("No repair system succeeded (tried {})", attempted.join(", "))))
+}
+
+/// Where the suites keep the output of each stage for the case called `name`.
+pub fn scratch_dir(name: &str) -> io::Result<PathBuf> {
+    Ok(env::current_dir()?.join("pipeline").join(name))
+}
+
+/// The file in `scratch_dir` that `run_pipeline_on_file` writes part of a
+/// stage's code to: `<stage>.working.rs` for the code it was given,
+/// `<stage>.temporary.rs` for the borrower's temporary code, and `<stage>.rs`
+/// for the code it produced when `part` is `None`.
+pub fn stage_file(scratch_dir: &Path, stage: &str, part: Option<&str>) -> PathBuf {
+    match part {
+        Some(part) => scratch_dir.join(format!("{}.{}.rs", stage, part)),
+        None => scratch_dir.join(format!("{}.rs", stage)),
+    }
+}
+
+fn write_stage(scratch_dir: &Path, stage: &str, part: Option<&str>, code: &str) -> Result<PathBuf, (&'static str, String)> {
+    let stage_path: PathBuf = stage_file(scratch_dir, stage, part);
+    fs::write(&stage_path, code)
+        .map_err(|e| (SETUP, format!("Failed to write {:?}: {}", stage_path, e)))?;
+    Ok(stage_path)
+}
+
+/// Prints how many cases made it all the way through, and for the rest, the
+/// stage each one first failed at.
+pub fn print_summary(report: &SuiteReport, elapsed: Duration) {
+    let mut first_failures: BTreeMap<String, usize> = BTreeMap::new();
+    for case in &report.cases {
+        if let Some(check) = case.first_failure() {
+            *first_failures.entry(check.name.clone()).or_insert(0) += 1;
+        }
+    }
+
+    let passed: usize = report.cases.len() - report.failed();
+    println!("------------------------------------------------------------------");
+    println!("Pipeline: {}/{} passed in {:.2}s", passed, report.cases.len(), elapsed.as_secs_f64());
+    info!("Pipeline: {}/{} passed in {:.2}s", passed, report.cases.len(), elapsed.as_secs_f64());
+    for (stage, count) in &first_failures {
+        println!("First failed at {}: {}", stage, count);
+        info!("First failed at {}: {}", stage, count);
+    }
+    println!("------------------------------------------------------------------");
+}
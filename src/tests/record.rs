@@ -0,0 +1,280 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    fs::{
+        self,
+        read_to_string,
+    },
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use colored::Colorize;
+
+use git2::Time;
+
+use log::{
+    info,
+    warn,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::tests::{
+    mine::iso_8601,
+    report::{
+        CaseResult,
+        SuiteReport,
+        TestReport,
+    },
+};
+
+/// A crate REM was built with.
+/// - version: From the crate's manifest, if it could be read
+/// - sha: The commit its checkout was on, if it is in a git repository
+/// - dirty: Whether the checkout had uncommitted changes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateVersion {
+    pub name: String,
+    pub version: Option<String>,
+    pub sha: Option<String>,
+    pub dirty: bool,
+}
+
+/// An evaluation run saved by `eval --record`, to be compared with later runs.
+/// The id is the time it was recorded, numbered if there is more than one run
+/// in the same second, so runs can be put in order by id (see `run_order`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRun {
+    pub id: String,
+    pub recorded_at: String,
+    pub crates: Vec<CrateVersion>,
+    pub suites: Vec<SuiteReport>,
+}
+
+/// How a case changed between two runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Change {
+    Regressed,
+    Fixed,
+    Added,
+    Removed,
+}
+
+impl fmt::Display for CrateVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.version.as_deref().unwrap_or("?"))?;
+        match (&self.sha, self.dirty) {
+            (Some(sha), true) => write!(f, " ({}, dirty)", &sha[..sha.len().min(7)]),
+            (Some(sha), false) => write!(f, " ({})", &sha[..sha.len().min(7)]),
+            (None, _) => Ok(()),
+        }
+    }
+}
+
+impl Change {
+    fn heading(&self) -> &'static str {
+        match self {
+            Change::Regressed => "Regressed",
+            Change::Fixed => "Started passing",
+            Change::Added => "Only in the second run",
+            Change::Removed => "Only in the first run",
+        }
+    }
+}
+
+/// Saves the results of an evaluation run in `results_dir`, along with the
+/// versions of the crates it was run with. Returns where the run was saved.
+pub fn record_run(report: &TestReport, results_dir: &Path) -> io::Result<PathBuf> {
+    let (recorded_at, stem) = timestamp();
+    fs::create_dir_all(results_dir)?;
+    let mut id: String = stem.clone();
+    let mut suffix: usize = 1;
+    while results_dir.join(&id).with_extension("json").exists() {
+        suffix += 1;
+        id = format!("{}_{}", stem, suffix);
+    }
+
+    let run: RecordedRun = RecordedRun {
+        id,
+        recorded_at,
+        crates: crate_versions(),
+        suites: report.suites.clone(),
+    };
+    let path: PathBuf = results_dir.join(&run.id).with_extension("json");
+    let contents: String = serde_json::to_string_pretty(&run).map_err(io::Error::other)?;
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+/// The current time in ISO 8601, and as something that can go in a file name,
+/// e.g. 2024-10-11T09:30:00+00:00 and 2024-10-11_09-30-00 (like the log files).
+pub fn timestamp() -> (String, String) {
+    let now: i64 = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_secs() as i64);
+    let time: String = iso_8601(Time::new(now, 0));
+    let stem: String = time[..19].replace('T', "_").replace(':', "-");
+    (time, stem)
+}
+
+/// Loads a recorded run. `run` is the path to a recorded run, its id, `latest`
+/// for the most recent run in `results_dir`, or `latest~N` for the one N runs
+/// before that.
+pub fn load_run(results_dir: &Path, run: &str) -> Result<RecordedRun, Box<dyn Error>> {
+    let path: PathBuf = if Path::new(run).is_file() {
+        PathBuf::from(run)
+    } else if let Some(back) = run.strip_prefix("latest") {
+        let back: usize = match back.strip_prefix('~') {
+            Some(back) => back.parse().map_err(|_| format!("Expected latest~<number>, got {}", run))?,
+            None if back.is_empty() => 0,
+            None => return Err(format!("No recorded run {} in {:?}", run, results_dir).into()),
+        };
+        let mut ids: Vec<PathBuf> = fs::read_dir(results_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+            .collect();
+        ids.sort_by_key(|path| run_order(path));
+        ids.into_iter()
+            .rev()
+            .nth(back)
+            .ok_or_else(|| format!("There are not enough recorded runs in {:?} for {}", results_dir, run))?
+    } else {
+        results_dir.join(run).with_extension("json")
+    };
+
+    let contents: String = read_to_string(&path).map_err(|e| format!("Failed to read recorded run {:?}: {}", path, e))?;
+    Ok(serde_json::from_str(&contents).map_err(|e| format!("Invalid recorded run {:?}: {}", path, e))?)
+}
+
+/// Orders recorded runs by when they were recorded. Runs recorded in the same
+/// second are saved as `<time>_2`, `<time>_3` and so on, so the suffix is
+/// compared as a number for `_10` to come after `_9`.
+fn run_order(path: &Path) -> (String, usize) {
+    let stem: String = path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().to_string());
+    match stem.rsplit_once('_') {
+        Some((time, suffix)) if !suffix.is_empty() && suffix.bytes().all(|byte| byte.is_ascii_digit()) => {
+            (time.to_string(), suffix.parse().unwrap_or(usize::MAX))
+        }
+        _ => (stem, 1),
+    }
+}
+
+/// The cases that changed between two runs, grouped by how they changed.
+/// Cases are matched by suite and name.
+pub fn compare_runs<'a>(before: &'a RecordedRun, after: &'a RecordedRun) -> Vec<(Change, &'a str, &'a CaseResult)> {
+    let cases = |run: &'a RecordedRun| -> HashMap<(&'a str, &'a str), &'a CaseResult> {
+        run.suites
+            .iter()
+            .flat_map(|suite| suite.cases.iter().map(move |case| ((suite.name.as_str(), case.name.as_str()), case)))
+            .collect()
+    };
+    let (before_cases, after_cases) = (cases(before), cases(after));
+
+    let mut changes: Vec<(Change, &str, &CaseResult)> = Vec::new();
+    for suite in &after.suites {
+        for case in &suite.cases {
+            let change: Option<Change> = match before_cases.get(&(suite.name.as_str(), case.name.as_str())) {
+                None => Some(Change::Added),
+                Some(previous) if previous.passed() && !case.passed() => Some(Change::Regressed),
+                Some(previous) if !previous.passed() && case.passed() => Some(Change::Fixed),
+                Some(_) => None,
+            };
+            if let Some(change) = change {
+                changes.push((change, suite.name.as_str(), case));
+            }
+        }
+    }
+    for suite in &before.suites {
+        for case in &suite.cases {
+            if !after_cases.contains_key(&(suite.name.as_str(), case.name.as_str())) {
+                changes.push((Change::Removed, suite.name.as_str(), case));
+            }
+        }
+    }
+    changes.sort_by_key(|(change, _, _)| *change);
+    changes
+}
+
+/// Prints which crates changed between two runs, and which cases regressed or
+/// started passing. Returns the number of regressions.
+pub fn print_comparison(before: &RecordedRun, after: &RecordedRun) -> usize {
+    println!("------------------------------------------------------------------");
+    println!("Comparing {} with {}", before.id, after.id);
+    for name in crate_names(before, after) {
+        let find = |run: &RecordedRun| run.crates.iter().find(|krate| krate.name == name).map(ToString::to_string);
+        let (was, now) = (find(before).unwrap_or_else(|| "-".to_string()), find(after).unwrap_or_else(|| "-".to_string()));
+        if was == now {
+            println!("    {:<16} {}", name, now);
+        } else {
+            println!("    {:<16} {} -> {}", name, was, now.yellow());
+        }
+    }
+
+    let changes: Vec<(Change, &str, &CaseResult)> = compare_runs(before, after);
+    let mut heading: Option<Change> = None;
+    for (change, suite, case) in &changes {
+        if heading != Some(*change) {
+            let count: usize = changes.iter().filter(|(other, _, _)| other == change).count();
+            println!("------------------------------------------------------------------");
+            println!("{} ({}):", change.heading(), count);
+            heading = Some(*change);
+        }
+        let name: String = format!("{} {}", suite, case.name);
+        match (change, case.first_failure()) {
+            (Change::Regressed, Some(check)) => {
+                let message: &str = check.message.as_deref().and_then(|message| message.lines().next()).unwrap_or_default();
+                println!("    {:<56} {} at {}: {}", name, "FAILED".red(), check.name, message);
+            }
+            (Change::Fixed, _) => println!("    {:<56} {}", name, "PASSED".green()),
+            _ => println!("    {}", name),
+        }
+    }
+
+    println!("------------------------------------------------------------------");
+    for suite in &after.suites {
+        let previous: Option<&SuiteReport> = before.suites.iter().find(|previous| previous.name == suite.name);
+        let passed = |suite: &SuiteReport| format!("{}/{}", suite.cases.len() - suite.failed(), suite.cases.len());
+        println!(
+            "{}: {} -> {} passed",
+            suite.name,
+            previous.map(passed).unwrap_or_else(|| "-".to_string()),
+            passed(suite),
+        );
+    }
+    let regressions: usize = changes.iter().filter(|(change, _, _)| *change == Change::Regressed).count();
+    let fixed: usize = changes.iter().filter(|(change, _, _)| *change == Change::Fixed).count();
+    println!("{} regressed, {} started passing", regressions, fixed);
+    info!("Compared {} with {}: {} regressed, {} started passing", before.id, after.id, regressions, fixed);
+    println!("------------------------------------------------------------------");
+    regressions
+}
+
+/// Every crate in either run, in the order they were recorded.
+fn crate_names<'a>(before: &'a RecordedRun, after: &'a RecordedRun) -> Vec<&'a str> {
+    let mut names: Vec<&str> = Vec::new();
+    for krate in before.crates.iter().chain(&after.crates) {
+        if !names.contains(&krate.name.as_str()) {
+            names.push(&krate.name);
+        }
+    }
+    names
+}
+
+/// rem-cli and the rem-* crates it depends on, as they were when rem-cli was
+/// built. Path dependencies were looked up in their checkouts by `build.rs`.
+pub fn crate_versions() -> Vec<CrateVersion> {
+    serde_json::from_str(env!("REM_CRATE_VERSIONS")).unwrap_or_else(|e| {
+        warn!("The crate versions recorded at build time are invalid: {}", e);
+        Vec::new()
+    })
+}
@@ -0,0 +1,471 @@
+use std::{
+    fmt,
+    fs,
+    io,
+    ops::Range,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use colored::Colorize;
+
+use log::{
+    info,
+    warn,
+};
+
+use syn::{
+    spanned::Spanned,
+    visit::{
+        self,
+        Visit,
+    },
+};
+
+use tempfile::TempDir;
+
+use crate::tests::{
+    extract::SelectionManifest,
+    fuzz::{
+        byte_offset,
+        line_starts,
+        run_selection,
+        Selection,
+    },
+    harness::run_parallel,
+    report::{
+        CaseOutcome,
+        CaseResult,
+    },
+};
+
+/// Options for a reduction.
+/// - jobs: The number of smaller inputs that are tried at the same time
+/// - timeout: How long a single attempt may run before it is counted as a
+///   hang
+/// - out: The test folder the reduced fixture is added to
+/// - name: The name of the reduced fixture (its folder and package name)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReduceOptions {
+    pub jobs: usize,
+    pub timeout: Duration,
+    pub out: PathBuf,
+    pub name: String,
+}
+
+/// What has to stay the same while the input is shrunk: how the case ended,
+/// and the first check that failed. Panics also keep the first line of the
+/// panic message, with any numbers masked out since they tend to be indices
+/// into the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailureSignature {
+    pub outcome: CaseOutcome,
+    pub check: String,
+    pub panic: Option<String>,
+}
+
+/// The program and selection being reduced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Candidate {
+    source: String,
+    start_idx: u32,
+    end_idx: u32,
+}
+
+/// The two granularities the input is reduced at. Items are tried first since
+/// removing them tends to remove the most code per attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pass {
+    Items,
+    Statements,
+}
+
+impl FailureSignature {
+    /// The signature of a failed case, or `None` if it passed.
+    pub fn of(result: &CaseResult) -> Option<Self> {
+        let check = result.first_failure()?;
+        let panic: Option<String> = match result.outcome {
+            CaseOutcome::Panicked => check
+                .message
+                .as_deref()
+                .and_then(|message| message.lines().next())
+                .map(|line| line.chars().map(|c| if c.is_ascii_digit() { '#' } else { c }).collect()),
+            _ => None,
+        };
+        Some(Self {
+            outcome: result.outcome,
+            check: check.name.clone(),
+            panic,
+        })
+    }
+}
+
+impl fmt::Display for Pass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pass::Items => write!(f, "items"),
+            Pass::Statements => write!(f, "statements"),
+        }
+    }
+}
+
+impl fmt::Display for FailureSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.panic {
+            Some(message) => write!(f, "panic ({})", message),
+            None if self.outcome == CaseOutcome::TimedOut => write!(f, "timeout"),
+            None => write!(f, "{} failed", self.check),
+        }
+    }
+}
+
+impl Candidate {
+    /// Removes the given ranges from the source, moving the selection so that
+    /// it still covers the same code. None of the ranges may straddle either
+    /// end of the selection.
+    fn without(&self, ranges: &[Range<usize>]) -> Candidate {
+        let mut ranges: Vec<Range<usize>> = ranges.to_vec();
+        ranges.sort_by_key(|range| (range.start, usize::MAX - range.end));
+
+        let mut source: String = String::with_capacity(self.source.len());
+        let mut kept_from: usize = 0;
+        let mut removed_before_start: usize = 0;
+        let mut removed_before_end: usize = 0;
+        for range in ranges {
+            // Nested inside a range that has already been removed
+            if range.start < kept_from {
+                continue;
+            }
+            source.push_str(&self.source[kept_from..range.start]);
+            kept_from = range.end;
+
+            if range.end <= self.start_idx as usize {
+                removed_before_start += range.len();
+            }
+            if range.end <= self.end_idx as usize {
+                removed_before_end += range.len();
+            }
+        }
+        source.push_str(&self.source[kept_from..]);
+
+        Candidate {
+            source,
+            start_idx: self.start_idx - removed_before_start as u32,
+            end_idx: self.end_idx - removed_before_end as u32,
+        }
+    }
+
+    /// Whether removing `range` leaves the selection intact: the range has to
+    /// be entirely before it, entirely after it, or strictly inside it.
+    fn can_remove(&self, range: &Range<usize>) -> bool {
+        let (start, end) = (self.start_idx as usize, self.end_idx as usize);
+        range.end <= start || range.start >= end || (range.start > start && range.end < end)
+    }
+
+    /// The ranges that can be removed in the given pass. Each range is widened
+    /// to whole lines when it is the only thing on them, so that removing it
+    /// doesn't leave blank lines behind.
+    fn removable(&self, pass: Pass) -> Result<Vec<Range<usize>>, syn::Error> {
+        let file: syn::File = syn::parse_file(&self.source)?;
+        let mut collector: UnitCollector = UnitCollector {
+            source: &self.source,
+            line_starts: line_starts(&self.source),
+            pass,
+            units: Vec::new(),
+        };
+        collector.visit_file(&file);
+
+        let mut units: Vec<Range<usize>> = collector
+            .units
+            .into_iter()
+            .map(|unit| {
+                let widened: Range<usize> = widen_to_lines(&self.source, &unit);
+                if self.can_remove(&widened) { widened } else { unit }
+            })
+            .filter(|unit| self.can_remove(unit))
+            .collect();
+        units.sort_by_key(|range| (range.start, range.end));
+        units.dedup();
+        Ok(units)
+    }
+}
+
+/// Collects the byte ranges of the items or statements in a file.
+struct UnitCollector<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+    pass: Pass,
+    units: Vec<Range<usize>>,
+}
+
+impl UnitCollector<'_> {
+    fn push(&mut self, span: proc_macro2::Span) {
+        let start: Option<usize> = byte_offset(self.source, &self.line_starts, span.start());
+        let end: Option<usize> = byte_offset(self.source, &self.line_starts, span.end());
+        if let (Some(start), Some(end)) = (start, end) {
+            if start < end {
+                self.units.push(start..end);
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for UnitCollector<'_> {
+    fn visit_item(&mut self, item: &'ast syn::Item) {
+        // The fixtures are binaries, so `main` always stays
+        let is_main: bool = matches!(item, syn::Item::Fn(item_fn) if item_fn.sig.ident == "main");
+        if self.pass == Pass::Items && !is_main {
+            self.push(item.span());
+        }
+        visit::visit_item(self, item);
+    }
+
+    fn visit_impl_item(&mut self, item: &'ast syn::ImplItem) {
+        if self.pass == Pass::Items {
+            self.push(item.span());
+        }
+        visit::visit_impl_item(self, item);
+    }
+
+    fn visit_trait_item(&mut self, item: &'ast syn::TraitItem) {
+        if self.pass == Pass::Items {
+            self.push(item.span());
+        }
+        visit::visit_trait_item(self, item);
+    }
+
+    fn visit_stmt(&mut self, stmt: &'ast syn::Stmt) {
+        if self.pass == Pass::Statements {
+            self.push(stmt.span());
+        }
+        visit::visit_stmt(self, stmt);
+    }
+}
+
+/// Widens `range` to cover its whole lines (including the final newline) if
+/// nothing but whitespace shares those lines with it.
+fn widen_to_lines(source: &str, range: &Range<usize>) -> Range<usize> {
+    let line_start: usize = source[..range.start].rfind('\n').map(|index| index + 1).unwrap_or(0);
+    let line_end: usize = source[range.end..]
+        .find('\n')
+        .map(|index| range.end + index + 1)
+        .unwrap_or(source.len());
+
+    if source[line_start..range.start].trim().is_empty() && source[range.end..line_end].trim().is_empty() {
+        line_start..line_end
+    } else {
+        range.clone()
+    }
+}
+
+/// Shrinks the program in `fixture` (a Cargo project) with delta debugging,
+/// removing items and then statements for as long as the selection still
+/// fails in the same way. Code straddling either end of the selection is kept,
+/// and the selection's offsets are moved along with the code before it, but
+/// code inside the selection can be removed too. The reduced program is
+/// written to `<out>/extract/input/<name>` as a fixture for the extract suite,
+/// with a selection manifest so that `test` picks it up.
+pub fn run_reduce(fixture: &Path, selection: &Selection, options: &ReduceOptions) -> io::Result<PathBuf> {
+    let start: Instant = Instant::now();
+    let manifest: String = fs::read_to_string(fixture.join("Cargo.toml"))?;
+    let source: String = fs::read_to_string(fixture.join("src").join("main.rs"))?;
+    let mut reducer: Reducer = Reducer {
+        manifest: &manifest,
+        fixture_name: &selection.fixture,
+        options,
+        scratch: tempfile::Builder::new().prefix("rem-reduce-").tempdir()?,
+        attempts: 0,
+    };
+
+    let mut current: Candidate = Candidate {
+        source,
+        start_idx: selection.start_idx,
+        end_idx: selection.end_idx,
+    };
+    let result: CaseResult = reducer.run(&current)?;
+    let signature: FailureSignature = FailureSignature::of(&result).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("{} passes, there is nothing to reduce", selection.name()))
+    })?;
+    println!("Reducing {} ({} bytes), failing with {}", selection.name(), current.source.len(), signature);
+    info!("Reducing {} ({} bytes), failing with {}", selection.name(), current.source.len(), signature);
+
+    // Removing statements can leave items unused and vice versa, so keep going
+    // until neither pass gets any further
+    loop {
+        let size: usize = current.source.len();
+        for pass in [Pass::Items, Pass::Statements] {
+            current = reducer.reduce_pass(current, pass, &signature)?;
+        }
+        if current.source.len() >= size {
+            break;
+        }
+    }
+
+    let dest: PathBuf = write_fixture(&current, &manifest, options)?;
+    println!("------------------------------------------------------------------");
+    println!(
+        "Reduced to {} bytes in {} attempts ({:.2}s), still failing with {}",
+        current.source.len(),
+        reducer.attempts,
+        start.elapsed().as_secs_f64(),
+        signature,
+    );
+    println!("Wrote {:?}, selecting {}..{}", dest, current.start_idx, current.end_idx);
+    println!("------------------------------------------------------------------");
+    info!("Reduced {} to {} bytes, written to {:?}", selection.name(), current.source.len(), dest);
+    Ok(dest)
+}
+
+struct Reducer<'a> {
+    manifest: &'a str,
+    fixture_name: &'a str,
+    options: &'a ReduceOptions,
+    scratch: TempDir,
+    attempts: usize,
+}
+
+impl Reducer<'_> {
+    /// One round of delta debugging over the items or statements of
+    /// `current`. The units are split into chunks, and removing each chunk is
+    /// tried in turn. When a removal keeps the failure, it is kept and the
+    /// chunks are made slightly larger again; when none do, the chunks are
+    /// halved, until they are single units.
+    fn reduce_pass(&mut self, mut current: Candidate, pass: Pass, signature: &FailureSignature) -> io::Result<Candidate> {
+        let mut units: Vec<Range<usize>> = match current.removable(pass) {
+            Ok(units) => units,
+            Err(e) => {
+                warn!("Reduced program no longer parses, stopping: {}", e);
+                return Ok(current);
+            }
+        };
+        let mut chunks: usize = 2.min(units.len());
+
+        while !units.is_empty() {
+            let chunk_size: usize = units.len().div_ceil(chunks);
+            let attempts: Vec<Candidate> = units
+                .chunks(chunk_size)
+                .map(|chunk| current.without(chunk))
+                .filter(|candidate| syn::parse_file(&candidate.source).is_ok())
+                .collect();
+
+            match self.first_matching(&attempts, signature)? {
+                Some(index) => {
+                    current = attempts[index].clone();
+                    println!("{}: {} bytes left after removing {}", "REDUCED".green(), current.source.len(), pass);
+                    // The offsets of the remaining units have moved, so
+                    // collect them again from the smaller program
+                    units = match current.removable(pass) {
+                        Ok(units) => units,
+                        Err(_) => break,
+                    };
+                    chunks = chunks.saturating_sub(1).max(2).min(units.len());
+                }
+                None if chunks >= units.len() => break,
+                None => chunks = (chunks * 2).min(units.len()),
+            }
+        }
+
+        Ok(current)
+    }
+
+    /// Tries the candidates in order, `jobs` at a time, and returns the index
+    /// of the first one that still fails with `signature`.
+    fn first_matching(
+        &mut self,
+        attempts: &[Candidate],
+        signature: &FailureSignature,
+    ) -> io::Result<Option<usize>> {
+        let batch_size: usize = self.options.jobs.max(1);
+        for (batch_index, batch) in attempts.chunks(batch_size).enumerate() {
+            let mut projects: Vec<(PathBuf, Selection)> = Vec::new();
+            for (offset, candidate) in batch.iter().enumerate() {
+                let project: PathBuf = self
+                    .scratch
+                    .path()
+                    .join(format!("{}", self.attempts + offset))
+                    .join(self.fixture_name);
+                write_project(&project, self.manifest, &candidate.source)?;
+                projects.push((project, Selection {
+                    fixture: self.fixture_name.to_string(),
+                    start_idx: candidate.start_idx,
+                    end_idx: candidate.end_idx,
+                }));
+            }
+            self.attempts += batch.len();
+
+            let results: Vec<CaseResult> = run_parallel(&projects, self.options.jobs, |(project, selection)| {
+                run_selection(project, selection, self.options.timeout)
+            });
+            for (project, _) in &projects {
+                if let Some(parent) = project.parent() {
+                    let _ = fs::remove_dir_all(parent);
+                }
+            }
+
+            if let Some(offset) = results
+                .iter()
+                .position(|result| FailureSignature::of(result).as_ref() == Some(signature))
+            {
+                return Ok(Some(batch_index * batch_size + offset));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Runs the unreduced program, to find the signature to preserve.
+    fn run(&mut self, candidate: &Candidate) -> io::Result<CaseResult> {
+        let project: PathBuf = self.scratch.path().join("original").join(self.fixture_name);
+        write_project(&project, self.manifest, &candidate.source)?;
+        self.attempts += 1;
+        Ok(run_selection(&project, &Selection {
+            fixture: self.fixture_name.to_string(),
+            start_idx: candidate.start_idx,
+            end_idx: candidate.end_idx,
+        }, self.options.timeout))
+    }
+}
+
+fn write_project(project: &Path, manifest: &str, source: &str) -> io::Result<()> {
+    fs::create_dir_all(project.join("src"))?;
+    fs::write(project.join("Cargo.toml"), manifest)?;
+    fs::write(project.join("src").join("main.rs"), source)
+}
+
+/// Writes the reduced program and its selection to
+/// `<out>/extract/input/<name>`, renaming its package to match the folder as
+/// the extract fixtures do.
+fn write_fixture(candidate: &Candidate, manifest: &str, options: &ReduceOptions) -> io::Result<PathBuf> {
+    let dest: PathBuf = options.out.join("extract/input").join(&options.name);
+    write_project(&dest, &rename_package(manifest, &options.name), &candidate.source)?;
+    SelectionManifest::new(candidate.start_idx, candidate.end_idx).write(&dest)?;
+    Ok(dest)
+}
+
+/// Renames the package in `manifest` to `name`.
+pub fn rename_package(manifest: &str, name: &str) -> String {
+    let mut in_package: bool = false;
+    let mut renamed: bool = false;
+    let mut lines: Vec<String> = Vec::new();
+    for line in manifest.lines() {
+        let trimmed: &str = line.trim();
+        if trimmed.starts_with('[') {
+            in_package = trimmed == "[package]";
+        } else if in_package && !renamed {
+            if let Some((key, _)) = trimmed.split_once('=') {
+                if key.trim() == "name" {
+                    lines.push(format!("name = \"{}\"", name));
+                    renamed = true;
+                    continue;
+                }
+            }
+        }
+        lines.push(line.to_string());
+    }
+    lines.join("\n") + "\n"
+}
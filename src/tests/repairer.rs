@@ -1,168 +1,347 @@
-use rem_repairer::{
-    common::{
-        RepairResult,
-        RepairSystem,
-    },
-    repair_lifetime_loosest_bound_first,
-    repair_lifetime_simple,
-    repair_lifetime_tightest_bound_first,
-    repair_rustfix,
-};
-use colored::Colorize;
-use std::{
-    io,
-    iter::zip,
-    path::{
-        Path,
-        PathBuf
-    },
-    time::SystemTime
-
-};
-
-use log::{
-    info,
-    error,
-};
-
-use crate::tests::utils::{
-    cleanup_new_files,
-    list_files_in_dir,
-};
-
-pub fn test(path: PathBuf) -> Result<u8, io::Error> {
-
-    let folder_path: String = match path.to_str() {
-        Some(path_str) => path_str.to_string(),
-        None => {
-            error!("Failed to convert path to string: {:?}", path);
-            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid path"));
-        }
-    };
-
-    // The path provided must be a directory
-    if !path.is_dir() {
-        error!("The path provided is not a directory: {}", folder_path);
-        return Err(io::Error::new(io::ErrorKind::NotFound, "Path is not a directory"));
-    }
-
-    info!("Running tests from directory {}{}", folder_path, "repairer");
-
-    let file_names: Vec<&str> = vec![
-        "borrow",
-        "in_out_lifetimes",
-        "lifetime_bounds",
-        "in_out_lifetimes_original_extract",
-        "lifetime_bounds_not_enough_annotations",
-        "in_out_lifetimes_wide_bounds",
-        "bounds_elider",
-    ];
-
-    let function_sigs: Vec<(&str, &str)> = vec![
-        ("", ""),
-        (
-            "bar_extracted",
-            "fn bar_extracted(x_ref: &i32, z: &i32, y: &i32) -> &i32",
-        ),
-        ("bar_extracted", "fn bar_extracted(p: &mut & i32, x: & i32)"),
-        ("", ""),
-        ("", ""),
-        (
-            "bar_extracted",
-            "fn bar_extracted<'a, 'b, 'c>(x_ref: &'a i32, z: &'b i32, y: &'c i32) -> &'a i32 {",
-        ),
-        ("bar", ""),
-    ];
-
-    let repair_systems: Vec<&dyn RepairSystem> = vec![
-        &repair_lifetime_simple::Repairer {},
-        &repair_rustfix::Repairer {},
-        &repair_lifetime_tightest_bound_first::Repairer {},
-        &repair_lifetime_loosest_bound_first::Repairer {},
-    ];
-
-    // For deleting the large amount of files that REM creates.
-    let current_dir: &Path = Path::new("./");
-    let initial_files: Vec<String> = list_files_in_dir(current_dir)?;
-
-    let mut total_failed_tests: u8 = 0;
-
-    for (file_name, (fn_name, _)) in zip(file_names, function_sigs) {
-        for repair_system in repair_systems.iter() {
-
-            // Format input file name correctly
-            let input_file_name = format!("{}/repairer/input/{}.rs", folder_path, file_name);
-
-            // Format output file name correctly
-            let output_file_name = format!("{}/repairer/output/{}_{}.rs", folder_path, file_name, repair_system.name());
-
-            // Perform the repair operation
-            let success = print_repair_stat(
-                repair_system,
-                input_file_name.as_str(),
-                output_file_name.as_str(),
-                fn_name,
-            )?;
-
-            if !success {
-                total_failed_tests += 1;
-            }
-        }
-        println!("------------------------------------------------------------------");
-    }
-
-    let _ = cleanup_new_files(initial_files, current_dir)?;
-
-    Ok(total_failed_tests)
-}
-
-fn print_repair_stat(
-    repair_system: &&dyn RepairSystem,
-    file_name: &str,
-    new_file_name: &str,
-    fn_name: &str,
-) -> Result<bool, io::Error> {
-    println!("\n\n{}: {}", file_name, fn_name);
-    let now = SystemTime::now();
-    let RepairResult { success, .. } =
-        repair_system.repair_function(file_name, new_file_name, fn_name);
-    let time_elapsed = now.elapsed().unwrap();
-    println!(
-        "{}: {} refactored {} in {:#?}",
-        (if success {
-            format!("PASSED").green()
-        } else {
-            format!("FAILED").red()
-        }),
-        repair_system.name(),
-        file_name,
-        time_elapsed
-    );
-    Ok(success)
-}
-
-
-// fn print_repair_stat_project(
-//     repair_system: &&dyn RepairSystem,
-//     src_path: &str,
-//     manifest_path: &str,
-//     fn_name: &str,
-// ) -> bool {
-//     println!("\n\n{}: {}", src_path, fn_name);
-//     let now = SystemTime::now();
-//     let RepairResult { success, .. } =
-//         repair_system.repair_project(src_path, manifest_path, fn_name);
-//     let time_elapsed = now.elapsed().unwrap();
-//     println!(
-//         "{}: {} refactored {} in {:#?}",
-//         (if success {
-//             format!("PASSED").green()
-//         } else {
-//             format!("FAILED").red()
-//         }),
-//         repair_system.name(),
-//         src_path,
-//         time_elapsed
-//     );
-//     success
-// }
+use rem_repairer::{
+    common::{
+        RepairResult,
+        RepairSystem,
+    },
+    repair_lifetime_loosest_bound_first,
+    repair_lifetime_simple,
+    repair_lifetime_tightest_bound_first,
+    repair_rustfix,
+};
+use colored::Colorize;
+use std::{
+    fs::read_to_string,
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
+
+use log::{
+    error,
+    info,
+};
+
+use crate::tests::ast::{
+    find_signature,
+    normalise_signature,
+    parse_signature,
+    signatures_match,
+};
+use crate::tests::report::{
+    CaseResult,
+    SuiteReport,
+};
+
+use lazy_static::lazy_static;
+
+struct RepairTest<'a> {
+    pub file_name: &'a str, // Just the name of the file. It is assumed the file is in ./repairer/input/{file_name}.rs
+    pub fn_name: &'a str,
+    pub signature: &'a str, // The signature we expect the repaired function to have. Empty if there isn't one. Must differ from the input's
+}
+
+impl RepairTest<'_> {
+    pub fn new<'a>(file_name: &'a str, fn_name: &'a str, signature: &'a str) -> RepairTest<'a> {
+        RepairTest {
+            file_name,
+            fn_name,
+            signature,
+        }
+    }
+}
+
+/// Every repair system, in the order they are tried.
+pub fn repair_systems() -> Vec<&'static dyn RepairSystem> {
+    vec![
+        &repair_lifetime_simple::Repairer {},
+        &repair_rustfix::Repairer {},
+        &repair_lifetime_tightest_bound_first::Repairer {},
+        &repair_lifetime_loosest_bound_first::Repairer {},
+    ]
+}
+
+fn case_name(test: &RepairTest, repair_system: &dyn RepairSystem) -> String {
+    format!("{}_{}", test.file_name, repair_system.name())
+}
+
+/// Finds the test file and repair system a case name was built from.
+fn find_case(case: &str) -> Option<(&'static RepairTest<'static>, &'static dyn RepairSystem)> {
+    for test in TEST_FILES.iter() {
+        for repair_system in repair_systems() {
+            if case_name(test, repair_system) == case {
+                return Some((test, repair_system));
+            }
+        }
+    }
+    None
+}
+
+/// The input file of a case, which is shared by every repair system.
+pub fn input_file(path: &Path, case: &str) -> Option<PathBuf> {
+    let (test, _) = find_case(case)?;
+    Some(path.join("repairer/input").join(test.file_name).with_extension("rs"))
+}
+
+/// Every test file is run against every repair system, giving one case per
+/// pair.
+pub fn cases(_path: &Path) -> Result<Vec<String>, io::Error> {
+    let mut cases: Vec<String> = Vec::new();
+    for test in TEST_FILES.iter() {
+        for repair_system in repair_systems() {
+            cases.push(case_name(test, repair_system));
+        }
+    }
+    Ok(cases)
+}
+
+pub fn run_case(path: &Path, case: &str) -> Result<CaseResult, io::Error> {
+    let folder_path: String = match path.to_str() {
+        Some(path_str) => path_str.to_string(),
+        None => {
+            error!("Failed to convert path to string: {:?}", path);
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid path"));
+        }
+    };
+
+    let Some((test, repair_system)) = find_case(case) else {
+        error!("No repairer test named {}", case);
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("No repairer test named {}", case)));
+    };
+
+    // Format input file name correctly
+    let input_file_name = format!("{}/repairer/input/{}.rs", folder_path, test.file_name);
+
+    // Format output file name correctly
+    let output_file_name = format!("{}/repairer/output/{}_{}.rs", folder_path, test.file_name, repair_system.name());
+
+    // An expected signature that is the input's own would only pass if the
+    // repairer did nothing, so the table needs fixing before the case means
+    // anything
+    if let Some(message) = unrepaired_signature(&input_file_name, test) {
+        println!("{}: {}", "FAILED".red(), message);
+        let mut result: CaseResult = CaseResult::new(case, Duration::ZERO);
+        result.add_check("signature", false, Some(message));
+        return Ok(result);
+    }
+
+    // Perform the repair operation
+    let mut result: CaseResult = print_repair_stat(
+        &repair_system,
+        case,
+        input_file_name.as_str(),
+        output_file_name.as_str(),
+        test.fn_name,
+    )?;
+
+    // Only a successful repair has a signature worth checking
+    if result.passed() && !test.signature.is_empty() {
+        let (matches, message) = check_signature(&output_file_name, test);
+        println!(
+            "{}: signature of {}",
+            (if matches {
+                format!("PASSED").green()
+            } else {
+                format!("FAILED").red()
+            }),
+            test.fn_name,
+        );
+        result.add_check("signature", matches, message);
+    }
+
+    Ok(result)
+}
+
+/// Checks that the repaired function in `output_file_name` has the signature
+/// the test expects, modulo lifetime renaming. Returns whether it matched, and
+/// why not if it didn't.
+fn check_signature(output_file_name: &str, test: &RepairTest) -> (bool, Option<String>) {
+    let expected = match parse_signature(test.signature) {
+        Ok(expected) => expected,
+        Err(e) => {
+            error!("Failed to parse expected signature {:?}: {}", test.signature, e);
+            return (false, Some(format!("Invalid expected signature {:?}: {}", test.signature, e)));
+        }
+    };
+
+    let output: String = match read_to_string(output_file_name) {
+        Ok(output) => output,
+        Err(e) => return (false, Some(format!("Failed to read {}: {}", output_file_name, e))),
+    };
+
+    match find_signature(&output, test.fn_name) {
+        Ok(Some(actual)) if signatures_match(&actual, &expected) => (true, None),
+        Ok(Some(actual)) => (false, Some(format!(
+            "Expected `{}`, found `{}`",
+            normalise_signature(&expected),
+            normalise_signature(&actual),
+        ))),
+        Ok(None) => (false, Some(format!("No function named {} in {}", test.fn_name, output_file_name))),
+        Err(e) => (false, Some(format!("Failed to parse {}: {}", output_file_name, e))),
+    }
+}
+
+/// Whether the signature a test expects is the one its function already has
+/// before the repair, which means the test table is out of date. Returns why if
+/// it is.
+fn unrepaired_signature(input_file_name: &str, test: &RepairTest) -> Option<String> {
+    if test.signature.is_empty() {
+        return None;
+    }
+    let expected = parse_signature(test.signature).ok()?;
+    let input: String = read_to_string(input_file_name).ok()?;
+    let unrepaired = find_signature(&input, test.fn_name).ok()??;
+    signatures_match(&unrepaired, &expected).then(|| format!(
+        "The expected signature of {} is the same as in {}, so it doesn't check the repair",
+        test.fn_name,
+        input_file_name,
+    ))
+}
+
+/// Prints, for each repair system, how many of its repairs succeeded and how
+/// many of those produced the expected signature.
+pub fn print_summary(report: &SuiteReport, _elapsed: Duration) {
+    println!("------------------------------------------------------------------");
+    for repair_system in repair_systems() {
+        let mut repaired: usize = 0;
+        let mut signatures_checked: usize = 0;
+        let mut signatures_matched: usize = 0;
+        let mut total: usize = 0;
+
+        for case in &report.cases {
+            let Some((_, system)) = find_case(&case.name) else {
+                continue;
+            };
+            if system.name() != repair_system.name() {
+                continue;
+            }
+
+            total += 1;
+            for check in &case.checks {
+                match check.name.as_str() {
+                    "repair" if check.passed => repaired += 1,
+                    "signature" => {
+                        signatures_checked += 1;
+                        if check.passed {
+                            signatures_matched += 1;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let summary: String = format!(
+            "{}: repaired {}/{}, expected signature {}/{}",
+            repair_system.name(),
+            repaired,
+            total,
+            signatures_matched,
+            signatures_checked,
+        );
+        println!("{}", summary);
+        info!("{}", summary);
+    }
+    println!("------------------------------------------------------------------");
+}
+
+fn print_repair_stat(
+    repair_system: &&dyn RepairSystem,
+    case_name: &str,
+    file_name: &str,
+    new_file_name: &str,
+    fn_name: &str,
+) -> Result<CaseResult, io::Error> {
+    println!("\n\n{}: {}", file_name, fn_name);
+    let now = SystemTime::now();
+    let RepairResult { success, .. } =
+        repair_system.repair_function(file_name, new_file_name, fn_name);
+    let time_elapsed = now.elapsed().unwrap();
+    println!(
+        "{}: {} refactored {} in {:#?}",
+        (if success {
+            format!("PASSED").green()
+        } else {
+            format!("FAILED").red()
+        }),
+        repair_system.name(),
+        file_name,
+        time_elapsed
+    );
+
+    let mut case: CaseResult = CaseResult::new(case_name, time_elapsed);
+    case.add_check("repair", success, None);
+    Ok(case)
+}
+
+
+// fn print_repair_stat_project(
+//     repair_system: &&dyn RepairSystem,
+//     src_path: &str,
+//     manifest_path: &str,
+//     fn_name: &str,
+// ) -> bool {
+//     println!("\n\n{}: {}", src_path, fn_name);
+//     let now = SystemTime::now();
+//     let RepairResult { success, .. } =
+//         repair_system.repair_project(src_path, manifest_path, fn_name);
+//     let time_elapsed = now.elapsed().unwrap();
+//     println!(
+//         "{}: {} refactored {} in {:#?}",
+//         (if success {
+//             format!("PASSED").green()
+//         } else {
+//             format!("FAILED").red()
+//         }),
+//         repair_system.name(),
+//         src_path,
+//         time_elapsed
+//     );
+//     success
+// }
+
+
+lazy_static! {
+    static ref TEST_FILES: Vec<RepairTest<'static>> = vec![
+        RepairTest::new(
+            "borrow",
+            "",
+            "",
+        ),
+        RepairTest::new(
+            "in_out_lifetimes",
+            "bar_extracted",
+            "fn bar_extracted<'a>(z: &'a i32, y: &'a mut i32) -> &'a i32",
+        ),
+        RepairTest::new(
+            "lifetime_bounds",
+            "bar_extracted",
+            "fn bar_extracted<'a, 'b>(p: &'a mut &'b i32, x: &'b i32)",
+        ),
+        RepairTest::new(
+            "in_out_lifetimes_original_extract",
+            "",
+            "",
+        ),
+        RepairTest::new(
+            "lifetime_bounds_not_enough_annotations",
+            "",
+            "",
+        ),
+        RepairTest::new(
+            "in_out_lifetimes_wide_bounds",
+            "bar_extracted",
+            "fn bar_extracted<'a, 'b, 'c>(x_ref: &'a i32, z: &'b i32, y: &'c i32) -> &'a i32 where 'b: 'a, 'c: 'a",
+        ),
+        RepairTest::new(
+            "bounds_elider",
+            "bar",
+            "",
+        ),
+    ];
+}
@@ -0,0 +1,364 @@
+use std::{
+    error::Error,
+    ffi::OsString,
+    fs::{self, read_to_string},
+    io,
+    ops::RangeInclusive,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use colored::Colorize;
+
+use log::{
+    error,
+    info,
+};
+
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+};
+
+use tempfile::TempDir;
+
+use crate::tests::{
+    ast::{
+        compare_ast,
+        find_fn,
+        CompareOptions,
+    },
+    diff::explain_mismatch,
+    fuzz::line_starts,
+    harness::{
+        run_in_child,
+        run_parallel,
+        write_result_catching_panics,
+    },
+    pipeline::{
+        add_stage_checks,
+        run_pipeline_on_file,
+        scratch_dir,
+        SETUP,
+        STAGES,
+    },
+    report::{
+        CaseResult,
+        SuiteReport,
+        Tally,
+    },
+};
+use crate::utils::{
+    checkout_from_git,
+    read_from_git,
+};
+
+/// Options for replaying mined refactorings.
+/// - repos: Folder holding a local clone of every repository in the cases file
+/// - jobs: The number of cases that are replayed at the same time
+/// - timeout: How long a single case may run before it is killed
+/// - compare: How REM's function is compared with the developer's
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayOptions {
+    pub repos: PathBuf,
+    pub jobs: usize,
+    pub timeout: Duration,
+    pub compare: CompareOptions,
+}
+
+/// An extract-method refactoring found in a repository's history, as recorded
+/// by `scraping/0_scraper.py`. Lines `start_line..=end_line` of `pre_file` at
+/// `pre_sha` were moved into a new function called `fn_name`, which is in
+/// `post_file` at `post_sha`. Any other columns are ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MinedCase {
+    pub repo: String,
+    pub pre_sha: String,
+    pub post_sha: String,
+    pub pre_file: String,
+    pub post_file: String,
+    #[serde(rename = "fn")]
+    pub fn_name: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    #[serde(deserialize_with = "flag")]
+    pub generic: bool,
+    #[serde(rename = "async", deserialize_with = "flag")]
+    pub is_async: bool,
+}
+
+impl MinedCase {
+    /// The name the case is reported under, e.g. `deno_1a2b3c4_parse_args`.
+    pub fn case_name(&self) -> String {
+        let short_sha: &str = self.pre_sha.get(..7).unwrap_or(&self.pre_sha);
+        format!("{}_{}_{}", self.repo.replace('/', "_"), short_sha, self.fn_name)
+    }
+
+    /// The `generic` and `async` flags, as tags for the report.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        if self.generic {
+            tags.push("generic".to_string());
+        }
+        if self.is_async {
+            tags.push("async".to_string());
+        }
+        tags
+    }
+}
+
+/// The scraper is written in Python, so its flags are `True` and `False`.
+/// Cases that were saved as JSON (e.g. in an imported fixture's
+/// `selection.json`) have plain booleans instead.
+fn flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bool(bool),
+        Number(u64),
+        Text(String),
+    }
+
+    match Flag::deserialize(deserializer)? {
+        Flag::Bool(value) => Ok(value),
+        Flag::Number(value) => Ok(value != 0),
+        Flag::Text(value) => match value.trim().to_lowercase().as_str() {
+            "true" | "1" => Ok(true),
+            "false" | "0" | "" => Ok(false),
+            _ => Err(serde::de::Error::custom(format!("Expected true or false, found {:?}", value))),
+        },
+    }
+}
+
+/// Reads every case from a CSV file with the scraper's columns.
+pub fn read_cases(path: &Path) -> io::Result<Vec<MinedCase>> {
+    let mut reader = csv::Reader::from_path(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    reader
+        .deserialize::<MinedCase>()
+        .map(|row| row.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)))
+        .collect()
+}
+
+/// Replays every case in `cases_path`, each in its own checkout of its
+/// repository's local clone and its own `rem-cli replay-case` process, and
+/// prints the success rates.
+pub fn run_replay(cases_path: &Path, options: &ReplayOptions) -> io::Result<SuiteReport> {
+    let cases: Vec<MinedCase> = read_cases(cases_path)?;
+    let repos: PathBuf = options.repos.canonicalize()?;
+    info!("Replaying {} cases from {:?} against {:?}", cases.len(), cases_path, repos);
+    let start: Instant = Instant::now();
+
+    let mut report: SuiteReport = SuiteReport::new("replay");
+    report.cases = run_parallel(&cases, options.jobs, |mined| {
+        let name: String = mined.case_name();
+        let mut result: CaseResult = match replay_in_clone(mined, &name, &repos, options) {
+            Ok(result) => result,
+            Err(e) => {
+                error!("Failed to replay {}: {}", name, e);
+                println!("{}: {}: {}", "FAILED".red(), name, e);
+                let mut result: CaseResult = CaseResult::new(&name, Duration::ZERO);
+                result.add_check("checkout", false, Some(e.to_string()));
+                result
+            }
+        };
+        result.tags = mined.tags();
+        result
+    });
+
+    print_summary(&cases, &report, start.elapsed());
+    Ok(report)
+}
+
+/// Checks the local clone of the case's repository out at `pre_sha` into a
+/// temporary directory, writes the developer's version of the file next to it,
+/// and runs the case on the checkout. The local clone itself is only read.
+fn replay_in_clone(mined: &MinedCase, name: &str, repos: &Path, options: &ReplayOptions) -> Result<CaseResult, Box<dyn Error>> {
+    let local: PathBuf = local_clone(repos, &mined.repo)?;
+    let expected_code: String = read_from_git(&local, &mined.post_sha, &mined.post_file)?;
+
+    // The checkout is left on disk until the case is done, so that the
+    // extraction can load the rest of the crate
+    let clone: TempDir = checkout_from_git(&local, &mined.pre_sha)?;
+    let expected_dir: TempDir = tempfile::Builder::new()
+        .prefix("rem-replay-")
+        .tempdir()?;
+    let expected_path: PathBuf = expected_dir.path().join("expected.rs");
+    fs::write(&expected_path, expected_code)?;
+
+    let mut args: Vec<OsString> = vec![
+        "replay-case".into(),
+        name.into(),
+        clone.path().join(&mined.pre_file).into(),
+        expected_path.into(),
+        mined.fn_name.clone().into(),
+        mined.start_line.to_string().into(),
+        mined.end_line.to_string().into(),
+    ];
+    args.extend(options.compare.to_args().into_iter().map(OsString::from));
+
+    Ok(run_in_child(&args, name, options.timeout)?)
+}
+
+/// The local clone of `repo`. The scraper records repositories by name, but
+/// `owner/name` is accepted as well.
+pub fn local_clone(repos: &Path, repo: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let name: &str = repo.rsplit('/').next().unwrap_or(repo);
+    [repos.join(repo), repos.join(name)]
+        .into_iter()
+        .find(|path| path.is_dir())
+        .ok_or_else(|| format!("No clone of {} in {:?}", repo, repos).into())
+}
+
+/// Entry point for the hidden `replay-case` subcommand. Runs the pipeline on
+/// `lines` of `input` (counting from 1), extracting them into a function called
+/// `fn_name`, and compares the result with `expected`. Writes the result
+/// to `result_path`.
+pub fn run_replay_case(
+    name: &str,
+    input: &Path,
+    expected: &Path,
+    fn_name: &str,
+    lines: RangeInclusive<usize>,
+    compare: &CompareOptions,
+    result_path: &Path,
+) -> io::Result<()> {
+    write_result_catching_panics(name, result_path, || {
+        let source: String = read_to_string(input)?;
+        let Some((start_idx, end_idx)) = line_range(&source, *lines.start(), *lines.end()) else {
+            let mut case: CaseResult = CaseResult::new(name, Duration::ZERO);
+            let message: String = format!("Lines {:?} are not in {:?}", lines, input);
+            case.add_check("extraction", false, Some(message));
+            return Ok(case);
+        };
+
+        let start: Instant = Instant::now();
+        let result: Result<String, (&str, String)> = scratch_dir(name)
+            .map_err(|e| (SETUP, e.to_string()))
+            .and_then(|scratch_dir| run_pipeline_on_file(input, fn_name, &scratch_dir, start_idx, end_idx, &mut Vec::new()));
+        let mut case: CaseResult = CaseResult::new(name, start.elapsed());
+
+        if let Some(output_code) = add_stage_checks(&mut case, result) {
+            let expected_code: String = read_to_string(expected)?;
+            let (matches, message) = compare_fn(&output_code, &expected_code, fn_name, compare);
+            case.add_check("fn", matches, message);
+            let (matches, message) = match compare_ast(&output_code, &expected_code, compare) {
+                Ok(true) => (true, None),
+                Ok(false) => (false, Some(explain_mismatch(&output_code, &expected_code, compare, "the developer's file"))),
+                Err(e) => (false, Some(e)),
+            };
+            case.add_check("file", matches, message);
+        }
+
+        let status: String = match case.first_failure() {
+            Some(check) => format!("FAILED at {}", check.name).red().to_string(),
+            None => "PASSED".green().to_string(),
+        };
+        println!("{}: {} in {:.2}s", status, name, case.duration.as_secs_f64());
+        Ok(case)
+    })
+}
+
+/// The byte offsets of lines `start_line..=end_line` (counting from 1), without
+/// the indentation before them or the line break after them.
+pub fn line_range(source: &str, start_line: usize, end_line: usize) -> Option<(u32, u32)> {
+    let line_starts: Vec<usize> = line_starts(source);
+    if start_line == 0 || start_line > end_line || end_line > line_starts.len() {
+        return None;
+    }
+
+    let first: usize = line_starts[start_line - 1];
+    let last: usize = line_starts.get(end_line).copied().unwrap_or(source.len());
+    let lines: &str = &source[first..last];
+    let start: usize = first + lines.len() - lines.trim_start().len();
+    let end: usize = first + lines.trim_end().len();
+    (start < end).then_some((start as u32, end as u32))
+}
+
+/// Compares the function REM extracted with the one the developer wrote.
+/// Returns whether they match, and why not if they don't.
+fn compare_fn(output: &str, expected: &str, fn_name: &str, compare: &CompareOptions) -> (bool, Option<String>) {
+    let output_fn: String = match find_fn(output, fn_name) {
+        Ok(Some(output_fn)) => output_fn,
+        Ok(None) => return (false, Some(format!("REM's output has no function named {}", fn_name))),
+        Err(e) => return (false, Some(format!("Failed to parse REM's output: {}", e))),
+    };
+    let expected_fn: String = match find_fn(expected, fn_name) {
+        Ok(Some(expected_fn)) => expected_fn,
+        Ok(None) => return (false, Some(format!("The developer's file has no function named {}", fn_name))),
+        Err(e) => return (false, Some(format!("Failed to parse the developer's file: {}", e))),
+    };
+
+    match compare_ast(&output_fn, &expected_fn, compare) {
+        Ok(true) => (true, None),
+        Ok(false) => (false, Some(explain_mismatch(&output_fn, &expected_fn, compare, &format!("the developer's {}", fn_name)))),
+        Err(e) => (false, Some(e)),
+    }
+}
+
+/// A group of cases in the summary, and which cases are in it.
+type Group = (&'static str, fn(&MinedCase) -> bool);
+
+/// Prints how many cases made it through every stage, how many extracted the
+/// same function as the developer, and how many produced the developer's whole
+/// file, for all cases and broken down by the `generic` and `async` flags.
+pub fn print_summary(cases: &[MinedCase], report: &SuiteReport, elapsed: Duration) {
+    let groups: [Group; 5] = [
+        ("all", |_| true),
+        ("generic", |mined| mined.generic),
+        ("not generic", |mined| !mined.generic),
+        ("async", |mined| mined.is_async),
+        ("not async", |mined| !mined.is_async),
+    ];
+
+    println!("------------------------------------------------------------------");
+    println!("{:<12} {:>16} {:>16} {:>16}", "cases", "refactored", "fn matches", "file matches");
+    for (group, includes) in groups {
+        let mut refactored: Tally = Tally::default();
+        let mut fn_matches: Tally = Tally::default();
+        let mut file_matches: Tally = Tally::default();
+
+        for (mined, case) in cases.iter().zip(&report.cases) {
+            if !includes(mined) {
+                continue;
+            }
+            let passed = |checks: &[&str]| -> bool {
+                checks.iter().all(|name| case.checks.iter().any(|check| check.name == *name && check.passed))
+            };
+            for (tally, checks) in [
+                (&mut refactored, &STAGES[..]),
+                (&mut fn_matches, &["fn"][..]),
+                (&mut file_matches, &["file"][..]),
+            ] {
+                tally.total += 1;
+                if passed(checks) {
+                    tally.passed += 1;
+                }
+            }
+        }
+
+        println!(
+            "{:<12} {:>16} {:>16} {:>16}",
+            group,
+            refactored.to_string(),
+            fn_matches.to_string(),
+            file_matches.to_string(),
+        );
+        info!(
+            "Replay ({}): refactored {}, fn matches {}, file matches {}",
+            group,
+            refactored,
+            fn_matches,
+            file_matches,
+        );
+    }
+    println!("------------------------------------------------------------------");
+    println!("Replayed {} cases in {:.2}s", report.cases.len(), elapsed.as_secs_f64());
+    println!("------------------------------------------------------------------");
+}
@@ -0,0 +1,597 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs,
+    io,
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+};
+
+use crate::tests::{
+    table::{
+        Column,
+        Table,
+    },
+};
+
+/// The columns of a CSV or LaTeX report of a `test` or `eval` run. Each row is
+/// the pass rate of either the whole case (`case`) or one of its checks, over
+/// the cases in a suite with a tag (or `all` of them). Latencies are of whole
+/// cases, so they are only on `case` rows.
+const RESULT_COLUMNS: [Column; 9] = [
+    Column::text("suite", "Suite"),
+    Column::text("tag", "Tag"),
+    Column::text("check", "Check"),
+    Column::number("passed", "Passed"),
+    Column::number("total", "Total"),
+    Column::number("rate", "Rate (\\%)"),
+    Column::number("median_ms", "Median (ms)"),
+    Column::number("p95_ms", "p95 (ms)"),
+    Column::number("max_ms", "Max (ms)"),
+];
+
+/// The final state of a single test case. `TimedOut` and `Panicked` mean the
+/// case never finished, so none of its checks can be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseOutcome {
+    Passed,
+    Failed,
+    TimedOut,
+    Panicked,
+}
+
+/// A single named check performed as part of a test case, e.g. "extraction"
+/// or "ast". A case passes only if all of its checks pass. A skipped check
+/// couldn't be performed (its message says why), so it neither passes nor
+/// fails the case, and isn't counted in pass rates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub passed: bool,
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skipped: bool,
+}
+
+/// The result of running one test case from one of the suites.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseResult {
+    pub name: String,
+    pub outcome: CaseOutcome,
+    #[serde(rename = "time", serialize_with = "serialize_duration", deserialize_with = "deserialize_duration")]
+    pub duration: Duration,
+    pub checks: Vec<CheckResult>,
+    /// The language features the case exercises, see `tags::Tag`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// All of the case results from a single suite (extract, controller, borrower,
+/// repairer).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuiteReport {
+    pub name: String,
+    pub cases: Vec<CaseResult>,
+}
+
+/// The combined results of a `test` or `test-github` run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TestReport {
+    pub suites: Vec<SuiteReport>,
+}
+
+/// How often one check passed, out of the cases that ran it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Tally {
+    pub passed: usize,
+    pub total: usize,
+}
+
+/// Latencies, in milliseconds, e.g. of one stage on one fixture in a benchmark,
+/// or of the cases in a suite.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub samples: Vec<f64>,
+    pub min: f64,
+    pub median: f64,
+    pub mean: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+/// The pass rates of the cases in one suite with one tag: of the whole case,
+/// and of each check (i.e. stage) the cases ran.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CapabilityRow {
+    pub suite: String,
+    pub tag: String,
+    pub cases: Tally,
+    pub checks: BTreeMap<String, Tally>,
+}
+
+/// The output formats a report can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Junit,
+    Json,
+    Csv,
+    Latex,
+}
+
+/// A report that can be written to a file in (some of) the `ReportFormat`s.
+pub trait WriteReport {
+    /// The contents of the report in `format`.
+    fn render(&self, format: ReportFormat) -> io::Result<String>;
+
+    fn write(&self, target: &ReportTarget) -> io::Result<()> {
+        let contents: String = self.render(target.format)?;
+        if let Some(parent) = target.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        fs::write(&target.path, contents)
+    }
+}
+
+/// A report requested on the command line, in the form `<format>=<path>`,
+/// e.g. `junit=results.xml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportTarget {
+    pub format: ReportFormat,
+    pub path: PathBuf,
+}
+
+impl CaseResult {
+    pub fn new(name: &str, duration: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            outcome: CaseOutcome::Passed,
+            duration,
+            checks: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    /// A case that was killed after running for longer than the timeout.
+    pub fn timed_out(name: &str, duration: Duration) -> Self {
+        let mut case: CaseResult = Self::new(name, duration);
+        case.outcome = CaseOutcome::TimedOut;
+        case.checks.push(CheckResult {
+            name: "timeout".to_string(),
+            passed: false,
+            message: Some(format!("Timed out after {:.2}s", duration.as_secs_f64())),
+            skipped: false,
+        });
+        case
+    }
+
+    /// A case that panicked or crashed before it could produce a result.
+    pub fn panicked(name: &str, duration: Duration, message: String) -> Self {
+        let mut case: CaseResult = Self::new(name, duration);
+        case.outcome = CaseOutcome::Panicked;
+        case.checks.push(CheckResult {
+            name: "panic".to_string(),
+            passed: false,
+            message: Some(message),
+            skipped: false,
+        });
+        case
+    }
+
+    /// Records a check against this case. A failing check fails the case,
+    /// unless it has already timed out or panicked.
+    pub fn add_check(&mut self, name: &str, passed: bool, message: Option<String>) {
+        if !passed && self.outcome == CaseOutcome::Passed {
+            self.outcome = CaseOutcome::Failed;
+        }
+        self.checks.push(CheckResult {
+            name: name.to_string(),
+            passed,
+            message,
+            skipped: false,
+        });
+    }
+
+    /// Records that a check couldn't be performed, and why.
+    pub fn skip_check(&mut self, name: &str, reason: String) {
+        self.checks.push(CheckResult {
+            name: name.to_string(),
+            passed: true,
+            message: Some(reason),
+            skipped: true,
+        });
+    }
+
+    /// The checks that couldn't be performed.
+    pub fn skipped_checks(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks.iter().filter(|check| check.skipped)
+    }
+
+    pub fn passed(&self) -> bool {
+        self.outcome == CaseOutcome::Passed
+    }
+
+    /// The first check that failed, in the order they were recorded.
+    pub fn first_failure(&self) -> Option<&CheckResult> {
+        self.checks.iter().find(|check| !check.passed)
+    }
+
+    /// A one line summary of every failed check, used as the failure message
+    /// in the JUnit report.
+    pub fn failure_message(&self) -> String {
+        self.checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| match &check.message {
+                Some(msg) => format!("{}: {}", check.name, msg),
+                None => format!("{} failed", check.name),
+            })
+            .collect::<Vec<String>>()
+            .join("; ")
+    }
+}
+
+impl SuiteReport {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            cases: Vec::new(),
+        }
+    }
+
+    /// The number of cases that did not pass, for any reason.
+    pub fn failed(&self) -> usize {
+        self.cases.iter().filter(|case| !case.passed()).count()
+    }
+
+    pub fn count(&self, outcome: CaseOutcome) -> usize {
+        self.cases.iter().filter(|case| case.outcome == outcome).count()
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.cases.iter().map(|case| case.duration).sum()
+    }
+
+    /// The names of every check run by the suite, in the order they were
+    /// first recorded.
+    pub fn check_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for check in self.cases.iter().flat_map(|case| &case.checks) {
+            if !names.contains(&check.name) {
+                names.push(check.name.clone());
+            }
+        }
+        names
+    }
+
+    /// One row per tag used by the suite's cases, in tag order.
+    pub fn capabilities(&self) -> Vec<CapabilityRow> {
+        let mut rows: BTreeMap<&str, CapabilityRow> = BTreeMap::new();
+        for case in &self.cases {
+            for tag in &case.tags {
+                let row: &mut CapabilityRow = rows.entry(tag).or_insert_with(|| CapabilityRow {
+                    suite: self.name.clone(),
+                    tag: tag.clone(),
+                    cases: Tally::default(),
+                    checks: BTreeMap::new(),
+                });
+                row.cases.add(case.passed());
+                for check in case.checks.iter().filter(|check| !check.skipped) {
+                    row.checks.entry(check.name.clone()).or_default().add(check.passed);
+                }
+            }
+        }
+        rows.into_values().collect()
+    }
+
+    /// The suite's rows in a CSV or LaTeX report (see `RESULT_COLUMNS`): every
+    /// case, then the cases with each tag in turn.
+    fn table_rows(&self) -> Vec<Vec<String>> {
+        let mut tags: Vec<&str> = self.cases.iter().flat_map(|case| case.tags.iter().map(String::as_str)).collect();
+        tags.sort();
+        tags.dedup();
+        let mut groups: Vec<(&str, Vec<&CaseResult>)> = vec![("all", self.cases.iter().collect())];
+        for tag in tags {
+            let cases: Vec<&CaseResult> = self.cases.iter().filter(|case| case.tags.iter().any(|t| t == tag)).collect();
+            groups.push((tag, cases));
+        }
+
+        let check_names: Vec<String> = self.check_names();
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for (tag, cases) in groups {
+            let mut tally: Tally = Tally::default();
+            for case in &cases {
+                tally.add(case.passed());
+            }
+            let durations: Vec<Duration> = cases.iter().map(|case| case.duration).collect();
+            let latency: Option<LatencyStats> = LatencyStats::from_samples(&durations);
+            rows.push(result_row(&self.name, tag, "case", tally, latency.as_ref()));
+
+            for check_name in &check_names {
+                let mut tally: Tally = Tally::default();
+                for check in cases.iter().flat_map(|case| &case.checks).filter(|check| &check.name == check_name && !check.skipped) {
+                    tally.add(check.passed);
+                }
+                if tally.total > 0 {
+                    rows.push(result_row(&self.name, tag, check_name, tally, None));
+                }
+            }
+        }
+        rows
+    }
+}
+
+/// A row of a CSV or LaTeX report of a `test` or `eval` run.
+fn result_row(suite: &str, tag: &str, check: &str, tally: Tally, latency: Option<&LatencyStats>) -> Vec<String> {
+    let millis = |value: Option<f64>| value.map(|value| format!("{:.3}", value)).unwrap_or_default();
+    vec![
+        suite.to_string(),
+        tag.to_string(),
+        check.to_string(),
+        tally.passed.to_string(),
+        tally.total.to_string(),
+        tally.rate().map(|rate| format!("{:.1}", rate)).unwrap_or_default(),
+        millis(latency.map(|latency| latency.median)),
+        millis(latency.map(|latency| latency.p95)),
+        millis(latency.map(|latency| latency.max)),
+    ]
+}
+
+impl Tally {
+    fn add(&mut self, passed: bool) {
+        self.total += 1;
+        if passed {
+            self.passed += 1;
+        }
+    }
+
+    /// The percentage that passed, or None if nothing was counted.
+    pub fn rate(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.passed as f64 * 100.0 / self.total as f64)
+    }
+}
+
+impl fmt::Display for Tally {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.total == 0 {
+            return write!(f, "-");
+        }
+        write!(f, "{}/{} ({:.0}%)", self.passed, self.total, self.passed as f64 * 100.0 / self.total as f64)
+    }
+}
+
+impl LatencyStats {
+    /// Returns None if there are no samples.
+    pub fn from_samples(samples: &[Duration]) -> Option<Self> {
+        Self::from_millis(samples.iter().map(|sample| sample.as_secs_f64() * 1000.0).collect())
+    }
+
+    /// Returns None if there are no samples.
+    pub fn from_millis(mut millis: Vec<f64>) -> Option<Self> {
+        if millis.is_empty() {
+            return None;
+        }
+        millis.sort_by(|a, b| a.total_cmp(b));
+
+        let len: usize = millis.len();
+        let median: f64 = if len % 2 == 0 {
+            (millis[len / 2 - 1] + millis[len / 2]) / 2.0
+        } else {
+            millis[len / 2]
+        };
+        // Nearest rank
+        let p95: f64 = millis[((len as f64 * 0.95).ceil() as usize).clamp(1, len) - 1];
+
+        Some(Self {
+            min: millis[0],
+            median,
+            mean: millis.iter().sum::<f64>() / len as f64,
+            p95,
+            max: millis[len - 1],
+            samples: millis,
+        })
+    }
+}
+
+impl TestReport {
+    /// The capability matrix of every suite.
+    pub fn capabilities(&self) -> Vec<CapabilityRow> {
+        self.suites.iter().flat_map(|suite| suite.capabilities()).collect()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.suites.iter().map(|suite| suite.failed()).sum()
+    }
+
+    pub fn count(&self, outcome: CaseOutcome) -> usize {
+        self.suites.iter().map(|suite| suite.count(outcome)).sum()
+    }
+
+    /// The pass rates and latencies of every suite, for a CSV or LaTeX report.
+    pub fn to_table(&self) -> Table {
+        let mut table: Table = Table::new(&RESULT_COLUMNS);
+        for row in self.suites.iter().flat_map(|suite| suite.table_rows()) {
+            table.push(row);
+        }
+        table
+    }
+
+    /// Renders the report as JUnit XML. Each suite becomes a `<testsuite>` and
+    /// each case a `<testcase>` with the suite name as its classname. Failed
+    /// checks are reported as `<failure>`s, while timeouts and panics are
+    /// reported as `<error>`s. A case's tags are listed as its `<properties>`.
+    pub fn to_junit(&self) -> String {
+        let total_tests: usize = self.suites.iter().map(|suite| suite.cases.len()).sum();
+        let total_time: Duration = self.suites.iter().map(|suite| suite.duration()).sum();
+
+        let mut xml: String = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites name=\"rem-cli\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+            total_tests,
+            self.count(CaseOutcome::Failed),
+            self.count(CaseOutcome::TimedOut) + self.count(CaseOutcome::Panicked),
+            total_time.as_secs_f64(),
+        ));
+
+        for suite in &self.suites {
+            xml.push_str(&format!(
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+                escape_xml(&suite.name),
+                suite.cases.len(),
+                suite.count(CaseOutcome::Failed),
+                suite.count(CaseOutcome::TimedOut) + suite.count(CaseOutcome::Panicked),
+                suite.duration().as_secs_f64(),
+            ));
+
+            for case in &suite.cases {
+                xml.push_str(&format!(
+                    "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                    escape_xml(&case.name),
+                    escape_xml(&suite.name),
+                    case.duration.as_secs_f64(),
+                ));
+
+                if case.passed() && case.tags.is_empty() {
+                    xml.push_str("/>\n");
+                    continue;
+                }
+                xml.push_str(">\n");
+
+                if !case.tags.is_empty() {
+                    xml.push_str("      <properties>\n");
+                    for tag in &case.tags {
+                        xml.push_str(&format!("        <property name=\"tag\" value=\"{}\"/>\n", escape_xml(tag)));
+                    }
+                    xml.push_str("      </properties>\n");
+                }
+
+                if case.passed() {
+                    xml.push_str("    </testcase>\n");
+                    continue;
+                }
+
+                let message: String = case.failure_message();
+                let (element, kind): (&str, &str) = match case.outcome {
+                    CaseOutcome::TimedOut => ("error", "timeout"),
+                    CaseOutcome::Panicked => ("error", "panic"),
+                    _ => ("failure", "failure"),
+                };
+                // Keep the attribute to one line, the full message goes in the
+                // body of the element.
+                let summary: &str = message.lines().next().unwrap_or_default();
+                xml.push_str(&format!(
+                    "      <{} type=\"{}\" message=\"{}\">{}</{}>\n",
+                    element,
+                    kind,
+                    escape_xml(summary),
+                    escape_xml(&message),
+                    element,
+                ));
+                xml.push_str("    </testcase>\n");
+            }
+
+            xml.push_str("  </testsuite>\n");
+        }
+
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+impl WriteReport for TestReport {
+    fn render(&self, format: ReportFormat) -> io::Result<String> {
+        match format {
+            ReportFormat::Junit => Ok(self.to_junit()),
+            ReportFormat::Json => serde_json::to_string_pretty(&JsonReport {
+                suites: &self.suites,
+                capabilities: self.capabilities(),
+            }).map_err(io::Error::other),
+            ReportFormat::Csv => self.to_table().to_csv(),
+            ReportFormat::Latex => Ok(self.to_table().to_latex()),
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "junit" | "xml" => Ok(ReportFormat::Junit),
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            "latex" | "tex" => Ok(ReportFormat::Latex),
+            other => Err(format!("Unknown report format '{}' (expected junit, json, csv or latex)", other)),
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportFormat::Junit => write!(f, "junit"),
+            ReportFormat::Json => write!(f, "json"),
+            ReportFormat::Csv => write!(f, "csv"),
+            ReportFormat::Latex => write!(f, "latex"),
+        }
+    }
+}
+
+impl FromStr for ReportTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, path) = match s.split_once('=') {
+            Some((format, path)) if !path.is_empty() => (format, path),
+            _ => return Err(format!("Expected <format>=<path>, got '{}'", s)),
+        };
+
+        Ok(ReportTarget {
+            format: format.parse()?,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+/// The layout of a JSON report, which also includes the capability matrix.
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    suites: &'a [SuiteReport],
+    capabilities: Vec<CapabilityRow>,
+}
+
+fn serialize_duration<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+fn deserialize_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let secs: f64 = f64::deserialize(deserializer)?;
+    Ok(Duration::from_secs_f64(secs.max(0.0)))
+}
+
+fn escape_xml(input: &str) -> String {
+    let mut escaped: String = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters (other than whitespace) are not valid XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {},
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,104 @@
+use std::io;
+
+use crate::messages::version::VERSION;
+
+/// A column of a CSV or LaTeX report.
+/// - key: The CSV header, which scripts rely on, so it shouldn't change
+/// - title: The LaTeX header, which is written as is
+/// - numeric: Right aligned in LaTeX
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    pub key: &'static str,
+    pub title: &'static str,
+    pub numeric: bool,
+}
+
+/// The results of a run as a table, to be written as CSV or as a LaTeX
+/// `tabular`. Every row has one cell per column, and empty cells are values
+/// that don't apply to the row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub columns: &'static [Column],
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Column {
+    pub const fn text(key: &'static str, title: &'static str) -> Self {
+        Self { key, title, numeric: false }
+    }
+
+    pub const fn number(key: &'static str, title: &'static str) -> Self {
+        Self { key, title, numeric: true }
+    }
+}
+
+impl Table {
+    pub fn new(columns: &'static [Column]) -> Self {
+        Self {
+            columns,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<String>) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+    }
+
+    pub fn to_csv(&self) -> io::Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(self.columns.iter().map(|column| column.key))?;
+        for row in &self.rows {
+            writer.write_record(row)?;
+        }
+        let bytes: Vec<u8> = writer.into_inner().map_err(|e| io::Error::other(e.to_string()))?;
+        String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Renders the table as a `tabular` to be `\input` into a paper, using the
+    /// `booktabs` rules. A rule separates rows with different first cells
+    /// (e.g. each suite), and empty cells are shown as a dash.
+    pub fn to_latex(&self) -> String {
+        let alignment: String = self.columns.iter().map(|column| if column.numeric { 'r' } else { 'l' }).collect();
+        let header: Vec<String> = self.columns.iter().map(|column| column.title.to_string()).collect();
+
+        let mut latex: String = format!("% Written by rem-cli {}, needs \\usepackage{{booktabs}}\n", VERSION);
+        latex.push_str(&format!("\\begin{{tabular}}{{{}}}\n\\toprule\n", alignment));
+        latex.push_str(&format!("{} \\\\\n\\midrule\n", header.join(" & ")));
+        let mut previous: Option<&str> = None;
+        for row in &self.rows {
+            let first: Option<&str> = row.first().map(String::as_str);
+            if previous.is_some() && previous != first {
+                latex.push_str("\\midrule\n");
+            }
+            previous = first;
+
+            let cells: Vec<String> = row
+                .iter()
+                .map(|cell| if cell.is_empty() { "--".to_string() } else { escape_latex(cell) })
+                .collect();
+            latex.push_str(&format!("{} \\\\\n", cells.join(" & ")));
+        }
+        latex.push_str("\\bottomrule\n\\end{tabular}\n");
+        latex
+    }
+}
+
+/// Escapes the characters LaTeX treats specially, e.g. the underscores in case
+/// names.
+fn escape_latex(text: &str) -> String {
+    let mut escaped: String = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
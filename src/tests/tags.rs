@@ -0,0 +1,331 @@
+use std::{
+    collections::{
+        BTreeSet,
+        HashSet,
+    },
+    fmt,
+    fs,
+    io,
+    ops::Range,
+    path::Path,
+};
+
+use syn::{
+    spanned::Spanned,
+    visit::{
+        self,
+        Visit,
+    },
+    BinOp,
+    Expr,
+    GenericParam,
+    Generics,
+};
+
+use crate::tests::fuzz::{
+    byte_offset,
+    line_starts,
+};
+
+/// A Rust feature a fixture exercises. Features of the code inside the
+/// selection (e.g. a `?` or a labelled break) and of the function the
+/// selection is in (e.g. being async, or a trait method) both count.
+/// - Loop: A loop, or a `break` or `continue`
+/// - MutRef: A `&mut`, or a change to a variable declared outside the
+///   selection, which the extracted function has to borrow mutably
+/// - Macro: A macro invocation in the selection, or around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Tag {
+    Loop,
+    LabelledBreak,
+    Return,
+    Try,
+    Async,
+    Generics,
+    Lifetimes,
+    TraitImpl,
+    MutRef,
+    Macro,
+}
+
+impl Tag {
+    pub const ALL: [Tag; 10] = [
+        Tag::Loop,
+        Tag::LabelledBreak,
+        Tag::Return,
+        Tag::Try,
+        Tag::Async,
+        Tag::Generics,
+        Tag::Lifetimes,
+        Tag::TraitImpl,
+        Tag::MutRef,
+        Tag::Macro,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tag::Loop => "loop",
+            Tag::LabelledBreak => "labelled_break",
+            Tag::Return => "return",
+            Tag::Try => "try",
+            Tag::Async => "async",
+            Tag::Generics => "generics",
+            Tag::Lifetimes => "lifetimes",
+            Tag::TraitImpl => "trait_impl",
+            Tag::MutRef => "mut_ref",
+            Tag::Macro => "macro",
+        }
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The features used by the selected code in `source`, and by the function it
+/// is in.
+pub fn tag_selection(source: &str, start_idx: u32, end_idx: u32) -> Result<BTreeSet<Tag>, syn::Error> {
+    tag(source, Some(start_idx as usize..end_idx as usize))
+}
+
+/// The features used anywhere in `source`, for fixtures that don't have a
+/// selection.
+pub fn tag_file(source: &str) -> Result<BTreeSet<Tag>, syn::Error> {
+    tag(source, None)
+}
+
+/// Reads and tags a whole file. A file that can't be read or parsed has no
+/// tags.
+pub fn tag_path(path: &Path) -> io::Result<BTreeSet<Tag>> {
+    let source: String = fs::read_to_string(path)?;
+    tag_file(&source).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn tag(source: &str, selection: Option<Range<usize>>) -> Result<BTreeSet<Tag>, syn::Error> {
+    let file: syn::File = syn::parse_file(source)?;
+    let mut tagger: Tagger = Tagger {
+        source,
+        line_starts: line_starts(source),
+        selection,
+        in_trait_impl: false,
+        impl_generics: Vec::new(),
+        inner_bindings: HashSet::new(),
+        outer_mut_bindings: HashSet::new(),
+        tags: BTreeSet::new(),
+    };
+    tagger.visit_file(&file);
+    Ok(tagger.tags)
+}
+
+struct Tagger<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+    selection: Option<Range<usize>>,
+    in_trait_impl: bool,
+    impl_generics: Vec<&'a Generics>,
+    /// Names bound inside the selection
+    inner_bindings: HashSet<String>,
+    /// Names bound mutably outside the selection (including `&mut self`)
+    outer_mut_bindings: HashSet<String>,
+    tags: BTreeSet<Tag>,
+}
+
+impl Tagger<'_> {
+    fn range(&self, span: proc_macro2::Span) -> Option<Range<usize>> {
+        let start: usize = byte_offset(self.source, &self.line_starts, span.start())?;
+        let end: usize = byte_offset(self.source, &self.line_starts, span.end())?;
+        Some(start..end)
+    }
+
+    /// Whether a node is part of the selected code. Everything is, when
+    /// tagging a whole file.
+    fn selected(&self, span: proc_macro2::Span) -> bool {
+        match (&self.selection, self.range(span)) {
+            (None, _) => true,
+            (Some(selection), Some(range)) => range.start >= selection.start && range.end <= selection.end,
+            (Some(_), None) => false,
+        }
+    }
+
+    /// Whether a function contains the selection, so that its own features
+    /// count. Every function does, when tagging a whole file.
+    fn encloses_selection(&self, span: proc_macro2::Span) -> bool {
+        match (&self.selection, self.range(span)) {
+            (None, _) => true,
+            (Some(selection), Some(range)) => range.start <= selection.start && range.end >= selection.end,
+            (Some(_), None) => false,
+        }
+    }
+
+    /// Whether changing `place` changes a variable declared outside the
+    /// selection. When tagging a whole file, no variable is.
+    fn mutates_outer(&self, place: &Expr, declared_mut: bool) -> bool {
+        if self.selection.is_none() {
+            return false;
+        }
+        match root_ident(place) {
+            Some(name) if self.inner_bindings.contains(&name) => false,
+            Some(name) => !declared_mut || self.outer_mut_bindings.contains(&name),
+            None => false,
+        }
+    }
+
+    fn tag_generics(&mut self, generics: &Generics) {
+        for param in &generics.params {
+            match param {
+                GenericParam::Lifetime(_) => self.tags.insert(Tag::Lifetimes),
+                GenericParam::Type(_) | GenericParam::Const(_) => self.tags.insert(Tag::Generics),
+            };
+        }
+    }
+
+    fn tag_fn(&mut self, span: proc_macro2::Span, sig: &syn::Signature) {
+        if !self.encloses_selection(span) {
+            return;
+        }
+        if sig.asyncness.is_some() {
+            self.tags.insert(Tag::Async);
+        }
+        if self.in_trait_impl {
+            self.tags.insert(Tag::TraitImpl);
+        }
+        self.tag_generics(&sig.generics);
+        for generics in self.impl_generics.clone() {
+            self.tag_generics(generics);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Tagger<'ast> {
+    fn visit_item_impl(&mut self, item: &'ast syn::ItemImpl) {
+        let in_trait_impl: bool = self.in_trait_impl;
+        self.in_trait_impl = item.trait_.is_some();
+        self.impl_generics.push(&item.generics);
+        visit::visit_item_impl(self, item);
+        self.impl_generics.pop();
+        self.in_trait_impl = in_trait_impl;
+    }
+
+    fn visit_item_fn(&mut self, item: &'ast syn::ItemFn) {
+        // A free function inside a method body is not part of the impl
+        let in_trait_impl: bool = std::mem::replace(&mut self.in_trait_impl, false);
+        let impl_generics: Vec<&Generics> = std::mem::take(&mut self.impl_generics);
+        self.tag_fn(item.span(), &item.sig);
+        visit::visit_item_fn(self, item);
+        self.impl_generics = impl_generics;
+        self.in_trait_impl = in_trait_impl;
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast syn::ImplItemFn) {
+        self.tag_fn(item.span(), &item.sig);
+        visit::visit_impl_item_fn(self, item);
+    }
+
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        if self.selected(expr.span()) {
+            match expr {
+                Expr::Loop(_) | Expr::While(_) | Expr::ForLoop(_) => {
+                    self.tags.insert(Tag::Loop);
+                },
+                Expr::Break(syn::ExprBreak { label, .. }) => {
+                    self.tags.insert(Tag::Loop);
+                    if label.is_some() {
+                        self.tags.insert(Tag::LabelledBreak);
+                    }
+                },
+                Expr::Continue(syn::ExprContinue { label, .. }) => {
+                    self.tags.insert(Tag::Loop);
+                    if label.is_some() {
+                        self.tags.insert(Tag::LabelledBreak);
+                    }
+                },
+                Expr::Return(_) => {
+                    self.tags.insert(Tag::Return);
+                },
+                Expr::Try(_) => {
+                    self.tags.insert(Tag::Try);
+                },
+                Expr::Await(_) | Expr::Async(_) => {
+                    self.tags.insert(Tag::Async);
+                },
+                Expr::Reference(syn::ExprReference { mutability: Some(_), .. }) => {
+                    self.tags.insert(Tag::MutRef);
+                },
+                Expr::Assign(assign) if self.mutates_outer(&assign.left, false) => {
+                    self.tags.insert(Tag::MutRef);
+                },
+                Expr::Binary(binary) if is_compound_assign(&binary.op) && self.mutates_outer(&binary.left, false) => {
+                    self.tags.insert(Tag::MutRef);
+                },
+                // Without types, a method call only counts if the receiver
+                // was declared `mut`
+                Expr::MethodCall(call) if self.mutates_outer(&call.receiver, true) => {
+                    self.tags.insert(Tag::MutRef);
+                },
+                _ => {},
+            }
+        }
+        visit::visit_expr(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        if self.selected(mac.span()) || self.encloses_selection(mac.span()) {
+            self.tags.insert(Tag::Macro);
+        }
+        visit::visit_macro(self, mac);
+    }
+
+    fn visit_pat_ident(&mut self, pat: &'ast syn::PatIdent) {
+        if self.selection.is_some() && self.selected(pat.span()) {
+            self.inner_bindings.insert(pat.ident.to_string());
+        } else if pat.mutability.is_some() {
+            self.outer_mut_bindings.insert(pat.ident.to_string());
+        }
+        visit::visit_pat_ident(self, pat);
+    }
+
+    fn visit_receiver(&mut self, receiver: &'ast syn::Receiver) {
+        if receiver.mutability.is_some() {
+            self.outer_mut_bindings.insert("self".to_string());
+        }
+        visit::visit_receiver(self, receiver);
+    }
+
+    fn visit_type_reference(&mut self, ty: &'ast syn::TypeReference) {
+        if ty.mutability.is_some() && self.selected(ty.span()) {
+            self.tags.insert(Tag::MutRef);
+        }
+        visit::visit_type_reference(self, ty);
+    }
+}
+
+/// The variable a place expression such as `a.b[0]` or `*a` refers to.
+fn root_ident(place: &Expr) -> Option<String> {
+    match place {
+        Expr::Path(path) => path.path.get_ident().map(|ident| ident.to_string()),
+        Expr::Field(field) => root_ident(&field.base),
+        Expr::Index(index) => root_ident(&index.expr),
+        Expr::Paren(paren) => root_ident(&paren.expr),
+        Expr::Unary(unary) => root_ident(&unary.expr),
+        Expr::MethodCall(call) => root_ident(&call.receiver),
+        _ => None,
+    }
+}
+
+fn is_compound_assign(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::AddAssign(_)
+            | BinOp::SubAssign(_)
+            | BinOp::MulAssign(_)
+            | BinOp::DivAssign(_)
+            | BinOp::RemAssign(_)
+            | BinOp::BitXorAssign(_)
+            | BinOp::BitAndAssign(_)
+            | BinOp::BitOrAssign(_)
+            | BinOp::ShlAssign(_)
+            | BinOp::ShrAssign(_)
+    )
+}
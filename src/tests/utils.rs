@@ -1,41 +1,100 @@
-use std::{
-    fs, io, path::Path, process::Command
-};
-
-pub fn list_files_in_dir(dir: &Path) -> io::Result<Vec<String>> {
-    let mut files = Vec::new();
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        if entry.path().is_file() {
-            files.push(entry.file_name().to_string_lossy().to_string());
-        }
-    }
-    Ok(files)
-}
-
-pub fn cleanup_new_files(initial_files: Vec<String>, current_dir: &Path) -> io::Result<()> {
-    let final_files = list_files_in_dir(current_dir)?;
-
-    // Determine which files are new
-    let new_files: Vec<String> = final_files
-        .into_iter()
-        .filter(|file| !initial_files.contains(file))
-        .collect();
-
-    // Delete new files
-    for file in new_files {
-        let file_path = current_dir.join(&file);
-        fs::remove_file(file_path)?;
-    }
-
-    Ok(())
-}
-
-pub fn compile_file(file_name: &str, args: &Vec<&str>) -> Command {
-    let mut compile = Command::new("rustc");
-    for arg in args {
-        compile.arg(arg);
-    }
-    compile.arg(file_name);
-    compile
-}
\ No newline at end of file
+use std::{
+    fs,
+    io::{
+        self,
+        Read,
+    },
+    path::Path,
+    process::{
+        Child,
+        Command,
+        ExitStatus,
+    },
+    thread::{
+        self,
+        JoinHandle,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+/// How often a running child process is checked for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+pub fn list_files_in_dir(dir: &Path) -> io::Result<Vec<String>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.path().is_file() {
+            files.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    Ok(files)
+}
+
+pub fn compile_file(file_name: &str, args: &Vec<&str>) -> Command {
+    let mut compile = Command::new("rustc");
+    for arg in args {
+        compile.arg(arg);
+    }
+    compile.arg(file_name);
+    compile
+}
+
+/// Starts the command in a process group of its own (on Unix), so that
+/// `wait_with_timeout` can kill whatever it spawns along with it, e.g. the test
+/// binaries `cargo test` runs.
+pub fn in_own_process_group(command: &mut Command) -> &mut Command {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    command
+}
+
+/// Waits for the child to exit. Returns `None` if it had to be killed because
+/// it ran for longer than `timeout`. If it was started with
+/// `in_own_process_group`, the rest of its process group is killed too.
+pub fn wait_with_timeout(child: &mut Child, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+    let start: Instant = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+
+        if start.elapsed() >= timeout {
+            kill_process_group(child);
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Kills the process group the child leads, if it leads one. Otherwise there
+/// is no group with its id, and nothing happens.
+#[cfg(unix)]
+fn kill_process_group(child: &Child) {
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: kill only sends a signal, and a negative pid restricts it to
+        // the group with that id
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_child: &Child) {}
+
+pub fn read_pipe<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buffer: Vec<u8> = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        String::from_utf8_lossy(&buffer).to_string()
+    })
+}
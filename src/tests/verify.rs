@@ -0,0 +1,349 @@
+use std::{
+    fs,
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+    process::{
+        Child,
+        Command,
+        Output,
+        Stdio,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use crate::tests::utils::{
+    in_own_process_group,
+    read_pipe,
+    wait_with_timeout,
+};
+
+/// How long a compiled fixture may run before it is killed.
+pub const RUN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A copy of one of the fixture Cargo projects, with its `src/main.rs`
+/// replaced. Used to build and run the code before and after a refactoring
+/// without touching the fixture itself.
+pub struct ScratchProject {
+    pub dir: PathBuf,
+    pub bin_name: String,
+}
+
+/// The result of `cargo build` on a scratch project.
+pub struct BuildOutput {
+    pub success: bool,
+    pub stderr: String,
+}
+
+/// The result of running a built scratch project.
+/// - status: The exit code, `None` if the process timed out or was killed by a
+///   signal
+/// - timeout: How long it was allowed to run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOutput {
+    pub status: Option<i32>,
+    pub timed_out: bool,
+    pub timeout: Duration,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl ScratchProject {
+    /// Copies the manifest of `fixture_dir` into `dest`, and writes
+    /// `main_rs` as its `src/main.rs`. The copied manifest is made its own
+    /// workspace so that it never gets picked up by an enclosing one.
+    pub fn new(fixture_dir: &Path, main_rs: &str, dest: &Path) -> io::Result<Self> {
+        let manifest: String = fs::read_to_string(fixture_dir.join("Cargo.toml"))?;
+        let bin_name: String = package_name(&manifest).ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("No package name in {:?}", fixture_dir.join("Cargo.toml")),
+        ))?;
+
+        fs::create_dir_all(dest.join("src"))?;
+        let manifest: String = if manifest.lines().any(|line| line.trim() == "[workspace]") {
+            manifest
+        } else {
+            format!("{}\n[workspace]\n", manifest)
+        };
+        fs::write(dest.join("Cargo.toml"), manifest)?;
+        fs::write(dest.join("src").join("main.rs"), main_rs)?;
+
+        Ok(Self {
+            dir: dest.to_path_buf(),
+            bin_name,
+        })
+    }
+
+    /// A Cargo project that is already on disk, such as a copy of a real crate,
+    /// used as it is.
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        let manifest: String = fs::read_to_string(dir.join("Cargo.toml"))?;
+        let bin_name: String = package_name(&manifest).ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("No package name in {:?}", dir.join("Cargo.toml")),
+        ))?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            bin_name,
+        })
+    }
+
+    pub fn build(&self, target_dir: &Path) -> io::Result<BuildOutput> {
+        let output: Output = self.cargo(&["build"], target_dir).output()?;
+        Ok(BuildOutput {
+            success: output.status.success(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+
+    /// Runs the binary produced by `build`. The program is killed if it runs
+    /// for longer than `timeout`.
+    pub fn run(&self, target_dir: &Path, timeout: Duration) -> io::Result<RunOutput> {
+        let binary: PathBuf = target_dir
+            .join("debug")
+            .join(format!("{}{}", self.bin_name, std::env::consts::EXE_SUFFIX));
+
+        let mut command: Command = Command::new(binary);
+        command.current_dir(&self.dir).stdin(Stdio::null());
+        run_with_timeout(command, timeout)
+    }
+
+    /// Runs `cargo test` on the project. The tests are built first, so that
+    /// only running them counts towards `timeout`. Only the result of each test
+    /// is kept, sorted by name, as the rest of the output (timings, cargo's
+    /// progress) changes from run to run.
+    pub fn test(&self, target_dir: &Path, timeout: Duration) -> io::Result<RunOutput> {
+        let build: Output = self.cargo(&["test", "--no-run"], target_dir).output()?;
+        if !build.status.success() {
+            return Ok(RunOutput {
+                status: build.status.code(),
+                timed_out: false,
+                timeout,
+                stdout: String::new(),
+                stderr: String::new(),
+            });
+        }
+
+        let mut output: RunOutput = run_with_timeout(self.cargo(&["test"], target_dir), timeout)?;
+        output.stdout = test_results(&output.stdout);
+        output.stderr.clear();
+        Ok(output)
+    }
+
+    /// Runs the one test called `test` (its full path), under Miri if `miri`
+    /// is set. Unlike `test`, stderr is kept, as that is where build errors
+    /// and Miri's reports end up. Under Miri, threads still running when the
+    /// test ends are allowed, as the equivalence harness gives up on calls that
+    /// don't return rather than waiting for them.
+    pub fn run_test(&self, target_dir: &Path, test: &str, miri: bool, timeout: Duration) -> io::Result<RunOutput> {
+        let subcommand: &[&str] = if miri { &["miri", "test"] } else { &["test"] };
+        let mut cargo: Command = self.cargo(subcommand, target_dir);
+        cargo.args(["--", test, "--exact"]);
+        if miri {
+            let flags: String = std::env::var("MIRIFLAGS").unwrap_or_default();
+            cargo.env("MIRIFLAGS", format!("{} -Zmiri-ignore-leaks", flags).trim_start());
+        }
+        run_with_timeout(cargo, timeout)
+    }
+
+    /// Runs `cargo check` on the project. Stderr holds the diagnostics.
+    pub fn check(&self, target_dir: &Path, timeout: Duration) -> io::Result<RunOutput> {
+        run_with_timeout(self.cargo(&["check"], target_dir), timeout)
+    }
+
+    /// Whether `cargo miri` is installed for the toolchain the project is
+    /// built with.
+    pub fn miri_available(&self) -> bool {
+        Command::new("cargo")
+            .args(["miri", "--version"])
+            .current_dir(&self.dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    fn cargo(&self, subcommand: &[&str], target_dir: &Path) -> Command {
+        let mut cargo: Command = Command::new("cargo");
+        cargo
+            .args(subcommand)
+            .arg("--offline")
+            .arg("--message-format=short")
+            .arg("--manifest-path")
+            .arg(self.dir.join("Cargo.toml"))
+            .arg("--target-dir")
+            .arg(target_dir)
+            .current_dir(&self.dir)
+            .stdin(Stdio::null());
+        cargo
+    }
+}
+
+impl RunOutput {
+    pub fn success(&self) -> bool {
+        !self.timed_out && self.status == Some(0)
+    }
+
+    /// Compares the observable behaviour of two runs: exit status, stdout and
+    /// stderr. Panic locations are ignored, as extracting a function moves
+    /// code around. Returns a description of every difference, or `None` if
+    /// the runs behaved the same. Two runs that both timed out can't be
+    /// compared, which counts as a difference.
+    pub fn diverges_from(&self, other: &RunOutput) -> Option<String> {
+        if self.timed_out && other.timed_out {
+            return Some("both timed out, so their behaviour can't be compared".to_string());
+        }
+
+        let mut differences: Vec<String> = Vec::new();
+
+        if self.timed_out != other.timed_out || self.status != other.status {
+            differences.push(format!(
+                "exit status {} vs {}",
+                describe_status(self),
+                describe_status(other),
+            ));
+        }
+
+        if self.stdout != other.stdout {
+            differences.push(format!(
+                "stdout {:?} vs {:?}",
+                self.stdout,
+                other.stdout,
+            ));
+        }
+
+        let self_stderr: String = strip_source_locations(&self.stderr);
+        let other_stderr: String = strip_source_locations(&other.stderr);
+        if self_stderr != other_stderr {
+            differences.push(format!(
+                "stderr {:?} vs {:?}",
+                self_stderr,
+                other_stderr,
+            ));
+        }
+
+        if differences.is_empty() {
+            None
+        } else {
+            Some(differences.join("; "))
+        }
+    }
+
+    /// A short description of how the program exited, for check messages.
+    pub fn describe(&self) -> String {
+        match (self.timed_out, self.status) {
+            (true, _) => format!("timed out after {:?}", self.timeout),
+            (false, Some(code)) => format!("exited with code {}: {}", code, self.stderr.trim()),
+            (false, None) => format!("killed by a signal: {}", self.stderr.trim()),
+        }
+    }
+}
+
+/// Runs the command in its own process group, so that when it times out
+/// anything it started (the test binary under `cargo test`, Miri under
+/// `cargo miri`) is killed with it.
+fn run_with_timeout(mut command: Command, timeout: Duration) -> io::Result<RunOutput> {
+    let mut child: Child = in_own_process_group(&mut command)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout: JoinHandle<String> = read_pipe(child.stdout.take());
+    let stderr: JoinHandle<String> = read_pipe(child.stderr.take());
+
+    let Some(status) = wait_with_timeout(&mut child, timeout)? else {
+        // Anything that got out of the process group may still hold the pipes
+        // open, so the reader threads are left to finish on their own.
+        return Ok(RunOutput {
+            status: None,
+            timed_out: true,
+            timeout,
+            stdout: String::new(),
+            stderr: String::new(),
+        });
+    };
+    Ok(RunOutput {
+        status: status.code(),
+        timed_out: false,
+        timeout,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// The `test <name> ... <result>` line of each test in libtest's output,
+/// sorted, as tests run in parallel. The summary line is left out, as it says
+/// how long the tests took.
+fn test_results(stdout: &str) -> String {
+    let mut results: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("test ") && line.contains(" ... "))
+        .collect();
+    results.sort_unstable();
+    results.join("\n")
+}
+
+fn describe_status(output: &RunOutput) -> String {
+    match (output.timed_out, output.status) {
+        (true, _) => "timed out".to_string(),
+        (false, Some(code)) => code.to_string(),
+        (false, None) => "signal".to_string(),
+    }
+}
+
+/// Replaces `src/main.rs:<line>:<col>` (as printed in panic messages) with
+/// just `src/main.rs`, so moving code around doesn't count as a change in
+/// behaviour.
+fn strip_source_locations(stderr: &str) -> String {
+    const FILE: &str = "src/main.rs";
+    let mut stripped: String = String::with_capacity(stderr.len());
+    let mut rest: &str = stderr;
+
+    while let Some(position) = rest.find(FILE) {
+        let (before, after) = rest.split_at(position + FILE.len());
+        stripped.push_str(before);
+
+        // Skip over any number of `:<digits>` groups
+        let mut after: &str = after;
+        while let Some(digits) = after.strip_prefix(':') {
+            let end: usize = digits
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(digits.len());
+            if end == 0 {
+                break;
+            }
+            after = &digits[end..];
+        }
+        rest = after;
+    }
+
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Pulls the package name out of the `[package]` table of a manifest.
+fn package_name(manifest: &str) -> Option<String> {
+    let mut in_package: bool = false;
+    for line in manifest.lines() {
+        let line: &str = line.trim();
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+            continue;
+        }
+
+        if !in_package {
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            if key.trim() == "name" {
+                return Some(value.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+    None
+}
@@ -1,287 +1,353 @@
-use std::{
-    path::PathBuf,
-    io,
-    fs,
-    error::Error,
-    process::exit,
-    time::SystemTime,
-    env,
-};
-use git2::Repository;
-
-use log::{
-    info,
-    error,
-};
-
-use crate::tests::{
-    extract,
-    controller,
-    borrower,
-    repairer,
-};
-
-use ra_ap_vfs::AbsPathBuf;
-
-use camino::Utf8PathBuf;
-
-use crate::error::TestFailed;
-
-// ====================================================================
-//                 Main Program Helper Functions
-// ====================================================================
-
-// Given an `&str` path to a file, returns the `AbsPathBuf` to the file.
-/// The `AbsPathBuf` is used by the `ra_ap` crates to represent file paths.
-/// If the input is not an absolute path, it resulves the path relative to the
-/// current directory.
-/// Will also canonicalize the path before returning it.
-pub fn convert_to_abs_path_buf(path: &str) -> Result<AbsPathBuf, Utf8PathBuf> {
-    if path.is_empty() {
-        return Err(Utf8PathBuf::from_path_buf(PathBuf::new()).unwrap());
-    }
-
-    // Check if the path is valid for a file system
-    if !path.is_ascii() {
-        return Err(Utf8PathBuf::from_path_buf(PathBuf::new()).unwrap());
-    }
-
-    // Attempt to convert it as-is (absolute path).
-    match AbsPathBuf::try_from(path) {
-        Ok(abs_path_buf) => Ok(abs_path_buf),
-        Err(_) => {
-            // Resolve non-absolute path to the current working directory.
-            let current_dir = env::current_dir().expect("Failed to get current directory");
-            let utf8_current_dir = Utf8PathBuf::from_path_buf(current_dir)
-                .expect("Failed to convert current directory to Utf8PathBuf");
-
-            // println!("Current dir: {:?}", utf8_current_dir);
-            // println!("Current path: {:?}", path);
-            let resolved_path = utf8_current_dir.join(path);
-
-            // Normalize the path to eliminate unnecessary components
-            let normalized_path = resolved_path.canonicalize().unwrap_or(resolved_path.clone().into());
-
-            // Create directories leading to the resolved path if they don't exist
-            if let Some(parent) = normalized_path.parent() {
-                fs::create_dir_all(parent).expect("Failed to create directories");
-            }
-
-            // Attempt to convert the normalized path to AbsPathBuf
-            let abs_path = AbsPathBuf::try_from(normalized_path.to_str().unwrap())
-                .map_err(|e| e); // Return the error if the resolved path is still invalid
-            // println!("Resolved path: {:?}", abs_path);
-
-            // If the abs_path as a string starts with either a \ or a ? (or some
-            // combination), strip it out
-
-            let abs_path_str: String = abs_path.unwrap().to_string();
-            let abs_path_str: String = abs_path_str
-                .replace(r"\\?\", "");
-
-            let new_abs_path = AbsPathBuf::try_from(abs_path_str.as_str())
-                .map_err(|e| e);
-
-            // println!("New abs path: {:?}", new_abs_path);
-            new_abs_path
-        }
-    }
-}
-
-
-// ====================================================================
-//                  Test Running Helper Functions
-// ====================================================================
-
-/// Downloads a repo from a URL.
-/// # Returns
-/// * The path to the git-repo it has cloned.
-pub fn get_from_git(repo_url: String) -> Result<PathBuf, Box<dyn Error>> {
-    // Define a path where the repo will be cloned
-    let path: PathBuf = PathBuf::from("tmp_test_src/");
-
-    // Clone the repository
-    Repository::clone(&repo_url, &path)?;
-
-    Ok(path)
-}
-
-/// Deletes a cloned repo from the user's HDD.
-pub fn delete_repo(path: PathBuf) -> Result<(), Box<dyn Error>> {
-    if path.is_dir() {
-        fs::remove_dir_all(&path)?;
-    } else {
-        // Return an error if the path is not a directory
-        let err_msg = format!("The specified path is not a directory: {:?}", path);
-        error!("{}", err_msg);
-        return Err(Box::new(std::io::Error::new(std::io::ErrorKind::NotFound, err_msg)));
-    }
-
-    Ok(())
-}
-
-/// Runs tests given a path to the test files.
-/// # Returns
-/// * `u8` The number of tests that failed. Returning 0 is ideal!
-pub fn run_tests(path: std::path::PathBuf) -> Result<u8, TestFailed> {
-    info!("Running tests from path: {:?}", path);
-
-    // Initialize the total number of failed tests
-    let mut total_failed_tests: u8 = 0;
-
-    // Run extract tests
-    let extract_failed: u8 = match extract::test(path.clone()) {
-        Ok(failed) => failed,
-        Err(e) => {
-            error!("Extract tests failed: {:?}", e);
-            return Err(TestFailed::ExtractFailed(e));
-        }
-    };
-    info!("Extract tests successful, {} tests failed", extract_failed);
-    total_failed_tests += extract_failed;
-
-    // Run controller tests
-    let controller_failed: u8 = match controller::test(path.clone()) {
-        Ok(failed) => failed,
-        Err(e) => {
-            error!("Controller tests failed: {:?}", e);
-            return Err(TestFailed::ControllerFailed(e));
-        }
-    };
-    info!("Controller tests successful, {} tests failed", controller_failed);
-    total_failed_tests += controller_failed;
-
-    let borrower_failed: u8 = match borrower::test(path.clone()) {
-        Ok(failed) => failed,
-        Err(e) => {
-            error!("Borrower tests failed: {:?}", e);
-            return Err(TestFailed::BorrowerFailed(e));
-        }
-    };
-    info!("Borrower tests successful, {} tests failed", borrower_failed);
-    total_failed_tests += borrower_failed;
-
-    // Run repairer tests
-    let repairer_failed: u8 = match repairer::test(path.clone()) {
-        Ok(failed) => failed,
-        Err(e) => {
-            error!("Repairer tests failed: {:?}", e);
-            return Err(TestFailed::RepairerFailed(e));
-        }
-    };
-    info!("Repairer tests succesful, {} tests failed", repairer_failed);
-    total_failed_tests += repairer_failed;
-
-    info!("All tests completed. Total failed tests: {}", total_failed_tests);
-    Ok(total_failed_tests)
-}
-
-/// Deletes a backup file or directory at the given path.
-pub fn delete_backup(backup_path: PathBuf) -> Result<(), io::Error> {
-    if backup_path.is_file() {
-        // Delete the file if it's a file
-        match fs::remove_file(&backup_path) {
-            Ok(_) => {
-                info!("Backup file deleted successfully: {:?}", backup_path);
-                Ok(())
-            }
-            Err(e) => {
-                error!("Failed to delete backup file: {:?}", e);
-                Err(e)
-            }
-        }
-    } else if backup_path.is_dir() {
-        // Delete the directory and its contents if it's a directory
-        match fs::remove_dir_all(&backup_path) {
-            Ok(_) => {
-                info!("Backup directory deleted successfully: {:?}", backup_path);
-                Ok(())
-            }
-            Err(e) => {
-                error!("Failed to delete backup directory: {:?}", e);
-                Err(e)
-            }
-        }
-    } else {
-        // If the path is neither a file nor a directory, return an error
-        let err_msg = format!("The specified backup path is neither a file nor a directory: {:?}", backup_path);
-        error!("{}", err_msg); // Output to logs and std err.
-        eprintln!("{}", err_msg);
-        Err(io::Error::new(io::ErrorKind::InvalidInput, err_msg))
-    }
-}
-
-pub fn handle_result(success: bool, run: &str, msg: &str) {
-    if success {
-        info!("Program {} was successful.", run);
-    } else {
-        error!("Program {} failed: {}", run, msg);
-        exit(1);
-    }
-}
-
-pub fn backup_file(original_path: PathBuf) -> Option<PathBuf> {
-    // Check if the path is a file; directories are not supported
-    if !original_path.is_file() {
-        let err_msg = "Path must be a file";
-        error!("{}: {:?}", err_msg, original_path);
-        panic!("{}", err_msg);
-    }
-
-    // Get the current timestamp
-    let now = SystemTime::now();
-    let timestamp = match now.duration_since(SystemTime::UNIX_EPOCH) {
-        Ok(duration) => duration.as_secs(),
-        Err(_) => {
-            let err_msg = "Failed to get timestamp";
-            error!("{}", err_msg);
-            panic!("{}", err_msg);
-        }
-    };
-
-    // Construct the backup path
-    let parent_dir = match original_path.parent() {
-        Some(parent) => parent,
-        None => {
-            let err_msg = "No parent directory found";
-            error!("{}", err_msg);
-            panic!("{}", err_msg);
-        }
-    };
-
-    // let file_name = match original_path.file_name() {
-    //     Some(name) => name.to_string_lossy(),
-    //     None => {
-    //         let err_msg = "No file name found";
-    //         error!("{}", err_msg);
-    //         panic!("{}", err_msg);
-    //     }
-    // };
-
-    let file_stem = match original_path.file_stem() {
-        Some(stem) => stem.to_string_lossy(),
-        None => {
-            let err_msg = "No file stem found";
-            error!("{}", err_msg);
-            panic!("{}", err_msg);
-        }
-    };
-
-    let extension = match original_path.extension() {
-        Some(ext) => ext.to_string_lossy(),
-        None => String::new().into(), // No extension
-    };
-
-    let backup_file_name = format!("{}_backup_{}.{}", file_stem, timestamp, extension);
-    let backup_path = parent_dir.join(backup_file_name);
-
-    // Attempt to copy the file
-    if let Err(e) = fs::copy(&original_path, &backup_path) {
-        error!("Failed to copy file from {:?} to {:?}: {}", original_path, backup_path, e);
-        panic!("Failed to copy file from {:?} to {:?}: {}", original_path, backup_path, e);
-    }
-
-    info!("Successfully backed up file from {:?} to {:?}", original_path, backup_path);
-    Some(backup_path)
-}
-
+use std::{
+    path::{
+        Path,
+        PathBuf,
+    },
+    io,
+    fs,
+    error::Error,
+    process::exit,
+    time::SystemTime,
+    env,
+};
+use git2::{
+    build::CheckoutBuilder,
+    Blob,
+    Commit,
+    Object,
+    Repository,
+    TreeEntry,
+};
+
+use tempfile::TempDir;
+
+use log::{
+    info,
+    error,
+};
+
+use crate::tests::{
+    harness::{
+        print_capabilities,
+        run_suite,
+        HarnessOptions,
+        Suite,
+    },
+    report::{
+        ReportTarget,
+        SuiteReport,
+        TestReport,
+        WriteReport,
+    },
+};
+
+use ra_ap_vfs::AbsPathBuf;
+
+use camino::Utf8PathBuf;
+
+use crate::error::TestFailed;
+
+// ====================================================================
+//                 Main Program Helper Functions
+// ====================================================================
+
+// Given an `&str` path to a file, returns the `AbsPathBuf` to the file.
+/// The `AbsPathBuf` is used by the `ra_ap` crates to represent file paths.
+/// If the input is not an absolute path, it resulves the path relative to the
+/// current directory.
+/// Will also canonicalize the path before returning it.
+pub fn convert_to_abs_path_buf(path: &str) -> Result<AbsPathBuf, Utf8PathBuf> {
+    if path.is_empty() {
+        return Err(Utf8PathBuf::from_path_buf(PathBuf::new()).unwrap());
+    }
+
+    // Check if the path is valid for a file system
+    if !path.is_ascii() {
+        return Err(Utf8PathBuf::from_path_buf(PathBuf::new()).unwrap());
+    }
+
+    // Attempt to convert it as-is (absolute path).
+    match AbsPathBuf::try_from(path) {
+        Ok(abs_path_buf) => Ok(abs_path_buf),
+        Err(_) => {
+            // Resolve non-absolute path to the current working directory.
+            let current_dir = env::current_dir().expect("Failed to get current directory");
+            let utf8_current_dir = Utf8PathBuf::from_path_buf(current_dir)
+                .expect("Failed to convert current directory to Utf8PathBuf");
+
+            // println!("Current dir: {:?}", utf8_current_dir);
+            // println!("Current path: {:?}", path);
+            let resolved_path = utf8_current_dir.join(path);
+
+            // Normalize the path to eliminate unnecessary components
+            let normalized_path = resolved_path.canonicalize().unwrap_or(resolved_path.clone().into());
+
+            // Create directories leading to the resolved path if they don't exist
+            if let Some(parent) = normalized_path.parent() {
+                fs::create_dir_all(parent).expect("Failed to create directories");
+            }
+
+            // Attempt to convert the normalized path to AbsPathBuf
+            let abs_path = AbsPathBuf::try_from(normalized_path.to_str().unwrap())
+                .map_err(|e| e); // Return the error if the resolved path is still invalid
+            // println!("Resolved path: {:?}", abs_path);
+
+            // If the abs_path as a string starts with either a \ or a ? (or some
+            // combination), strip it out
+
+            let abs_path_str: String = abs_path.unwrap().to_string();
+            let abs_path_str: String = abs_path_str
+                .replace(r"\\?\", "");
+
+            let new_abs_path = AbsPathBuf::try_from(abs_path_str.as_str())
+                .map_err(|e| e);
+
+            // println!("New abs path: {:?}", new_abs_path);
+            new_abs_path
+        }
+    }
+}
+
+
+// ====================================================================
+//                  Test Running Helper Functions
+// ====================================================================
+
+/// Clones a repository into a fresh temporary directory, so that concurrent
+/// runs never share a checkout. The directory is deleted when the returned
+/// `TempDir` is dropped or closed.
+/// - repo: A URL, a `file://` URL, or the path to a local (possibly bare)
+///   repository
+/// - rev: A branch, tag or commit to check out instead of the default branch
+pub fn get_from_git(repo: &str, rev: Option<&str>) -> Result<TempDir, Box<dyn Error>> {
+    // libgit2 wants local repositories as absolute paths
+    let local_path: &Path = Path::new(repo);
+    let repo_url: String = if local_path.exists() {
+        local_path.canonicalize()?.to_string_lossy().to_string()
+    } else {
+        repo.to_string()
+    };
+
+    let clone_dir: TempDir = tempfile::Builder::new()
+        .prefix("rem-test-src-")
+        .tempdir()?;
+    info!("Cloning {} into {:?}", repo_url, clone_dir.path());
+    let repository: Repository = Repository::clone(&repo_url, clone_dir.path())?;
+
+    if let Some(rev) = rev {
+        checkout_rev(&repository, rev)?;
+        info!("Checked out {}", rev);
+    }
+
+    Ok(clone_dir)
+}
+
+/// Checks out `rev` as a detached HEAD. Branches other than the default one
+/// only exist as remote branches in a fresh clone, so `origin/<rev>` is tried
+/// as well.
+fn checkout_rev(repository: &Repository, rev: &str) -> Result<(), git2::Error> {
+    let object: Object = match repository.revparse_single(rev) {
+        Ok(object) => object,
+        Err(e) => repository
+            .revparse_single(&format!("origin/{}", rev))
+            .map_err(|_| e)?,
+    };
+    let commit: Commit = object.peel_to_commit()?;
+    repository.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().force()))?;
+    repository.set_head_detached(commit.id())?;
+    Ok(())
+}
+
+/// Reads a file as it was at `rev` straight out of a repository's object
+/// database, without touching its working tree.
+pub fn read_from_git(repo: &Path, rev: &str, file: &str) -> Result<String, Box<dyn Error>> {
+    let repository: Repository = Repository::open(repo)?;
+    let commit: Commit = repository.revparse_single(rev)?.peel_to_commit()?;
+    let entry: TreeEntry = commit.tree()?.get_path(Path::new(file))?;
+    let blob: Blob = entry.to_object(&repository)?.peel_to_blob()?;
+    Ok(String::from_utf8(blob.content().to_vec())?)
+}
+
+/// Writes the files of a repository as they were at `rev` into a new temporary
+/// directory, straight out of its object database. Unlike `get_from_git`
+/// nothing is cloned, and the repository's own HEAD, index and working tree
+/// are left alone, so one clone can be checked out at many revisions (at the
+/// same time).
+pub fn checkout_from_git(repo: &Path, rev: &str) -> Result<TempDir, Box<dyn Error>> {
+    let repository: Repository = Repository::open(repo)?;
+    let commit: Commit = repository.revparse_single(rev)?.peel_to_commit()?;
+
+    let checkout_dir: TempDir = tempfile::Builder::new()
+        .prefix("rem-test-src-")
+        .tempdir()?;
+    info!("Checking out {} of {:?} into {:?}", rev, repo, checkout_dir.path());
+    repository.checkout_tree(
+        commit.as_object(),
+        Some(CheckoutBuilder::new()
+            .target_dir(checkout_dir.path())
+            .update_index(false)
+            .force()),
+    )?;
+    Ok(checkout_dir)
+}
+
+/// Runs tests given a path to the test files. Every case is run in its own
+/// scratch directory, so the current directory is left untouched.
+/// # Returns
+/// * `TestReport` The results of every suite. `TestReport::failed` gives the
+///   number of tests that failed. Returning 0 is ideal!
+pub fn run_tests(path: std::path::PathBuf, options: &HarnessOptions) -> Result<TestReport, TestFailed> {
+    info!("Running tests from path: {:?}", path);
+
+    let mut report: TestReport = TestReport::default();
+
+    // Run the extract, controller, borrower and repairer tests in turn
+    for suite in Suite::ALL {
+        let suite_report: SuiteReport = match run_suite(suite, &path, options) {
+            Ok(suite_report) => suite_report,
+            Err(e) => {
+                error!("{} tests failed: {:?}", suite, e);
+                return Err(suite.test_failed(e));
+            }
+        };
+        info!("{} tests successful, {} tests failed", suite, suite_report.failed());
+        report.suites.push(suite_report);
+    }
+
+    print_capabilities(&report);
+    info!("All tests completed. Total failed tests: {}", report.failed());
+    Ok(report)
+}
+
+/// Writes a test or benchmark report out to each of the requested report
+/// targets. Failing to write one report does not stop the others from being
+/// written.
+pub fn write_reports(report: &impl WriteReport, targets: &[ReportTarget]) -> Result<(), Box<dyn Error>> {
+    let mut failed_targets: Vec<String> = Vec::new();
+
+    for target in targets {
+        match report.write(target) {
+            Ok(_) => info!("Wrote {} report to {:?}", target.format, target.path),
+            Err(e) => {
+                error!("Failed to write {} report to {:?}: {}", target.format, target.path, e);
+                failed_targets.push(target.path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    if !failed_targets.is_empty() {
+        return Err(format!("Failed to write reports: {}", failed_targets.join(", ")).into());
+    }
+
+    Ok(())
+}
+
+/// Deletes a backup file or directory at the given path.
+pub fn delete_backup(backup_path: PathBuf) -> Result<(), io::Error> {
+    if backup_path.is_file() {
+        // Delete the file if it's a file
+        match fs::remove_file(&backup_path) {
+            Ok(_) => {
+                info!("Backup file deleted successfully: {:?}", backup_path);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to delete backup file: {:?}", e);
+                Err(e)
+            }
+        }
+    } else if backup_path.is_dir() {
+        // Delete the directory and its contents if it's a directory
+        match fs::remove_dir_all(&backup_path) {
+            Ok(_) => {
+                info!("Backup directory deleted successfully: {:?}", backup_path);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to delete backup directory: {:?}", e);
+                Err(e)
+            }
+        }
+    } else {
+        // If the path is neither a file nor a directory, return an error
+        let err_msg = format!("The specified backup path is neither a file nor a directory: {:?}", backup_path);
+        error!("{}", err_msg); // Output to logs and std err.
+        eprintln!("{}", err_msg);
+        Err(io::Error::new(io::ErrorKind::InvalidInput, err_msg))
+    }
+}
+
+pub fn handle_result(success: bool, run: &str, msg: &str) {
+    if success {
+        info!("Program {} was successful.", run);
+    } else {
+        error!("Program {} failed: {}", run, msg);
+        exit(1);
+    }
+}
+
+pub fn backup_file(original_path: PathBuf) -> Option<PathBuf> {
+    // Check if the path is a file; directories are not supported
+    if !original_path.is_file() {
+        let err_msg = "Path must be a file";
+        error!("{}: {:?}", err_msg, original_path);
+        panic!("{}", err_msg);
+    }
+
+    // Get the current timestamp
+    let now = SystemTime::now();
+    let timestamp = match now.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs(),
+        Err(_) => {
+            let err_msg = "Failed to get timestamp";
+            error!("{}", err_msg);
+            panic!("{}", err_msg);
+        }
+    };
+
+    // Construct the backup path
+    let parent_dir = match original_path.parent() {
+        Some(parent) => parent,
+        None => {
+            let err_msg = "No parent directory found";
+            error!("{}", err_msg);
+            panic!("{}", err_msg);
+        }
+    };
+
+    // let file_name = match original_path.file_name() {
+    //     Some(name) => name.to_string_lossy(),
+    //     None => {
+    //         let err_msg = "No file name found";
+    //         error!("{}", err_msg);
+    //         panic!("{}", err_msg);
+    //     }
+    // };
+
+    let file_stem = match original_path.file_stem() {
+        Some(stem) => stem.to_string_lossy(),
+        None => {
+            let err_msg = "No file stem found";
+            error!("{}", err_msg);
+            panic!("{}", err_msg);
+        }
+    };
+
+    let extension = match original_path.extension() {
+        Some(ext) => ext.to_string_lossy(),
+        None => String::new().into(), // No extension
+    };
+
+    let backup_file_name = format!("{}_backup_{}.{}", file_stem, timestamp, extension);
+    let backup_path = parent_dir.join(backup_file_name);
+
+    // Attempt to copy the file
+    if let Err(e) = fs::copy(&original_path, &backup_path) {
+        error!("Failed to copy file from {:?} to {:?}: {}", original_path, backup_path, e);
+        panic!("Failed to copy file from {:?} to {:?}: {}", original_path, backup_path, e);
+    }
+
+    info!("Successfully backed up file from {:?} to {:?}", original_path, backup_path);
+    Some(backup_path)
+}
+